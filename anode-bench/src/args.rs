@@ -93,7 +93,7 @@ fn parse_one(names: &[&str], name: &str, value: &str) -> ArgRange {
             ArgRange {
                 current: start,
                 limit: end,
-                step: step,
+                step,
             }
         }
         _ => print_usage(
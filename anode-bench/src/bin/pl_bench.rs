@@ -9,6 +9,7 @@ use anode_bench::args::ArgRange;
 use anode_bench::{args, pl_harness};
 use anode_bench::pl_shims::{ArrivalOrderedLock, ParkingLotLock, ReadBiasedLock, StdLock, StochasticLock, WriteBiasedLock};
 
+#[allow(clippy::too_many_arguments)]
 fn run_all(
     args: &[ArgRange],
     first: &mut bool,
@@ -1,5 +1,5 @@
 use std::sync::{Mutex, RwLock};
-use anode::zlock::{ArrivalOrdered, LegacyReadBiased, LegacyWriteBiased, Stochastic};
+use anode::zlock::{ArrivalOrdered, LegacyReadBiased, LegacyWriteBiased, Sharded, Stochastic};
 use anode::zlock::ReadBiased;
 use anode::zlock::WriteBiased;
 use anode::zlock::ZLock;
@@ -34,7 +34,9 @@ fn main() {
                         run::<ZLock::<_, LegacyWriteBiased>>("anode::rwlock::ZLock<LegacyWriteBiased>", &opts);
                         run::<ZLock::<_, ArrivalOrdered>>("anode::rwlock::ZLock<ArrivalOrdered>", &opts);
                         run::<ZLock::<_, Stochastic>>("anode::rwlock::ZLock<Stochastic>", &opts);
+                        run::<ZLock::<_, Sharded>>("anode::rwlock::ZLock<Sharded>", &opts);
                         run::<SpinMutex<_>>("anode::spin_mutex::SpinMutex", &opts);
+                        run::<parking_lot::RwLock<_>>("parking_lot::RwLock", &opts);
                         run::<RwLock<_>>("std::sync::RwLock", &opts);
                         run::<Mutex<_>>("std::sync::Mutex", &opts);
                     }
@@ -49,7 +49,7 @@ pub fn run<E: Executor + 'static>(executor: E, opts: &Options, ext_opts: &Extend
         let submitter = executor.submitter();
         thread::spawn(move || {
             let mut iterations = 0u64;
-            while iterations % time_check_interval != 0 || running.load(Ordering::Relaxed) {
+            while !iterations.is_multiple_of(time_check_interval) || running.load(Ordering::Relaxed) {
                 let completed_tasks = completed_tasks.clone();
                 submitter.submit(move || {
                     completed_tasks.fetch_add(1, Ordering::Relaxed);
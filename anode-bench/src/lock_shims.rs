@@ -101,6 +101,59 @@ impl<'a, T: Sync + Send + 'a> LockSpec<'a> for RwLock<T> {
     }
 }
 
+impl<'a, T> ReadGuardSpec<'a, T> for parking_lot::RwLockReadGuard<'a, T> {}
+
+impl<'a, T> WriteGuardSpec<'a, T> for parking_lot::RwLockWriteGuard<'a, T> {}
+
+impl<'a, T: Sync + Send + 'a> LockSpec<'a> for parking_lot::RwLock<T> {
+    type T = T;
+    type R = parking_lot::RwLockReadGuard<'a, T>;
+    type W = parking_lot::RwLockWriteGuard<'a, T>;
+
+    fn new(t: Self::T) -> Self {
+        Self::new(t)
+    }
+
+    fn supports_read() -> bool {
+        true
+    }
+
+    fn supports_downgrade() -> bool {
+        true
+    }
+
+    fn supports_upgrade() -> bool {
+        false
+    }
+
+    #[inline]
+    fn try_read(&'a self, duration: Duration) -> Option<Self::R> {
+        if duration == Duration::MAX {
+            Some(self.read())
+        } else {
+            self.try_read_for(duration)
+        }
+    }
+
+    #[inline]
+    fn try_write(&'a self, duration: Duration) -> Option<Self::W> {
+        if duration == Duration::MAX {
+            Some(self.write())
+        } else {
+            self.try_write_for(duration)
+        }
+    }
+
+    #[inline]
+    fn downgrade(guard: Self::W) -> Self::R {
+        parking_lot::RwLockWriteGuard::downgrade(guard)
+    }
+
+    fn try_upgrade(_guard: Self::R, _duration: Duration) -> UpgradeOutcome<Self::W, Self::R> {
+        unimplemented!()
+    }
+}
+
 impl<'a, T> WriteGuardSpec<'a, T> for SpinGuard<'a, T> {}
 
 impl<'a, T: Sync + Send + 'a> LockSpec<'a> for SpinMutex<T> {
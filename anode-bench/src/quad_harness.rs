@@ -144,7 +144,7 @@ pub fn run<T: Addable, L: for<'a> LockSpec<'a, T = T> + 'static>(
                 start_barrier.wait();
                 let mut iterations = 0u64;
                 let mut last_val = 0;
-                while iterations % time_check_interval != 0 || running.load(Ordering::Relaxed) {
+                while !iterations.is_multiple_of(time_check_interval) || running.load(Ordering::Relaxed) {
                     {
                         let val = read_eventually(&*lock, ext_opts.read_timeout);
                         if ext_opts.debug_locks {
@@ -181,7 +181,7 @@ pub fn run<T: Addable, L: for<'a> LockSpec<'a, T = T> + 'static>(
             thread::spawn(move || {
                 start_barrier.wait();
                 let mut iterations = 0u64;
-                while iterations % time_check_interval != 0 || running.load(Ordering::Relaxed) {
+                while !iterations.is_multiple_of(time_check_interval) || running.load(Ordering::Relaxed) {
                     {
                         let mut val = write_eventually(&*lock, ext_opts.write_timeout);
                         if ext_opts.debug_locks {
@@ -215,7 +215,7 @@ pub fn run<T: Addable, L: for<'a> LockSpec<'a, T = T> + 'static>(
                 start_barrier.wait();
                 let mut iterations = 0u64;
                 let mut last_val = 0;
-                while iterations % time_check_interval != 0 || running.load(Ordering::Relaxed) {
+                while !iterations.is_multiple_of(time_check_interval) || running.load(Ordering::Relaxed) {
                     {
                         let mut val = write_eventually(&*lock, ext_opts.write_timeout);
                         if ext_opts.debug_locks {
@@ -262,7 +262,7 @@ pub fn run<T: Addable, L: for<'a> LockSpec<'a, T = T> + 'static>(
                 let mut iterations = 0u64;
                 let mut last_val = 0;
                 let mut missed_upgrades = 0;
-                while iterations % time_check_interval != 0 || running.load(Ordering::Relaxed) {
+                while !iterations.is_multiple_of(time_check_interval) || running.load(Ordering::Relaxed) {
                     {
                         let val = read_eventually(&*lock, ext_opts.read_timeout);
                         if ext_opts.debug_locks {
@@ -335,19 +335,19 @@ pub fn run<T: Addable, L: for<'a> LockSpec<'a, T = T> + 'static>(
         .into_iter()
         .map(JoinHandle::join)
         .map(Result::unwrap)
-        .fold(0, |acc, reads| acc + reads);
+        .sum::<u64>();
 
     let writer_iterations = writer_threads
         .into_iter()
         .map(JoinHandle::join)
         .map(Result::unwrap)
-        .fold(0, |acc, reads| acc + reads);
+        .sum::<u64>();
 
     let downgrader_iterations = downgrader_threads
         .into_iter()
         .map(JoinHandle::join)
         .map(Result::unwrap)
-        .fold(0, |acc, reads| acc + reads);
+        .sum::<u64>();
 
     let (upgrader_reads, upgrader_upgrades) = upgrader_threads
         .into_iter()
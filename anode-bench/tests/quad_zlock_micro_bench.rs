@@ -1,6 +1,6 @@
 use std::any;
 use std::time::Duration;
-use anode::zlock::{ArrivalOrdered, ReadBiased, Stochastic, WriteBiased, ZLock};
+use anode::zlock::{ArrivalOrdered, ReadBiased, Sharded, Stochastic, WriteBiased, ZLock};
 use anode_bench::lock_spec::LockSpec;
 use anode_bench::quad_harness;
 use anode_bench::quad_harness::{Addable, BoxedInt, ExtendedOptions, Options};
@@ -65,6 +65,26 @@ fn quad_micro_bench_stochastic_string() {
     __quad_micro_bench::<String, ZLock<_, Stochastic>>();
 }
 
+#[test]
+fn quad_micro_bench_sharded_int() {
+    __quad_micro_bench::<i64, ZLock<_, Sharded>>();
+}
+
+#[test]
+fn quad_micro_bench_sharded_boxed_int() {
+    __quad_micro_bench::<BoxedInt, ZLock<_, Sharded>>();
+}
+
+#[test]
+fn quad_micro_bench_sharded_string() {
+    __quad_micro_bench::<String, ZLock<_, Sharded>>();
+}
+
+#[test]
+fn quad_micro_bench_parking_lot_int() {
+    __quad_micro_bench::<i64, parking_lot::RwLock<_>>();
+}
+
 fn __quad_micro_bench<T: Addable, L: for<'a> LockSpec<'a, T = T> + 'static>() {
     let opts = Options {
         readers: 4,
@@ -9,16 +9,21 @@ pub struct NonzeroDuration(Duration);
 
 impl NonzeroDuration {
     #[inline(always)]
-    pub fn new(duration: Duration) -> Self {
-        assert!(duration > Duration::ZERO);
+    pub const fn new(duration: Duration) -> Self {
+        assert!(!duration.is_zero());
         Self(duration)
     }
 }
 
+impl NonzeroDuration {
+    /// The shortest nonzero duration: one nanosecond.
+    pub const MIN: Self = Self(Duration::new(0, 1));
+}
+
 impl Default for NonzeroDuration {
     #[inline(always)]
     fn default() -> Self {
-        Self(Duration::new(0, 1))
+        Self::MIN
     }
 }
 
@@ -36,7 +41,7 @@ impl From<NonzeroDuration> for Duration {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ExpBackoff {
     pub spin_iters: u64,
     pub yield_iters: u64,
@@ -45,30 +50,42 @@ pub struct ExpBackoff {
 }
 
 impl ExpBackoff {
-    pub fn spinny() -> Self {
+    /// The policy of [`spinny`](Self::spinny), as a constant that may be borrowed for
+    /// `'static`.
+    pub const SPINNY: Self = Self::spinny();
+
+    /// The policy of [`yieldy`](Self::yieldy), as a constant that may be borrowed for
+    /// `'static`.
+    pub const YIELDY: Self = Self::yieldy();
+
+    /// The policy of [`sleepy`](Self::sleepy), as a constant that may be borrowed for
+    /// `'static`.
+    pub const SLEEPY: Self = Self::sleepy();
+
+    pub const fn spinny() -> Self {
         Self {
             spin_iters: u64::MAX,
             yield_iters: 0,
-            min_sleep: NonzeroDuration::default(),
-            max_sleep: NonzeroDuration::default()
+            min_sleep: NonzeroDuration::MIN,
+            max_sleep: NonzeroDuration::MIN
         }
     }
 
-    pub fn yieldy() -> Self {
+    pub const fn yieldy() -> Self {
         Self {
             spin_iters: 0,
             yield_iters: u64::MAX,
-            min_sleep: NonzeroDuration::default(),
-            max_sleep: NonzeroDuration::default()
+            min_sleep: NonzeroDuration::MIN,
+            max_sleep: NonzeroDuration::MIN
         }
     }
 
-    pub fn sleepy() -> Self {
+    pub const fn sleepy() -> Self {
         Self {
             spin_iters: 0,
             yield_iters: 0,
-            min_sleep: Duration::from_micros(100).into(),
-            max_sleep: Duration::from_millis(10).into()
+            min_sleep: NonzeroDuration::new(Duration::from_micros(100)),
+            max_sleep: NonzeroDuration::new(Duration::from_millis(10))
         }
     }
 }
@@ -32,13 +32,13 @@ fn exp_backoff() {
     assert_eq!(ExpBackoffAction::Yield, it.next());
     assert_eq!(ExpBackoffAction::Yield, it.next());
     assert_eq!(ExpBackoffAction::Yield, it.next());
-    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(1).into()), it.next());
-    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(2).into()), it.next());
-    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(4).into()), it.next());
-    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(8).into()), it.next());
-    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(16).into()), it.next());
-    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(30).into()), it.next());
-    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(30).into()), it.next());
+    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(1)), it.next());
+    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(2)), it.next());
+    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(4)), it.next());
+    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(8)), it.next());
+    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(16)), it.next());
+    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(30)), it.next());
+    assert_eq!(ExpBackoffAction::Sleep(Duration::from_micros(30)), it.next());
 
     let mut it = eb.into_inf_iter();
     assert_eq!(ExpBackoffAction::Nop, it.next());
@@ -55,5 +55,5 @@ fn exp_backoff_act() {
     let mut thread_rng = thread_rng();
     ExpBackoffAction::Nop.act(|| &mut thread_rng);
     ExpBackoffAction::Yield.act(|| &mut thread_rng);
-    ExpBackoffAction::Sleep(Duration::from_micros(10).into()).act(|| &mut thread_rng);
+    ExpBackoffAction::Sleep(Duration::from_micros(10)).act(|| &mut thread_rng);
 }
\ No newline at end of file
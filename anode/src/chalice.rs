@@ -45,12 +45,20 @@ impl<T: ?Sized> DerefMut for MutGuard<'_, T> {
 }
 
 #[derive(Debug)]
-pub struct Poisoned<T: ?Sized>(T);
+pub struct Poisoned<T: ?Sized>(pub(crate) T);
 
 impl<T> Poisoned<T> {
     pub fn into_inner(self) -> T {
         self.0
     }
+
+    pub fn get_ref(&self) -> &T {
+        &self.0
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
 }
 
 impl<T> Chalice<T> {
@@ -84,8 +84,7 @@ impl<T> Completable<T> {
             } else {
                 Directive::Return
             }
-        });
-        f.is_none()
+        }).is_notified()
     }
 
     /// Completes this instance, assigning `val` if the instance is incomplete. Otherwise,
@@ -116,32 +115,38 @@ impl<T> Completable<T> {
     }
 
     #[inline]
-    pub fn get(&self) -> Completed<T> {
+    pub fn get(&self) -> Completed<'_, T> {
         Completed {
-            guard: self.__try_get(Duration::MAX),
+            guard: self.__try_get(Deadline::Forever),
         }
     }
 
     #[inline]
     pub fn peek<'a>(&'a self) -> impl Deref<Target = Option<T>> + 'a {
-        self.__try_get(Duration::ZERO)
+        self.__try_get(Deadline::Elapsed)
     }
 
     #[inline]
     pub fn try_get<'a>(&'a self, duration: Duration) -> impl Deref<Target = Option<T>> + 'a {
-        self.__try_get(duration)
+        self.__try_get(Deadline::lazy_after(duration))
+    }
+
+    /// A variant of [`try_get`](Self::try_get) that waits until the given deadline, which
+    /// may be shared with other timed operations.
+    #[inline]
+    pub fn try_get_until<'a>(&'a self, deadline: impl Into<Deadline>) -> impl Deref<Target = Option<T>> + 'a {
+        self.__try_get(deadline.into())
     }
 
     /// [`__try_get`] is never exposed directly to avoid coupling the caller to the
     /// [`SpeculativeMonitorGuard`] type, which might change in future implementations. Instead, the return
     /// value is publicly exposed as a [`Deref`] trait.
     #[inline]
-    fn __try_get(&self, duration: Duration) -> SpeculativeMonitorGuard<Option<T>> {
-        if !duration.is_zero() {
-            let mut deadline = Deadline::lazy_after(duration);
-            self.monitor.enter(|state| {
+    fn __try_get(&self, mut deadline: Deadline) -> SpeculativeMonitorGuard<'_, Option<T>> {
+        if !deadline.remaining().is_zero() {
+            self.monitor.enter_until(deadline, |state| {
                 if state.is_none() {
-                    Directive::Wait(deadline.remaining())
+                    Directive::Wait(Duration::MAX)
                 } else {
                     Directive::Return
                 }
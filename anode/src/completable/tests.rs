@@ -1,6 +1,8 @@
 use std::sync::{Arc, Barrier};
 use std::thread;
+use std::time::Instant;
 use crate::completable::{Completable};
+use crate::deadline::Deadline;
 use crate::test_utils::SHORT_WAIT;
 
 #[test]
@@ -22,6 +24,19 @@ fn complete_later() {
     assert_eq!(Some(42), comp.into_inner())
 }
 
+#[test]
+fn complete_by_deadline() {
+    let comp = Completable::default();
+    let mut deadline = Deadline::after(SHORT_WAIT);
+    assert_eq!(None, *comp.try_get_until(deadline.resolved()));
+    assert_eq!(None, *comp.try_get_until(Deadline::Elapsed));
+    assert_eq!(None, *comp.try_get_until(Instant::now()));
+
+    assert!(comp.complete(42).is_none());
+    assert_eq!(Some(42), *comp.try_get_until(deadline.resolved()));
+    assert_eq!(Some(42), *comp.try_get_until(Deadline::Forever));
+}
+
 #[test]
 fn complete_at_init() {
     let comp = Completable::new(42);
@@ -1,5 +1,11 @@
 use std::time::{Duration, Instant};
 
+/// A point in time by which an operation must complete, expressed either absolutely or as
+/// a duration that is resolved lazily, upon first use.
+///
+/// A deadline is not [`Copy`], as each copy of a lazy deadline would resolve independently,
+/// drifting from the others. To share a deadline across several timed operations, as in the
+/// `*_until` family of methods, hand each a copy taken with [`resolved`](Self::resolved).
 #[derive(Debug)]
 pub enum Deadline {
     Point(Instant),
@@ -21,6 +27,11 @@ impl Deadline {
         deadline
     }
 
+    #[inline(always)]
+    pub fn at(instant: Instant) -> Self {
+        Self::Point(instant)
+    }
+
     #[inline(always)]
     fn saturating_add(instant: Instant, duration: Duration) -> Self {
         match instant.checked_add(duration) {
@@ -42,6 +53,20 @@ impl Deadline {
         }
     }
 
+    /// Resolves a lazy deadline in place, returning a copy of the resolved deadline. Unlike
+    /// copies of a lazy deadline, the copies expire together.
+    #[inline(always)]
+    pub fn resolved(&mut self) -> Self {
+        self.ensure_initialized();
+
+        match self {
+            Deadline::Point(instant) => Deadline::Point(*instant),
+            Deadline::Forever => Deadline::Forever,
+            Deadline::Elapsed => Deadline::Elapsed,
+            _ => unreachable!(),
+        }
+    }
+
     #[inline(always)]
     pub fn remaining(&mut self) -> Duration {
         self.ensure_initialized();
@@ -54,3 +79,10 @@ impl Deadline {
         }
     }
 }
+
+impl From<Instant> for Deadline {
+    #[inline(always)]
+    fn from(instant: Instant) -> Self {
+        Self::at(instant)
+    }
+}
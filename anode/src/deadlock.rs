@@ -0,0 +1,389 @@
+//! Lock-order tracking for detecting potential deadlocks, in the spirit of Linux's _lockdep_.
+//!
+//! When the `deadlock_detection` feature is enabled, every [`ZLock`](crate::zlock::ZLock),
+//! [`SpinMutex`](crate::spin_mutex::SpinMutex) and
+//! [`SpeculativeMonitor`](crate::monitor::SpeculativeMonitor) records the locks that the
+//! acquiring thread already holds. Acquiring lock _B_ while holding lock _A_ adds the edge
+//! _A_ → _B_ to a process-wide lock-order graph. An edge that closes a cycle in the graph
+//! signifies a lock-order inversion: a deadlock that has not (necessarily) happened yet, but
+//! may happen under an unlucky interleaving of the threads involved.
+//!
+//! Each such cycle is reported once, and may be collected with `check_deadlock`. A report
+//! identifies every lock in the cycle, along with the stacks at which each pair of locks was
+//! acquired. Stacks are captured with
+//! [`Backtrace::capture`](std::backtrace::Backtrace::capture), and are therefore only populated
+//! when the `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variable is set.
+//!
+//! Acquisitions that cannot block -- `try_lock` and friends with a zero timeout -- do not
+//! contribute edges, although the locks so acquired are tracked as held. Re-acquiring a lock
+//! that is already held (e.g., a second read lock) is not treated as an inversion.
+//!
+//! With the feature disabled, tracking compiles down to nothing.
+
+#[cfg(feature = "deadlock_detection")]
+use std::backtrace::Backtrace;
+#[cfg(feature = "deadlock_detection")]
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "deadlock_detection")]
+use std::fmt;
+#[cfg(feature = "deadlock_detection")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "deadlock_detection")]
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+#[cfg(feature = "deadlock_detection")]
+use std::thread;
+#[cfg(feature = "deadlock_detection")]
+use std::thread::ThreadId;
+use std::time::Duration;
+#[cfg(feature = "deadlock_detection")]
+use crate::remedy::Remedy;
+
+/// The type of lock taking part in a lock-order cycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockKind {
+    ZLock,
+    SpinMutex,
+    SpeculativeMonitor,
+}
+
+/// The identity of a lock within the lock-order graph. Embedded in every tracked lock.
+#[cfg(not(feature = "deadlock_detection"))]
+pub(crate) struct LockId;
+
+#[cfg(not(feature = "deadlock_detection"))]
+impl LockId {
+    #[inline(always)]
+    pub(crate) const fn new(_kind: LockKind) -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    pub(crate) fn attempt<L: ?Sized>(&self, _lock: &L, _duration: Duration) {}
+
+    #[inline(always)]
+    pub(crate) fn acquired<L: ?Sized>(&self, _lock: &L) {}
+
+    #[inline(always)]
+    pub(crate) fn released(&self) {}
+}
+
+/// The identity of a lock within the lock-order graph. Embedded in every tracked lock.
+///
+/// Identifiers are assigned lazily, upon first acquisition, and are never reused. (Unlike
+/// addresses, which may be reused by a different lock once the original is dropped.)
+#[cfg(feature = "deadlock_detection")]
+pub(crate) struct LockId {
+    id: AtomicUsize,
+    kind: LockKind,
+}
+
+#[cfg(feature = "deadlock_detection")]
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[cfg(feature = "deadlock_detection")]
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(Default::default);
+
+#[cfg(feature = "deadlock_detection")]
+#[inline]
+fn registry() -> MutexGuard<'static, Registry> {
+    REGISTRY.lock().remedy()
+}
+
+#[cfg(feature = "deadlock_detection")]
+impl LockId {
+    #[inline]
+    pub(crate) const fn new(kind: LockKind) -> Self {
+        Self {
+            id: AtomicUsize::new(0),
+            kind,
+        }
+    }
+
+    fn get(&self) -> usize {
+        let id = self.id.load(Ordering::Relaxed);
+        if id != 0 {
+            return id;
+        }
+
+        let fresh = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        match self.id.compare_exchange(0, fresh, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => fresh,
+            Err(existing) => existing,
+        }
+    }
+
+    fn identity<L: ?Sized>(&self, lock: &L) -> LockIdentity {
+        LockIdentity {
+            id: self.get(),
+            kind: self.kind,
+            address: lock as *const L as *const () as usize,
+        }
+    }
+
+    /// Records the intent to acquire this lock, adding an edge from every lock held by
+    /// the current thread. Invoked before blocking, so that an inversion is reported even
+    /// if the acquisition never completes.
+    pub(crate) fn attempt<L: ?Sized>(&self, lock: &L, duration: Duration) {
+        if !duration.is_zero() {
+            let identity = self.identity(lock);
+            registry().attempt(identity);
+        }
+    }
+
+    pub(crate) fn acquired<L: ?Sized>(&self, lock: &L) {
+        let held = Held {
+            identity: self.identity(lock),
+            backtrace: Arc::new(Backtrace::capture()),
+        };
+        registry().held.entry(thread::current().id()).or_default().push(held);
+    }
+
+    pub(crate) fn released(&self) {
+        registry().release(self.get());
+    }
+}
+
+#[cfg(feature = "deadlock_detection")]
+impl Drop for LockId {
+    fn drop(&mut self) {
+        let id = *self.id.get_mut();
+        if id != 0 {
+            registry().forget(id);
+        }
+    }
+}
+
+#[cfg(feature = "deadlock_detection")]
+struct Held {
+    identity: LockIdentity,
+    backtrace: Arc<Backtrace>,
+}
+
+#[cfg(feature = "deadlock_detection")]
+#[derive(Default)]
+struct Registry {
+    /// The locks held by each thread, in the order of acquisition.
+    held: HashMap<ThreadId, Vec<Held>>,
+
+    /// The lock-order graph, as an adjacency map keyed by the ID of the lock held.
+    edges: HashMap<usize, HashMap<usize, Edge>>,
+
+    /// Reports yet to be collected by [`check_deadlock`].
+    reports: Vec<Report>,
+}
+
+#[cfg(feature = "deadlock_detection")]
+impl Registry {
+    fn attempt(&mut self, acquiring: LockIdentity) {
+        let thread = thread::current();
+        let Some(held) = self.held.get(&thread.id()) else {
+            return;
+        };
+
+        let fresh = held.iter()
+            .filter(|held| held.identity.id != acquiring.id)
+            .filter(|held| !self.edges.get(&held.identity.id).is_some_and(|to| to.contains_key(&acquiring.id)))
+            .map(|held| (held.identity, held.backtrace.clone()))
+            .collect::<Vec<_>>();
+        if fresh.is_empty() {
+            return;
+        }
+
+        let acquiring_at = Arc::new(Backtrace::capture());
+        for (held, held_at) in fresh {
+            let edge = Edge {
+                held,
+                acquiring,
+                thread: thread.name().map(String::from),
+                held_at,
+                acquiring_at: acquiring_at.clone(),
+            };
+
+            // a path from the lock being acquired back to the lock held closes a cycle
+            if let Some(mut edges) = self.path(acquiring.id, held.id) {
+                edges.push(edge.clone());
+                self.reports.push(Report { edges });
+            }
+            self.edges.entry(held.id).or_default().insert(acquiring.id, edge);
+        }
+    }
+
+    /// Finds the shortest path between two locks in the lock-order graph.
+    fn path(&self, from: usize, to: usize) -> Option<Vec<Edge>> {
+        let mut parents = HashMap::<usize, &Edge>::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(id) = queue.pop_front() {
+            for (&next, edge) in self.edges.get(&id).into_iter().flatten() {
+                if next == from || parents.contains_key(&next) {
+                    continue;
+                }
+                parents.insert(next, edge);
+                if next == to {
+                    let mut edges = Vec::new();
+                    let mut cursor = to;
+                    while cursor != from {
+                        let edge = parents[&cursor];
+                        edges.push(edge.clone());
+                        cursor = edge.held.id;
+                    }
+                    edges.reverse();
+                    return Some(edges);
+                }
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
+    /// Removes the most recent acquisition of the given lock, favouring the current thread.
+    /// (The lock may have been released by another thread, when the moderator permits.)
+    fn release(&mut self, id: usize) {
+        let current = thread::current().id();
+        let owner = if self.held.get(&current).is_some_and(|held| held.iter().any(|held| held.identity.id == id)) {
+            Some(current)
+        } else {
+            self.held.iter()
+                .find(|(_, held)| held.iter().any(|held| held.identity.id == id))
+                .map(|(&thread, _)| thread)
+        };
+
+        if let Some(owner) = owner {
+            let held = self.held.get_mut(&owner).unwrap();
+            let index = held.iter().rposition(|held| held.identity.id == id).unwrap();
+            held.remove(index);
+            if held.is_empty() {
+                self.held.remove(&owner);
+            }
+        }
+    }
+
+    /// Purges a dropped lock from the graph.
+    fn forget(&mut self, id: usize) {
+        self.edges.remove(&id);
+        for to in self.edges.values_mut() {
+            to.remove(&id);
+        }
+    }
+}
+
+/// Identifies a lock taking part in a lock-order cycle.
+#[cfg(feature = "deadlock_detection")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockIdentity {
+    id: usize,
+    kind: LockKind,
+    address: usize,
+}
+
+#[cfg(feature = "deadlock_detection")]
+impl LockIdentity {
+    /// A process-wide unique identifier of the lock.
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    #[inline]
+    pub fn kind(&self) -> LockKind {
+        self.kind
+    }
+
+    /// The address of the lock at the time of acquisition. For a [`SpeculativeMonitor`], this
+    /// is the address of the spin lock guarding its state.
+    ///
+    /// [`SpeculativeMonitor`]: crate::monitor::SpeculativeMonitor
+    #[inline]
+    pub fn address(&self) -> usize {
+        self.address
+    }
+}
+
+#[cfg(feature = "deadlock_detection")]
+impl fmt::Display for LockIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}#{} at {:#x}", self.kind, self.id, self.address)
+    }
+}
+
+/// An edge in the lock-order graph: a thread acquiring one lock while holding another.
+#[cfg(feature = "deadlock_detection")]
+#[derive(Clone, Debug)]
+pub struct Edge {
+    held: LockIdentity,
+    acquiring: LockIdentity,
+    thread: Option<String>,
+    held_at: Arc<Backtrace>,
+    acquiring_at: Arc<Backtrace>,
+}
+
+#[cfg(feature = "deadlock_detection")]
+impl Edge {
+    #[inline]
+    pub fn held(&self) -> LockIdentity {
+        self.held
+    }
+
+    #[inline]
+    pub fn acquiring(&self) -> LockIdentity {
+        self.acquiring
+    }
+
+    /// The name of the thread that acquired the locks, if it was named.
+    #[inline]
+    pub fn thread(&self) -> Option<&str> {
+        self.thread.as_deref()
+    }
+
+    /// The stack at which the held lock was acquired.
+    #[inline]
+    pub fn held_at(&self) -> &Backtrace {
+        &self.held_at
+    }
+
+    /// The stack at which the second lock was acquired.
+    #[inline]
+    pub fn acquiring_at(&self) -> &Backtrace {
+        &self.acquiring_at
+    }
+}
+
+/// A potential deadlock: a cycle in the lock-order graph.
+#[cfg(feature = "deadlock_detection")]
+#[derive(Clone, Debug)]
+pub struct Report {
+    edges: Vec<Edge>,
+}
+
+#[cfg(feature = "deadlock_detection")]
+impl Report {
+    /// The edges making up the cycle, such that each edge acquires the lock held by the next,
+    /// and the last edge acquires the lock held by the first.
+    #[inline]
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+}
+
+#[cfg(feature = "deadlock_detection")]
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "potential deadlock: lock-order cycle through {} locks", self.edges.len())?;
+        for edge in &self.edges {
+            writeln!(f, "thread '{}' acquired {} while holding {}",
+                     edge.thread().unwrap_or("<unnamed>"), edge.acquiring, edge.held)?;
+            writeln!(f, "{} was acquired at:\n{}", edge.held, edge.held_at)?;
+            writeln!(f, "{} was acquired at:\n{}", edge.acquiring, edge.acquiring_at)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the potential deadlocks observed since the last call. Typically invoked
+/// periodically from a background thread.
+#[cfg(feature = "deadlock_detection")]
+pub fn check_deadlock() -> Vec<Report> {
+    std::mem::take(&mut registry().reports)
+}
+
+#[cfg(all(test, feature = "deadlock_detection"))]
+mod tests;
@@ -0,0 +1,164 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use crate::deadlock::{check_deadlock, LockKind, Report};
+use crate::monitor::{Monitor, SpeculativeMonitor};
+use crate::remedy::Remedy;
+use crate::spin_mutex::SpinMutex;
+use crate::zlock::{ReadBiased, WriteBiased, ZLock};
+
+/// Serialises the tests in this module, as reports are collected process-wide.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn address<L>(lock: &L) -> usize {
+    lock as *const L as usize
+}
+
+/// Collects the reports whose cycles pass through all the given locks.
+fn reports_for(addresses: &[usize]) -> Vec<Report> {
+    check_deadlock().into_iter()
+        .filter(|report| {
+            addresses.iter().all(|address| report.edges().iter().any(|edge| edge.held().address() == *address))
+        })
+        .collect()
+}
+
+#[test]
+fn inversion_reported() {
+    let _serial = SERIAL.lock().remedy();
+    let a = Arc::new(ZLock::<_, ReadBiased>::new(0));
+    let b = Arc::new(ZLock::<_, ReadBiased>::new(0));
+
+    {
+        let _a = a.write();
+        let _b = b.read();
+    }
+    assert!(reports_for(&[address(&*a), address(&*b)]).is_empty());
+
+    thread::Builder::new().name("inverter".into()).spawn({
+        let (a, b) = (a.clone(), b.clone());
+        move || {
+            let _b = b.write();
+            let _a = a.read();
+        }
+    }).unwrap().join().unwrap();
+
+    let reports = reports_for(&[address(&*a), address(&*b)]);
+    assert_eq!(1, reports.len(), "{reports:?}");
+    let edges = reports[0].edges();
+    assert_eq!(edges[0].acquiring(), edges[1].held());
+    assert_eq!(edges[1].acquiring(), edges[0].held());
+    assert_eq!(LockKind::ZLock, edges[0].held().kind());
+    assert_eq!(address(&*a), edges[0].held().address());
+    assert_ne!(Some("inverter"), edges[0].thread());
+    assert_eq!(Some("inverter"), edges[1].thread());
+    assert_ne!(edges[0].held().id(), edges[1].held().id());
+
+    let display = reports[0].to_string();
+    assert!(display.contains("thread 'inverter' acquired ZLock#"), "{display}");
+
+    // the cycle is only reported once
+    {
+        let _b = b.write();
+        let _a = a.read();
+    }
+    assert!(reports_for(&[address(&*a), address(&*b)]).is_empty());
+}
+
+#[test]
+fn consistent_order_not_reported() {
+    let _serial = SERIAL.lock().remedy();
+    let a = ZLock::<_, WriteBiased>::new(0);
+    let b = SpinMutex::new(0);
+    let c = SpeculativeMonitor::new(0);
+
+    for _ in 0..2 {
+        let _a = a.read();
+        let _b = b.lock();
+        let _c = c.lock();
+    }
+    {
+        let _a = a.write();
+        let _c = c.lock();
+    }
+    assert!(reports_for(&[address(&a), address(&b)]).is_empty());
+    assert!(reports_for(&[address(&a)]).is_empty());
+}
+
+#[test]
+fn cycle_across_lock_kinds() {
+    let _serial = SERIAL.lock().remedy();
+    let a = ZLock::<_, WriteBiased>::new(0);
+    let b = SpinMutex::new(0);
+    let c = SpeculativeMonitor::new(0);
+
+    {
+        let _a = a.write();
+        let _b = b.lock();
+    }
+    {
+        let _b = b.lock();
+        c.alter(|_| {});
+    }
+    assert!(reports_for(&[address(&a), address(&b)]).is_empty());
+    {
+        let _c = c.lock();
+        let _a = a.read();
+    }
+
+    let reports = reports_for(&[address(&a), address(&b)]);
+    assert_eq!(1, reports.len(), "{reports:?}");
+    let kinds = reports[0].edges().iter().map(|edge| edge.held().kind()).collect::<Vec<_>>();
+    assert_eq!(vec![LockKind::ZLock, LockKind::SpinMutex, LockKind::SpeculativeMonitor], kinds);
+}
+
+#[test]
+fn non_blocking_acquisition_not_reported() {
+    let _serial = SERIAL.lock().remedy();
+    let a = ZLock::<_, ReadBiased>::new(0);
+    let b = ZLock::<_, ReadBiased>::new(0);
+
+    {
+        let _a = a.write();
+        let _b = b.try_write(Duration::ZERO).unwrap();
+    }
+    {
+        let _b = b.write();
+        let _a = a.try_read(Duration::ZERO).unwrap();
+    }
+    assert!(reports_for(&[address(&a), address(&b)]).is_empty());
+
+    // locks acquired without blocking are still tracked as held
+    {
+        let _b = b.try_write(Duration::ZERO).unwrap();
+        let _a = a.write();
+    }
+    assert!(reports_for(&[address(&a), address(&b)]).is_empty());
+    {
+        let _a = a.try_write(Duration::ZERO).unwrap();
+        let _b = b.write();
+    }
+    assert_eq!(1, reports_for(&[address(&a), address(&b)]).len());
+}
+
+#[test]
+fn released_lock_not_held() {
+    let _serial = SERIAL.lock().remedy();
+    let a = ZLock::<_, ReadBiased>::new((0, 0));
+    let b = ZLock::<_, ReadBiased>::new(0);
+
+    {
+        let a = ZLock::<_, ReadBiased>::new(0);
+        let _a = a.read();
+        let _b = b.write();
+    }
+
+    // mapped and upgradable guards release the lock as tracked
+    drop(crate::zlock::LockWriteGuard::map(a.write(), |(x, _)| x));
+    drop(a.read_upgradable().upgrade());
+    {
+        let _b = b.write();
+        let _a = a.read();
+    }
+    assert!(reports_for(&[address(&a), address(&b)]).is_empty());
+}
@@ -1,9 +1,12 @@
 use crate::completable::{Completable, Outcome};
+use std::future::Future;
+use std::pin::pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Sender, SyncSender, TrySendError};
 use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
 use std::thread;
-use std::thread::JoinHandle;
+use std::thread::{JoinHandle, Thread};
 use crate::remedy::Remedy;
 
 pub type SubmissionOutcome<G> = Arc<Completable<Outcome<G>>>;
@@ -92,7 +95,6 @@ impl ThreadPool {
         };
         let receiver = Arc::new(Mutex::new(receiver));
         let threads = (0..threads)
-            .into_iter()
             .map(|_| {
                 let receiver = receiver.clone();
                 thread::spawn(move || loop {
@@ -197,5 +199,42 @@ impl Executor for ThreadPool {
     }
 }
 
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Drives the given future to completion on the current thread, parking the thread
+/// whenever the future is pending.
+///
+/// This is a minimal executor, intended for bridging asynchronous APIs into blocking code
+/// (and for testing them) without pulling in a runtime.
+///
+/// # Examples
+/// ```
+/// use anode::executor::block_on;
+/// assert_eq!(42, block_on(async { 42 }));
+/// ```
+pub fn block_on<F: Future>(f: F) -> F::Output {
+    let mut f = pin!(f);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match f.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests;
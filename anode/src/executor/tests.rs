@@ -1,7 +1,11 @@
+use std::future::Future;
 use std::ops::RangeInclusive;
+use std::pin::Pin;
 use std::sync::{Arc, Barrier};
+use std::task::{Context, Poll};
+use std::thread;
 use crate::completable::Outcome;
-use crate::executor::{Executor, Queue, Submitter, ThreadPool};
+use crate::executor::{block_on, Executor, Queue, Submitter, ThreadPool};
 
 #[test]
 fn unbounded_execute_tasks_via_submit() {
@@ -213,4 +217,34 @@ fn bounded_queuing() {
     assert_eq!(Outcome::Success(()), *task_1.get());
     assert_eq!(Outcome::Success(()), *task_2.get());
     assert_eq!(Outcome::Success(()), *task_3.get());
-}
\ No newline at end of file
+}
+
+#[test]
+fn block_on_ready() {
+    assert_eq!(42, block_on(async { 42 }));
+}
+
+#[test]
+fn block_on_woken_from_another_thread() {
+    /// Pends on the first poll, arranging for another thread to wake it.
+    struct WakeLater {
+        polls: u32,
+    }
+
+    impl Future for WakeLater {
+        type Output = u32;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            self.polls += 1;
+            if self.polls == 1 {
+                let waker = cx.waker().clone();
+                thread::spawn(move || waker.wake());
+                Poll::Pending
+            } else {
+                Poll::Ready(self.polls)
+            }
+        }
+    }
+
+    assert!(block_on(WakeLater { polls: 0 }) >= 2);
+}
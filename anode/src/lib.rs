@@ -1,6 +1,7 @@
 pub mod backoff;
 pub mod chalice;
 pub mod completable;
+pub mod deadlock;
 pub mod deadline;
 pub mod executor;
 pub mod inf_iterator;
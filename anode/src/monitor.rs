@@ -1,9 +1,14 @@
-use std::fmt;
+use std::{fmt, mem};
+use std::cell::Cell;
 use std::ops::{Deref, DerefMut};
+use crate::backoff::ExpBackoff;
+use crate::deadline::Deadline;
+use crate::deadlock::LockKind;
 use crate::spin_mutex::{SpinGuard, SpinMutex};
 use crate::remedy;
 use crate::remedy::Remedy;
 use std::sync::{Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
 
 pub trait MonitorGuard<'a, S: ?Sized>: DerefMut<Target = S> {}
@@ -11,7 +16,79 @@ pub trait MonitorGuard<'a, S: ?Sized>: DerefMut<Target = S> {}
 pub trait Monitor<'a, S: ?Sized> {
     type Guard: MonitorGuard<'a, S>;
 
-    fn enter<F: FnMut(&mut S) -> Directive>(&self, f: F);
+    /// Enters the monitor, evaluating the closure and acting on the returned [`Directive`]
+    /// until the thread leaves the monitor. The closure may be evaluated more than once,
+    /// including after it has requested a notification, and must be written accordingly.
+    ///
+    /// Returns the manner in which the thread left the monitor.
+    fn enter<F: FnMut(&mut S) -> Directive>(&self, f: F) -> Exit;
+
+    /// A variant of [`enter`](Self::enter) whose closure produces a value alongside each
+    /// directive. Returns the value produced by the final evaluation of the closure,
+    /// together with the manner in which the thread left the monitor.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use anode::monitor::{Directive, Exit, Monitor, SpeculativeMonitor};
+    ///
+    /// let monitor = SpeculativeMonitor::new(vec![42]);
+    /// let popped = monitor.enter_with(|stack| match stack.pop() {
+    ///     Some(top) => (Some(top), Directive::Return),
+    ///     None => (None, Directive::Wait(Duration::ZERO)),
+    /// });
+    /// assert_eq!((Some(42), Exit::Returned), popped);
+    ///
+    /// let popped = monitor.enter_with(|stack| match stack.pop() {
+    ///     Some(top) => (Some(top), Directive::Return),
+    ///     None => (None, Directive::Wait(Duration::ZERO)),
+    /// });
+    /// assert_eq!((None, Exit::TimedOut), popped);
+    /// ```
+    #[inline(always)]
+    fn enter_with<T, F: FnMut(&mut S) -> (T, Directive)>(&self, mut f: F) -> (T, Exit) {
+        let mut value = None;
+        let exit = self.enter(|state| {
+            let (produced, directive) = f(state);
+            value = Some(produced);
+            directive
+        });
+        (value.unwrap(), exit)
+    }
+
+    /// The asynchronous counterpart of [`enter`](Self::enter), evaluating the closure once
+    /// per poll.
+    ///
+    /// A [`Directive::Wait`] with a nonzero duration registers the task's waker, which
+    /// will be woken by a subsequent notification of its queue, and yields
+    /// [`Poll::Pending`]. The duration is otherwise ignored, as there is no timer driving
+    /// the wait. The other wait directives do likewise, the notify-and-wait directives
+    /// having first issued their notification. All other directives complete the poll.
+    fn poll_enter<F: FnMut(&mut S) -> Directive>(&self, f: F, cx: &mut Context<'_>) -> Poll<()>;
+
+    /// Withdraws a waker registered by [`poll_enter`](Self::poll_enter) on behalf of a task
+    /// that has abandoned its wait, so that it does not linger until the next notification
+    /// of its queue. A withdrawn waker is woken, as it may also stand for other pending waits
+    /// of the same task, which must then register afresh.
+    fn forget(&self, waker: &Waker);
+
+    /// A variant of [`enter`](Self::enter) that bounds every wait by the given deadline, so
+    /// that the closure need not track the remaining time itself. Once the deadline elapses,
+    /// a wait directive returns from the monitor.
+    #[inline(always)]
+    fn enter_until<F: FnMut(&mut S) -> Directive>(&self, mut deadline: Deadline, mut f: F) -> Exit {
+        self.enter(|state| f(state).bounded(&mut deadline))
+    }
+
+    /// A variant of [`enter_with`](Self::enter_with) that bounds every wait by the given
+    /// deadline, as per [`enter_until`](Self::enter_until).
+    #[inline(always)]
+    fn enter_with_until<T, F: FnMut(&mut S) -> (T, Directive)>(&self, mut deadline: Deadline, mut f: F) -> (T, Exit) {
+        self.enter_with(|state| {
+            let (value, directive) = f(state);
+            (value, directive.bounded(&mut deadline))
+        })
+    }
 
     fn lock(&'a self) -> Self::Guard;
 
@@ -62,22 +139,283 @@ pub trait Monitor<'a, S: ?Sized> {
     }
 }
 
+/// The action taken by a thread upon an evaluation of the closure supplied to
+/// [`Monitor::enter`].
+///
+/// A monitor may have several condition queues, identified by their indices. The plain
+/// wait directives wait on the first queue, and the plain notify directives notify every
+/// queue, so that a monitor with a single queue need never name it.
 pub enum Directive {
+    /// Leaves the monitor.
     Return,
+
+    /// Waits for a notification, for at most the given duration, and reevaluates the
+    /// closure upon waking. The thread leaves the monitor if the wait elapses.
     Wait(Duration),
+
+    /// Notifies one waiting thread on each queue (and all waiting tasks) and leaves the
+    /// monitor. The closure may be reevaluated before the notification is issued.
     NotifyOne,
-    NotifyAll
+
+    /// Notifies all waiting threads and tasks and leaves the monitor. The closure may be
+    /// reevaluated before the notification is issued.
+    NotifyAll,
+
+    /// Notifies one waiting thread on each queue (and all waiting tasks) and leaves the
+    /// monitor, without reevaluating the closure.
+    NotifyOneAndReturn,
+
+    /// Notifies all waiting threads and tasks and leaves the monitor, without reevaluating
+    /// the closure.
+    NotifyAllAndReturn,
+
+    /// As per [`Wait`](Self::Wait), but first notifies one waiting thread on each queue
+    /// (and all waiting tasks), which cannot be the thread itself. A zero duration
+    /// notifies and leaves the monitor.
+    ///
+    /// The notification is issued upon every evaluation of the closure that returns this
+    /// directive, including the evaluation that precedes the thread's acquisition of the
+    /// monitor's mutex.
+    NotifyOneAndWait(Duration),
+
+    /// As per [`NotifyOneAndWait`](Self::NotifyOneAndWait), but notifies all waiting
+    /// threads.
+    NotifyAllAndWait(Duration),
+
+    /// As per [`Wait`](Self::Wait), but waits on the queue with the given index.
+    WaitOn(usize, Duration),
+
+    /// As per [`NotifyOne`](Self::NotifyOne), but issues the given notification.
+    Notify(Notification),
+
+    /// As per [`NotifyOneAndReturn`](Self::NotifyOneAndReturn), but issues the given
+    /// notification.
+    NotifyAndReturn(Notification),
+
+    /// As per [`NotifyOneAndWait`](Self::NotifyOneAndWait), but issues the given
+    /// notification and waits on the queue with the given index.
+    NotifyAndWaitOn(Notification, usize, Duration),
+}
+
+impl Directive {
+    /// Caps the duration of any wait by the given deadline.
+    #[inline(always)]
+    fn bounded(self, deadline: &mut Deadline) -> Self {
+        match self.action() {
+            Action::Wait(notification, queue, duration) => {
+                let duration = duration.min(deadline.remaining());
+                match notification {
+                    None => Directive::WaitOn(queue, duration),
+                    Some(notification) => Directive::NotifyAndWaitOn(notification, queue, duration),
+                }
+            }
+            _ => self,
+        }
+    }
+
+    /// Reduces the directive to the notification it issues and the action that follows.
+    #[inline(always)]
+    fn action(&self) -> Action {
+        match *self {
+            Directive::Return => Action::Return,
+            Directive::Wait(duration) => Action::Wait(None, 0, duration),
+            Directive::NotifyOne => Action::Notify(Notification::ONE, true),
+            Directive::NotifyAll => Action::Notify(Notification::ALL, true),
+            Directive::NotifyOneAndReturn => Action::Notify(Notification::ONE, false),
+            Directive::NotifyAllAndReturn => Action::Notify(Notification::ALL, false),
+            Directive::NotifyOneAndWait(duration) => Action::Wait(Some(Notification::ONE), 0, duration),
+            Directive::NotifyAllAndWait(duration) => Action::Wait(Some(Notification::ALL), 0, duration),
+            Directive::WaitOn(queue, duration) => Action::Wait(None, queue, duration),
+            Directive::Notify(notification) => Action::Notify(notification, true),
+            Directive::NotifyAndReturn(notification) => Action::Notify(notification, false),
+            Directive::NotifyAndWaitOn(notification, queue, duration) => Action::Wait(Some(notification), queue, duration),
+        }
+    }
+}
+
+/// The essence of a [`Directive`].
+enum Action {
+    Return,
+
+    /// Waits on the given queue, having first issued the notification, if any.
+    Wait(Option<Notification>, usize, Duration),
+
+    /// Issues the notification and leaves the monitor. The flag permits the closure to be
+    /// reevaluated first.
+    Notify(Notification, bool),
+}
+
+/// The threads notified by a [`Directive`]: either one or all of the threads waiting on
+/// each of a set of condition queues. The tasks waiting on the chosen queues are always
+/// woken en masse.
+///
+/// # Examples
+/// ```
+/// use anode::monitor::Notification;
+///
+/// const READERS: usize = 0;
+/// const WRITERS: usize = 1;
+///
+/// // all waiting readers, but only one of the waiting writers
+/// let notification = Notification::all(READERS).and_one(WRITERS);
+/// assert_ne!(Notification::all(READERS), notification);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Notification {
+    one: u32,
+    all: u32,
+}
+
+impl Notification {
+    /// One waiting thread on each queue.
+    pub const ONE: Self = Self { one: u32::MAX, all: 0 };
+
+    /// All waiting threads on every queue.
+    pub const ALL: Self = Self { one: 0, all: u32::MAX };
+
+    /// One waiting thread on the given queue.
+    #[inline]
+    pub const fn one(queue: usize) -> Self {
+        Self { one: 0, all: 0 }.and_one(queue)
+    }
+
+    /// All waiting threads on the given queue.
+    #[inline]
+    pub const fn all(queue: usize) -> Self {
+        Self { one: 0, all: 0 }.and_all(queue)
+    }
+
+    /// Additionally notifies one waiting thread on the given queue.
+    #[inline]
+    pub const fn and_one(self, queue: usize) -> Self {
+        Self { one: self.one | Self::bit(queue), ..self }
+    }
+
+    /// Additionally notifies all waiting threads on the given queue.
+    #[inline]
+    pub const fn and_all(self, queue: usize) -> Self {
+        Self { all: self.all | Self::bit(queue), ..self }
+    }
+
+    #[inline]
+    const fn bit(queue: usize) -> u32 {
+        assert!(queue < MAX_QUEUES, "queue index out of range");
+        1 << queue
+    }
+
+    /// Whether the queue is notified, and if so, whether all of its threads are.
+    #[inline(always)]
+    fn of(self, queue: usize) -> Option<bool> {
+        let bit = Self::bit(queue);
+        if self.all & bit != 0 {
+            Some(true)
+        } else if self.one & bit != 0 {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// The largest number of condition queues a monitor may have.
+pub const MAX_QUEUES: usize = u32::BITS as usize;
+
+/// The manner in which a thread left a monitor, as reported by [`Monitor::enter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exit {
+    /// The closure returned [`Directive::Return`].
+    Returned,
+
+    /// The closure returned one of the notify directives that leave the monitor, and the
+    /// waiting threads (if any) were notified.
+    Notified,
+
+    /// The closure returned one of the wait directives, and the wait elapsed without the
+    /// thread being woken. A wait of zero duration elapses immediately.
+    TimedOut,
 }
 
-struct Tracker<S: ?Sized> {
-    waiting: u32,
+impl Exit {
+    #[inline]
+    pub fn is_returned(&self) -> bool {
+        matches!(self, Exit::Returned)
+    }
+
+    #[inline]
+    pub fn is_notified(&self) -> bool {
+        matches!(self, Exit::Notified)
+    }
+
+    #[inline]
+    pub fn is_timed_out(&self) -> bool {
+        matches!(self, Exit::TimedOut)
+    }
+}
+
+struct Tracker<S: ?Sized, const Q: usize> {
+    waiting: [u32; Q],
+    wakers: [Vec<Waker>; Q],
     data: S,
 }
 
-pub struct SpeculativeMonitor<S: ?Sized> {
+impl<S: ?Sized, const Q: usize> Tracker<S, Q> {
+    /// Takes the wakers of the tasks owed the notification, and determines which of the
+    /// condition variables have waiting threads to notify.
+    #[inline(always)]
+    fn take(&mut self, notification: Notification) -> Pending {
+        let mut pending = Pending { wakers: Vec::new(), one: 0, all: 0 };
+        for queue in 0..Q {
+            if let Some(all) = notification.of(queue) {
+                if pending.wakers.is_empty() {
+                    pending.wakers = mem::take(&mut self.wakers[queue]);
+                } else {
+                    pending.wakers.append(&mut self.wakers[queue]);
+                }
+                if self.waiting[queue] > 0 {
+                    if all {
+                        pending.all |= 1 << queue;
+                    } else {
+                        pending.one |= 1 << queue;
+                    }
+                }
+            }
+        }
+        pending
+    }
+}
+
+/// A notification captured under the tracker's lock, comprising the tasks to wake and the
+/// condition variables (as bitmasks of queues) to notify.
+struct Pending {
+    wakers: Vec<Waker>,
+    one: u32,
+    all: u32,
+}
+
+impl Pending {
+    #[inline(always)]
+    fn has_threads(&self) -> bool {
+        self.one | self.all != 0
+    }
+}
+
+thread_local! {
+    static WAITS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// The number of times the current thread has blocked on a condition variable of a
+/// [`SpeculativeMonitor`]. Comparing the count before and after entering a monitor tells
+/// whether the thread had to wait.
+#[inline]
+pub(crate) fn waits() -> u64 {
+    WAITS.with(Cell::get)
+}
+
+/// A monitor with `Q` condition queues, each served by its own condition variable.
+pub struct SpeculativeMonitor<S: ?Sized, const Q: usize = 1> {
     mutex: Mutex<()>,
-    cond: Condvar,
-    tracker: SpinMutex<Tracker<S>>,
+    conds: [Condvar; Q],
+    tracker: SpinMutex<Tracker<S, Q>>,
 }
 
 impl<S: Default> Default for SpeculativeMonitor<S> {
@@ -88,14 +426,47 @@ impl<S: Default> Default for SpeculativeMonitor<S> {
 
 impl<S> SpeculativeMonitor<S> {
     #[inline(always)]
-    pub fn new(s: S) -> Self {
+    pub const fn new(s: S) -> Self {
+        Self::with_backoff(s, &ExpBackoff::SLEEPY)
+    }
+
+    /// Creates a monitor whose tracker backs off according to the given policy while it
+    /// is contended.
+    #[inline(always)]
+    pub const fn with_backoff(s: S, backoff: &'static ExpBackoff) -> Self {
+        Self::with_queues(s, backoff)
+    }
+}
+
+impl<S, const Q: usize> SpeculativeMonitor<S, Q> {
+    /// Creates a monitor with `Q` condition queues, whose tracker backs off according to
+    /// the given policy.
+    ///
+    /// # Panics
+    /// If `Q` is zero or exceeds [`MAX_QUEUES`].
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use anode::backoff::ExpBackoff;
+    /// use anode::monitor::{Directive, Exit, Monitor, Notification, SpeculativeMonitor};
+    ///
+    /// let monitor = SpeculativeMonitor::<_, 2>::with_queues(0, &ExpBackoff::SLEEPY);
+    /// let exit = monitor.enter(|_| Directive::WaitOn(1, Duration::ZERO));
+    /// assert_eq!(Exit::TimedOut, exit);
+    /// assert_eq!(Exit::Notified, monitor.enter(|_| Directive::Notify(Notification::all(1))));
+    /// ```
+    #[inline(always)]
+    pub const fn with_queues(s: S, backoff: &'static ExpBackoff) -> Self {
+        assert!(Q > 0 && Q <= MAX_QUEUES, "unsupported number of queues");
         Self {
-            tracker: SpinMutex::new(Tracker {
+            tracker: SpinMutex::with_kind(Tracker {
                 data: s,
-                waiting: 0,
-            }),
+                waiting: [0; Q],
+                wakers: [const { Vec::new() }; Q],
+            }, LockKind::SpeculativeMonitor, backoff),
             mutex: Mutex::new(()),
-            cond: Default::default(),
+            conds: [const { Condvar::new() }; Q],
         }
     }
 
@@ -104,65 +475,118 @@ impl<S> SpeculativeMonitor<S> {
     }
 }
 
-impl<S: ?Sized> SpeculativeMonitor<S> {
+impl<S: ?Sized, const Q: usize> SpeculativeMonitor<S, Q> {
+    /// The number of threads waiting on any of the queues.
     pub fn num_waiting(&self) -> u32 {
-        self.tracker.lock().waiting
+        self.tracker.lock().waiting.iter().sum()
+    }
+
+    /// The number of threads waiting on the queue with the given index.
+    pub fn num_waiting_on(&self, queue: usize) -> u32 {
+        self.tracker.lock().waiting[queue]
+    }
+
+    /// Wakes the tasks owed the notification, as well as the notified threads blocked on
+    /// the condition variables. Tasks are always woken en masse, so that a task that is
+    /// woken and subsequently dropped cannot absorb a notification meant for another.
+    #[inline(always)]
+    fn notify(&self, mut spin_guard: SpinGuard<'_, Tracker<S, Q>>, notification: Notification) {
+        let pending = spin_guard.take(notification);
+        drop(spin_guard);
+        if pending.has_threads() {
+            let mutex_guard = self.mutex.lock().remedy();
+            self.notify_held(pending);
+            drop(mutex_guard);
+        } else {
+            self.notify_held(pending);
+        }
+    }
+
+    /// Wakes the pending tasks and notifies the pending threads. The caller holds the mutex
+    /// if there are threads to notify, so that every thread counted among the waiting is
+    /// already blocked.
+    #[inline(always)]
+    fn notify_held(&self, pending: Pending) {
+        pending.wakers.into_iter().for_each(Waker::wake);
+        for (queue, cond) in self.conds.iter().enumerate() {
+            let bit = 1 << queue;
+            if pending.all & bit != 0 {
+                cond.notify_all();
+            } else if pending.one & bit != 0 {
+                cond.notify_one();
+            }
+        }
     }
 }
 
-impl<'a, S: 'a> Monitor<'a, S> for SpeculativeMonitor<S> {
-    type Guard = SpeculativeMonitorGuard<'a, S>;
+impl<'a, S: 'a, const Q: usize> Monitor<'a, S> for SpeculativeMonitor<S, Q> {
+    type Guard = SpeculativeMonitorGuard<'a, S, Q>;
 
     #[inline(always)]
-    fn enter<F: FnMut(&mut S) -> Directive>(&self, mut f: F) {
+    fn enter<F: FnMut(&mut S) -> Directive>(&self, mut f: F) -> Exit {
         let mut mutex_guard = None;
-        let mut woken = false;
+        let mut woken = None;
         loop {
             let mut spin_guard = self.tracker.lock();
-            if woken {
-                woken = false;
-                spin_guard.waiting -= 1;
+            if let Some(queue) = woken.take() {
+                spin_guard.waiting[queue] -= 1;
             }
             let data = &mut spin_guard.data;
             let directive = f(data);
-            match directive {
-                Directive::Return => {
-                    return
+            match directive.action() {
+                Action::Return => {
+                    return Exit::Returned
                 }
-                Directive::Wait(duration) => {
+                Action::Wait(notification, queue, duration) => {
                     if duration.is_zero() {
-                        return
+                        if let Some(notification) = notification {
+                            drop(mutex_guard);
+                            self.notify(spin_guard, notification);
+                        }
+                        return Exit::TimedOut
                     } else {
+                        // the others are notified before the thread counts itself among the
+                        // waiting, so that it does not notify itself
+                        let pending = notification.map(|notification| spin_guard.take(notification));
                         match mutex_guard.take() {
                             None => {
                                 // println!("init lock");
                                 drop(spin_guard);
                                 mutex_guard = Some(self.mutex.lock().remedy());
+                                if let Some(pending) = pending {
+                                    self.notify_held(pending);
+                                }
                             }
                             Some(guard) => {
-                                spin_guard.waiting += 1;
+                                spin_guard.waiting[queue] += 1;
                                 drop(spin_guard);
+                                if let Some(pending) = pending {
+                                    self.notify_held(pending);
+                                }
 
+                                WAITS.with(|waits| waits.set(waits.get() + 1));
                                 let (guard, timed_out) =
-                                    remedy::cond_wait_remedy(&self.cond, guard, duration);
+                                    remedy::cond_wait_remedy(&self.conds[queue], guard, duration);
 
                                 if timed_out {
                                     // println!("timed out");
                                     let mut spin_guard = self.tracker.lock();
-                                    spin_guard.waiting -= 1;
-                                    return
+                                    spin_guard.waiting[queue] -= 1;
+                                    return Exit::TimedOut
                                 } else {
                                     // println!("keep going");
                                     mutex_guard = Some(guard);
-                                    woken = true;
+                                    woken = Some(queue);
                                 }
                             }
                         }
                     }
                 }
-                Directive::NotifyOne | Directive::NotifyAll => {
-                    if spin_guard.waiting > 0 {
-                        drop(spin_guard);
+                Action::Notify(notification, true) => {
+                    let mut pending = spin_guard.take(notification);
+                    drop(spin_guard);
+                    mem::take(&mut pending.wakers).into_iter().for_each(Waker::wake);
+                    if pending.has_threads() {
                         match mutex_guard.take() {
                             None => {
                                 // println!("init lock");
@@ -170,35 +594,89 @@ impl<'a, S: 'a> Monitor<'a, S> for SpeculativeMonitor<S> {
                             }
                             Some(guard) => {
                                 drop(guard);
-                                match directive {
-                                    Directive::NotifyOne => {
-                                        self.cond.notify_one();
-                                    }
-                                    Directive::NotifyAll => {
-                                        self.cond.notify_all();
-                                    }
-                                    _ => unreachable!()
-                                }
-                                return
+                                self.notify_held(pending);
+                                return Exit::Notified
                             }
                         }
                     } else {
-                        return
+                        return Exit::Notified
                     }
                 }
+                Action::Notify(notification, false) => {
+                    drop(mutex_guard);
+                    self.notify(spin_guard, notification);
+                    return Exit::Notified
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn poll_enter<F: FnMut(&mut S) -> Directive>(&self, mut f: F, cx: &mut Context<'_>) -> Poll<()> {
+        let mut spin_guard = self.tracker.lock();
+        let directive = f(&mut spin_guard.data);
+        match directive.action() {
+            Action::Return => Poll::Ready(()),
+            Action::Wait(notification, queue, duration) => {
+                // the task's waker is registered after the others are taken, so that the
+                // task does not wake itself
+                let pending = notification.map(|notification| spin_guard.take(notification));
+                if !duration.is_zero() {
+                    let wakers = &mut spin_guard.wakers[queue];
+                    if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                        wakers.push(cx.waker().clone());
+                    }
+                }
+                drop(spin_guard);
+                if let Some(pending) = pending {
+                    if pending.has_threads() {
+                        let mutex_guard = self.mutex.lock().remedy();
+                        self.notify_held(pending);
+                        drop(mutex_guard);
+                    } else {
+                        self.notify_held(pending);
+                    }
+                }
+
+                if duration.is_zero() {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            }
+            Action::Notify(notification, _) => {
+                self.notify(spin_guard, notification);
+                Poll::Ready(())
             }
         }
     }
 
+    #[inline]
+    fn forget(&self, waker: &Waker) {
+        let mut spin_guard = self.tracker.lock();
+        let mut registered = false;
+        for wakers in &mut spin_guard.wakers {
+            wakers.retain(|other| {
+                let same = other.will_wake(waker);
+                registered |= same;
+                !same
+            });
+        }
+        drop(spin_guard);
+        if registered {
+            waker.wake_by_ref();
+        }
+    }
+
     #[inline(always)]
-    fn lock(&self) -> SpeculativeMonitorGuard<S> {
+    fn lock(&self) -> SpeculativeMonitorGuard<'_, S, Q> {
         SpeculativeMonitorGuard {
             spin_guard: self.tracker.lock()
         }
     }
 }
 
-impl<T: ?Sized + fmt::Debug> fmt::Debug for SpeculativeMonitor<T> {
+impl<T: ?Sized + fmt::Debug, const Q: usize> fmt::Debug for SpeculativeMonitor<T, Q> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut d = f.debug_struct("SpeculativeMonitor");
         match self.tracker.try_lock() {
@@ -212,18 +690,18 @@ impl<T: ?Sized + fmt::Debug> fmt::Debug for SpeculativeMonitor<T> {
                 d.field("data", &LockedPlaceholder);
             }
             Some(guard) => {
-                d.field("data", &&(*guard).data);
+                d.field("data", &&guard.data);
             }
         }
         d.finish_non_exhaustive()
     }
 }
 
-pub struct SpeculativeMonitorGuard<'a, S: ?Sized> {
-    spin_guard: SpinGuard<'a, Tracker<S>>
+pub struct SpeculativeMonitorGuard<'a, S: ?Sized, const Q: usize = 1> {
+    spin_guard: SpinGuard<'a, Tracker<S, Q>>
 }
 
-impl<'a, S> Deref for SpeculativeMonitorGuard<'a, S> {
+impl<'a, S, const Q: usize> Deref for SpeculativeMonitorGuard<'a, S, Q> {
     type Target = S;
 
     #[inline(always)]
@@ -232,14 +710,14 @@ impl<'a, S> Deref for SpeculativeMonitorGuard<'a, S> {
     }
 }
 
-impl<'a, S> DerefMut for SpeculativeMonitorGuard<'a, S> {
+impl<'a, S, const Q: usize> DerefMut for SpeculativeMonitorGuard<'a, S, Q> {
     #[inline(always)]
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.spin_guard.data
     }
 }
 
-impl<'a, S> MonitorGuard<'a, S> for SpeculativeMonitorGuard<'a, S> {}
+impl<'a, S, const Q: usize> MonitorGuard<'a, S> for SpeculativeMonitorGuard<'a, S, Q> {}
 
 #[cfg(test)]
 mod tests;
\ No newline at end of file
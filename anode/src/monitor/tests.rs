@@ -1,9 +1,14 @@
 use std::cmp::Ordering;
 use std::sync::{Arc, Barrier};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicU32};
+use std::task::{Context, Poll, Wake, Waker};
 use std::time::Duration;
+use crate::backoff::ExpBackoff;
+use crate::deadline::Deadline;
 use crate::monitor::Monitor;
 use crate::monitor::Directive;
+use crate::monitor::Exit;
+use crate::monitor::{Notification, MAX_QUEUES};
 use crate::monitor::SpeculativeMonitor;
 use crate::{test_utils, wait};
 use crate::test_utils::{LONG_WAIT, SHORT_WAIT};
@@ -13,13 +18,14 @@ use crate::wait::{Wait, WaitResult};
 fn return_immediately() {
     let monitor = SpeculativeMonitor::new(0);
     let mut invocations = 0;
-    monitor.enter(|val| {
+    let exit = monitor.enter(|val| {
         assert_eq!(0, *val);
         *val = 42;
         invocations += 1;
         Directive::Return
     });
     assert_eq!(1, invocations);
+    assert_eq!(Exit::Returned, exit);
 
     let guard = monitor.lock();
     assert_eq!(42, *guard);
@@ -61,22 +67,257 @@ fn wait_for_nothing() {
     monitor.wait_for_num_waiting(Ordering::is_eq, 0, LONG_WAIT).unwrap();
 }
 
+#[test]
+fn wait_until_deadline() {
+    let monitor = SpeculativeMonitor::new(());
+    let mut invocations = 0;
+    let exit = monitor.enter_until(Deadline::Elapsed, |_| {
+        invocations += 1;
+        Directive::Wait(Duration::MAX)
+    });
+    // an elapsed deadline caps the wait at zero
+    assert_eq!(1, invocations);
+    assert!(exit.is_timed_out());
+
+    let mut deadline = Deadline::after(SHORT_WAIT);
+    assert!(monitor.enter_until(deadline.resolved(), |_| Directive::Wait(Duration::MAX)).is_timed_out());
+    assert!(deadline.remaining().is_zero());
+    monitor.wait_for_num_waiting(Ordering::is_eq, 0, LONG_WAIT).unwrap();
+}
+
 #[test]
 fn notify_nothing() {
     let monitor = SpeculativeMonitor::new(());
     let mut invocations = 0;
-    monitor.enter(|_| {
+    let exit = monitor.enter(|_| {
         invocations += 1;
         Directive::NotifyOne
     });
     assert_eq!(1, invocations);
+    assert_eq!(Exit::Notified, exit);
 
     let mut invocations = 0;
-    monitor.enter(|_| {
+    let exit = monitor.enter(|_| {
         invocations += 1;
         Directive::NotifyAll
     });
     assert_eq!(1, invocations);
+    assert_eq!(Exit::Notified, exit);
+}
+
+#[test]
+fn enter_with_final_value() {
+    let monitor = Arc::new(SpeculativeMonitor::new(None));
+    let t_2 = {
+        let monitor = monitor.clone();
+        test_utils::spawn_blocked(move || {
+            let mut invocations = 0;
+            let taken = monitor.enter_with(|val: &mut Option<u64>| {
+                invocations += 1;
+                match val.take() {
+                    None => (invocations, Directive::Wait(Duration::MAX)),
+                    Some(val) => (val, Directive::Return),
+                }
+            });
+            (taken, invocations)
+        })
+    };
+
+    monitor.wait_for_num_waiting(Ordering::is_eq, 1, LONG_WAIT).unwrap();
+    monitor.enter(|val| {
+        *val = Some(42);
+        Directive::NotifyOne
+    });
+    let (taken, invocations) = t_2.join().unwrap();
+    assert_eq!((42, Exit::Returned), taken);
+    assert!(invocations >= 2);
+}
+
+#[test]
+fn enter_with_until_timed_out() {
+    let monitor = SpeculativeMonitor::new(0);
+    let mut invocations = 0;
+    let produced = monitor.enter_with_until(Deadline::after(SHORT_WAIT), |_| {
+        invocations += 1;
+        (invocations, Directive::Wait(Duration::MAX))
+    });
+    // the value is that of the final evaluation
+    assert_eq!((invocations, Exit::TimedOut), produced);
+    monitor.wait_for_num_waiting(Ordering::is_eq, 0, LONG_WAIT).unwrap();
+}
+
+#[test]
+fn notify_and_return() {
+    let monitor = Arc::new(SpeculativeMonitor::new(false));
+    let t_2 = {
+        let monitor = monitor.clone();
+        test_utils::spawn_blocked(move || {
+            monitor.enter(|flag| {
+                if *flag {
+                    Directive::Return
+                } else {
+                    Directive::Wait(Duration::MAX)
+                }
+            })
+        })
+    };
+    monitor.wait_for_num_waiting(Ordering::is_eq, 1, LONG_WAIT).unwrap();
+
+    // the closure is evaluated exactly once, despite there being a waiter to notify
+    let mut invocations = 0;
+    let exit = monitor.enter(|flag| {
+        invocations += 1;
+        *flag = true;
+        Directive::NotifyAllAndReturn
+    });
+    assert_eq!(1, invocations);
+    assert_eq!(Exit::Notified, exit);
+    assert_eq!(Exit::Returned, t_2.join().unwrap());
+}
+
+#[test]
+fn notify_and_wait_zero() {
+    let monitor = SpeculativeMonitor::new(());
+    let mut invocations = 0;
+    let exit = monitor.enter(|_| {
+        invocations += 1;
+        Directive::NotifyOneAndWait(Duration::ZERO)
+    });
+    assert_eq!(1, invocations);
+    assert_eq!(Exit::TimedOut, exit);
+
+    // an elapsed deadline caps the wait at zero
+    let exit = monitor.enter_until(Deadline::Elapsed, |_| Directive::NotifyAllAndWait(Duration::MAX));
+    assert_eq!(Exit::TimedOut, exit);
+    assert_eq!(0, monitor.num_waiting());
+}
+
+/// Two threads take turns, each handing the turn to the other and waiting in a single
+/// directive. A lost notification would stall the exchange until the wait elapses.
+#[test]
+fn notify_and_wait_turns() {
+    const ROUNDS: u32 = 100;
+
+    struct Turns {
+        turn: usize,
+        rounds: u32,
+    }
+
+    let monitor = Arc::new(SpeculativeMonitor::new(Turns { turn: 0, rounds: 0 }));
+    let threads = (0..2)
+        .map(|id| {
+            let monitor = monitor.clone();
+            test_utils::spawn_blocked(move || {
+                monitor.enter(|turns| {
+                    if turns.rounds == ROUNDS {
+                        Directive::NotifyAllAndReturn
+                    } else if turns.turn == id {
+                        turns.turn = 1 - id;
+                        turns.rounds += 1;
+                        Directive::NotifyOneAndWait(LONG_WAIT)
+                    } else {
+                        Directive::Wait(LONG_WAIT)
+                    }
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        assert_eq!(Exit::Notified, thread.join().unwrap());
+    }
+    assert_eq!(ROUNDS, monitor.compute(|turns| turns.rounds));
+    assert_eq!(0, monitor.num_waiting());
+}
+
+#[test]
+fn notification_queues() {
+    let notification = Notification::all(0).and_one(2);
+    assert_eq!(Some(true), notification.of(0));
+    assert_eq!(None, notification.of(1));
+    assert_eq!(Some(false), notification.of(2));
+
+    // notifying all the threads of a queue subsumes notifying one
+    assert_eq!(Some(true), Notification::one(1).and_all(1).of(1));
+    assert_eq!(Some(false), Notification::ONE.of(MAX_QUEUES - 1));
+    assert_eq!(Some(true), Notification::ALL.of(MAX_QUEUES - 1));
+}
+
+/// Threads waiting on one queue are not woken by the notification of another, and the
+/// plain directives act on the first queue.
+#[test]
+fn wait_on_queues() {
+    let monitor = Arc::new(SpeculativeMonitor::<_, 2>::with_queues([false; 2], &ExpBackoff::SLEEPY));
+    let threads = (0..2)
+        .map(|queue| {
+            let monitor = monitor.clone();
+            test_utils::spawn_blocked(move || {
+                monitor.enter(|flags| {
+                    if flags[queue] {
+                        Directive::Return
+                    } else if queue == 0 {
+                        Directive::Wait(Duration::MAX)
+                    } else {
+                        Directive::WaitOn(queue, Duration::MAX)
+                    }
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    monitor.wait_for_num_waiting_on(0, Ordering::is_eq, 1, LONG_WAIT).unwrap();
+    monitor.wait_for_num_waiting_on(1, Ordering::is_eq, 1, LONG_WAIT).unwrap();
+    assert_eq!(2, monitor.num_waiting());
+
+    let mut threads = threads.into_iter();
+    let (t_1, t_2) = (threads.next().unwrap(), threads.next().unwrap());
+    let exit = monitor.enter(|flags| {
+        flags[1] = true;
+        Directive::Notify(Notification::all(1))
+    });
+    assert_eq!(Exit::Notified, exit);
+    assert_eq!(Exit::Returned, t_2.join().unwrap());
+    assert_eq!(1, monitor.num_waiting_on(0));
+
+    monitor.alter(|flags| flags[0] = true);
+    assert_eq!(Exit::Notified, monitor.enter(|_| Directive::NotifyOne));
+    assert_eq!(Exit::Returned, t_1.join().unwrap());
+    assert_eq!(0, monitor.num_waiting());
+}
+
+/// A thread that notifies one queue and waits on another is woken by a notification of
+/// the latter.
+#[test]
+fn notify_and_wait_on_queues() {
+    let monitor = Arc::new(SpeculativeMonitor::<_, 2>::with_queues(0, &ExpBackoff::SLEEPY));
+    let t_2 = {
+        let monitor = monitor.clone();
+        test_utils::spawn_blocked(move || {
+            monitor.enter(|stage| {
+                match *stage {
+                    0 => Directive::WaitOn(1, Duration::MAX),
+                    _ => {
+                        *stage = 2;
+                        Directive::NotifyAndReturn(Notification::one(0))
+                    }
+                }
+            })
+        })
+    };
+    monitor.wait_for_num_waiting_on(1, Ordering::is_eq, 1, LONG_WAIT).unwrap();
+
+    let exit = monitor.enter(|stage| {
+        match *stage {
+            0 => {
+                *stage = 1;
+                Directive::NotifyAndWaitOn(Notification::one(1), 0, Duration::MAX)
+            }
+            1 => Directive::Wait(Duration::MAX),
+            _ => Directive::Return,
+        }
+    });
+    assert_eq!(Exit::Returned, exit);
+    assert_eq!(Exit::Notified, t_2.join().unwrap());
+    assert_eq!(2, monitor.compute(|stage| *stage));
 }
 
 #[test]
@@ -375,6 +616,113 @@ fn wait_notify_chain() {
     t_4.join().unwrap();
 }
 
+#[test]
+fn poll_enter_notify_and_wait() {
+    let monitor = SpeculativeMonitor::new(());
+    let counter_1 = Arc::new(CountingWaker::default());
+    let counter_2 = Arc::new(CountingWaker::default());
+    let waker_1 = Waker::from(counter_1.clone());
+    let waker_2 = Waker::from(counter_2.clone());
+
+    assert!(monitor.poll_enter(|_| Directive::Wait(Duration::MAX), &mut Context::from_waker(&waker_1)).is_pending());
+
+    // the second task wakes the first, but not itself
+    assert!(monitor.poll_enter(|_| Directive::NotifyOneAndWait(Duration::MAX), &mut Context::from_waker(&waker_2)).is_pending());
+    assert_eq!(1, counter_1.0.load(std::sync::atomic::Ordering::Relaxed));
+    assert_eq!(0, counter_2.0.load(std::sync::atomic::Ordering::Relaxed));
+
+    assert!(monitor.poll_enter(|_| Directive::NotifyAllAndReturn, &mut Context::from_waker(&waker_1)).is_ready());
+    assert_eq!(1, counter_2.0.load(std::sync::atomic::Ordering::Relaxed));
+}
+
+#[test]
+fn poll_enter_wait_on() {
+    let monitor = SpeculativeMonitor::<_, 2>::with_queues((), &ExpBackoff::SLEEPY);
+    let counter = Arc::new(CountingWaker::default());
+    let waker = Waker::from(counter.clone());
+
+    assert!(monitor.poll_enter(|_| Directive::WaitOn(1, Duration::MAX), &mut Context::from_waker(&waker)).is_pending());
+    assert!(monitor.poll_enter(|_| Directive::WaitOn(1, Duration::ZERO), &mut Context::from_waker(&waker)).is_ready());
+
+    // the task is woken only by a notification of its queue
+    assert!(monitor.poll_enter(|_| Directive::NotifyAndReturn(Notification::all(0)), &mut Context::from_waker(&waker)).is_ready());
+    assert_eq!(0, counter.0.load(std::sync::atomic::Ordering::Relaxed));
+    assert!(monitor.poll_enter(|_| Directive::Notify(Notification::one(1)), &mut Context::from_waker(&waker)).is_ready());
+    assert_eq!(1, counter.0.load(std::sync::atomic::Ordering::Relaxed));
+}
+
+/// Counts the number of times it has been woken.
+#[derive(Default)]
+struct CountingWaker(AtomicU32);
+
+impl Wake for CountingWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn poll_enter_wait_for_notify() {
+    let monitor = SpeculativeMonitor::new(false);
+    let counter = Arc::new(CountingWaker::default());
+    let waker = Waker::from(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // a zero wait completes the poll
+    assert!(monitor.poll_enter(|_| Directive::Wait(Duration::ZERO), &mut cx).is_ready());
+
+    // a nonzero wait registers the waker; repeated polls do not register it twice
+    let mut wait_for_flag = |flag: &mut bool| {
+        if *flag {
+            Directive::Return
+        } else {
+            Directive::Wait(Duration::MAX)
+        }
+    };
+    assert!(monitor.poll_enter(&mut wait_for_flag, &mut cx).is_pending());
+    assert!(monitor.poll_enter(&mut wait_for_flag, &mut cx).is_pending());
+    assert_eq!(0, counter.0.load(std::sync::atomic::Ordering::Relaxed));
+    assert_eq!(0, monitor.num_waiting());
+
+    // raising the flag and notifying wakes the task exactly once
+    monitor.enter(|flag| {
+        *flag = true;
+        Directive::NotifyOne
+    });
+    assert_eq!(1, counter.0.load(std::sync::atomic::Ordering::Relaxed));
+    assert_eq!(Poll::Ready(()), monitor.poll_enter(&mut wait_for_flag, &mut cx));
+
+    // the waker was consumed by the notification
+    monitor.enter(|_| Directive::NotifyAll);
+    assert_eq!(1, counter.0.load(std::sync::atomic::Ordering::Relaxed));
+}
+
+#[test]
+fn poll_enter_forget() {
+    let monitor = SpeculativeMonitor::<_, 2>::with_queues((), &ExpBackoff::SLEEPY);
+    let counter_1 = Arc::new(CountingWaker::default());
+    let counter_2 = Arc::new(CountingWaker::default());
+    let waker_1 = Waker::from(counter_1.clone());
+    let waker_2 = Waker::from(counter_2.clone());
+
+    assert!(monitor.poll_enter(|_| Directive::WaitOn(0, Duration::MAX), &mut Context::from_waker(&waker_1)).is_pending());
+    assert!(monitor.poll_enter(|_| Directive::WaitOn(1, Duration::MAX), &mut Context::from_waker(&waker_1)).is_pending());
+    assert!(monitor.poll_enter(|_| Directive::WaitOn(0, Duration::MAX), &mut Context::from_waker(&waker_2)).is_pending());
+
+    // forgetting withdraws the waker from every queue and wakes it once
+    monitor.forget(&waker_1);
+    assert_eq!(1, counter_1.0.load(std::sync::atomic::Ordering::Relaxed));
+    assert_eq!([1, 0], monitor.tracker.lock().wakers.each_ref().map(Vec::len));
+
+    // forgetting an unregistered waker does nothing
+    monitor.forget(&waker_1);
+    assert_eq!(1, counter_1.0.load(std::sync::atomic::Ordering::Relaxed));
+
+    monitor.enter(|_| Directive::NotifyAll);
+    assert_eq!(1, counter_1.0.load(std::sync::atomic::Ordering::Relaxed));
+    assert_eq!(1, counter_2.0.load(std::sync::atomic::Ordering::Relaxed));
+}
+
 #[test]
 fn implements_debug() {
     let monitor = SpeculativeMonitor::new("foobar");
@@ -386,8 +734,22 @@ fn implements_debug() {
     drop(guard);
 }
 
-impl<T> SpeculativeMonitor<T> {
+#[test]
+fn static_monitor() {
+    static MONITOR: SpeculativeMonitor<u64> = SpeculativeMonitor::new(0);
+    MONITOR.enter(|state| {
+        *state += 1;
+        Directive::NotifyAll
+    });
+    assert_eq!(1, MONITOR.compute(|state| *state));
+}
+
+impl<T, const Q: usize> SpeculativeMonitor<T, Q> {
     fn wait_for_num_waiting(&self, cmp: impl FnMut(Ordering) -> bool, target: u32, duration: Duration) -> WaitResult {
         wait::Spin::wait_for_inequality(|| self.num_waiting(), cmp, &target, duration)
     }
+
+    fn wait_for_num_waiting_on(&self, queue: usize, cmp: impl FnMut(Ordering) -> bool, target: u32, duration: Duration) -> WaitResult {
+        wait::Spin::wait_for_inequality(|| self.num_waiting_on(queue), cmp, &target, duration)
+    }
 }
@@ -59,6 +59,9 @@ impl Probability {
     /// Creates a new [`Probability`] value, without checking the bounds. If a
     /// probability is created outside the range \[0, 1\], its behaviour with an
     /// RNG is undefined.
+    ///
+    /// # Safety
+    /// The caller must ensure that `p` lies in the range \[0, 1\].
     #[inline(always)]
     pub const unsafe fn new_unchecked(p: f64) -> Self {
         Self(p)
@@ -275,7 +278,7 @@ pub struct CyclicSeed(u64);
 
 impl CyclicSeed {
     #[inline]
-    pub fn new(seed: u64) -> Self {
+    pub const fn new(seed: u64) -> Self {
         Self(seed)
     }
 }
@@ -26,19 +26,19 @@ fn duration_from_nanos_reversible() {
 fn fixed_duration() {
     assert_eq!(
         Duration::ZERO,
-        FixedDuration::default().next_range(Duration::ZERO..Duration::ZERO)
+        FixedDuration.next_range(Duration::ZERO..Duration::ZERO)
     );
     assert_eq!(
         Duration::ZERO,
-        FixedDuration::default().next_range(Duration::ZERO..Duration::from_nanos(1))
+        FixedDuration.next_range(Duration::ZERO..Duration::from_nanos(1))
     );
     assert_eq!(
         Duration::from_nanos(1),
-        FixedDuration::default().next_range(Duration::ZERO..Duration::from_nanos(2))
+        FixedDuration.next_range(Duration::ZERO..Duration::from_nanos(2))
     );
     assert_eq!(
         Duration::MAX - Duration::from_nanos(1),
-        FixedDuration::default().next_range(Duration::ZERO..Duration::MAX)
+        FixedDuration.next_range(Duration::ZERO..Duration::MAX)
     );
 }
 
@@ -53,7 +53,7 @@ fn random_duration() {
         exp_min: Duration,
         exp_max: Duration,
     }
-    for case in &vec![
+    for case in &[
         // from zero
         Case {
             range: Duration::ZERO..Duration::ZERO,
@@ -217,8 +217,7 @@ fn next_bool() {
     // NB: no matter what the random number, p(0.0) should always evaluate to false,
     // while p(1.0) should always evaluate to true
 
-    let mut rng = MockRng::default();
-    rng.next = 0;
+    let mut rng = MockRng { next: 0 };
     assert!(!rng.next_bool(0.0.into()));
     assert!(rng.next_bool(f64::EPSILON.into()));
     assert!(rng.next_bool(0.5.into()));
@@ -1,5 +1,6 @@
 use std::sync::{Condvar, LockResult, MutexGuard, TryLockError, TryLockResult};
 use std::time::{Duration};
+use crate::chalice::{ChaliceResult, ChaliceResultExt};
 
 /// From _Poison_, by _The Prodigy_ (1994).
 /// I got the poison,
@@ -39,6 +40,24 @@ impl<T> Remedy<T> for TryLockResult<T> {
     }
 }
 
+impl<T> Remedy<T> for ChaliceResult<T> {
+    type Output = T;
+
+    #[inline(always)]
+    fn remedy(self) -> Self::Output {
+        self.either()
+    }
+}
+
+impl<T> Remedy<T> for Option<ChaliceResult<T>> {
+    type Output = Option<T>;
+
+    #[inline(always)]
+    fn remedy(self) -> Self::Output {
+        self.map(ChaliceResultExt::either)
+    }
+}
+
 #[inline(always)]
 pub fn cond_wait_remedy<'a, T>(
     cond: &Condvar,
@@ -3,7 +3,9 @@ use std::{fmt, hint};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use crate::backoff::ExpBackoff;
+use crate::deadlock::{LockId, LockKind};
 use crate::inf_iterator::{InfIterator, IntoInfIterator};
 use crate::rand::FIXED_DURATION;
 
@@ -13,6 +15,10 @@ unsafe impl<T: ?Sized + Sync> Sync for SpinGuard<'_, T> {}
 
 pub struct SpinMutex<T: ?Sized> {
     locked: AtomicBool,
+    id: LockId,
+    /// Borrowed rather than owned, so that the policy costs a pointer rather than the whole
+    /// [`ExpBackoff`].
+    backoff: &'static ExpBackoff,
     data: UnsafeCell<T>,
 }
 
@@ -24,9 +30,25 @@ pub struct SpinGuard<'a, T: ?Sized> {
 
 impl<T> SpinMutex<T> {
     #[inline]
-    pub fn new(t: T) -> Self {
+    pub const fn new(t: T) -> Self {
+        Self::with_backoff(t, &ExpBackoff::SLEEPY)
+    }
+
+    /// Creates a mutex that backs off according to the given policy while the lock is
+    /// contended.
+    #[inline]
+    pub const fn with_backoff(t: T, backoff: &'static ExpBackoff) -> Self {
+        Self::with_kind(t, LockKind::SpinMutex, backoff)
+    }
+
+    /// Creates a mutex that identifies as the given kind of lock for deadlock detection,
+    /// for use as the innards of another lock.
+    #[inline]
+    pub(crate) const fn with_kind(t: T, kind: LockKind, backoff: &'static ExpBackoff) -> Self {
         Self {
             locked: AtomicBool::new(false),
+            id: LockId::new(kind),
+            backoff,
             data: UnsafeCell::new(t),
         }
     }
@@ -63,15 +85,16 @@ impl<'a, T: ?Sized> DerefMut for SpinGuard<'a, T> {
 
 impl<T: ?Sized> SpinMutex<T> {
     #[inline]
-    pub fn lock(&self) -> SpinGuard<T> {
+    pub fn lock(&self) -> SpinGuard<'_, T> {
         // a [TTAS](https://en.wikipedia.org/wiki/Test_and_test-and-set) implementation that does not result in
         // continuous cache line invalidation
+        self.id.attempt(self, Duration::MAX);
         loop {
             match self.try_lock() {
                 None => {
                     // let mut rng = LazyRand64::<Xorshift, _>::lazy(clock_seed);
                     let mut rng = FIXED_DURATION;
-                    let mut backoff = ExpBackoff::sleepy().into_inf_iter();
+                    let mut backoff = self.backoff.into_inf_iter();
                     while self.locked.load(Ordering::Relaxed) {
                         hint::spin_loop();
                         backoff.next().act(|| &mut rng)
@@ -83,19 +106,26 @@ impl<T: ?Sized> SpinMutex<T> {
     }
 
     #[inline]
-    pub fn try_lock(&self) -> Option<SpinGuard<T>> {
+    pub fn try_lock(&self) -> Option<SpinGuard<'_, T>> {
         if self.locked.compare_exchange(false, true, Ordering::Acquire, Ordering::Acquire).is_ok() {
+            self.id.acquired(self);
             Some(SpinGuard {
                 lock: self,
-                __no_send: PhantomData::default()
+                __no_send: PhantomData
             })
         } else {
             None
         }
     }
 
+    /// Forcibly releases the lock.
+    ///
+    /// # Safety
+    /// May only be called by the lock's owner, when no [`SpinGuard`] is outstanding
+    /// (for example, after the guard has been forgotten).
     #[inline]
     pub unsafe fn unlock(&self) {
+        self.id.released();
         self.locked.store(false, Ordering::Release);
     }
 
@@ -1,4 +1,6 @@
 use std::sync::{Arc, Barrier};
+use std::thread;
+use crate::backoff::ExpBackoff;
 use crate::spin_mutex::SpinMutex;
 use crate::test_utils;
 
@@ -90,4 +92,29 @@ fn debug() {
     let guard = lock.lock();
     assert!(format!("{:?}", lock).contains("<locked>"), "{:?}", lock);
     drop(guard);
-}
\ No newline at end of file
+}
+#[test]
+fn static_lock() {
+    static LOCK: SpinMutex<u64> = SpinMutex::new(0);
+    *LOCK.lock() += 1;
+    assert_eq!(1, *LOCK.lock());
+}
+
+#[test]
+fn with_backoff() {
+    for backoff in [&ExpBackoff::SPINNY, &ExpBackoff::YIELDY, &ExpBackoff::SLEEPY] {
+        let lock = Arc::new(SpinMutex::with_backoff(0, backoff));
+        let threads = (0..4).map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    *lock.lock() += 1;
+                }
+            })
+        }).collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(400, *lock.lock());
+    }
+}
@@ -32,11 +32,11 @@ impl<T> UnwindableRefCell<T> {
 }
 
 impl<T: ?Sized> UnwindableRefCell<T> {
-    pub fn borrow(&self) -> Ref<T> {
+    pub fn borrow(&self) -> Ref<'_, T> {
         self.inner.borrow()
     }
 
-    pub fn borrow_mut(&self) -> RefMut<T> {
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
         self.inner.borrow_mut()
     }
 }
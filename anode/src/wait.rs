@@ -8,8 +8,9 @@ use crate::rand::{FIXED_DURATION};
 
 pub type WaitResult = Result<(), ()>;
 
+#[allow(clippy::result_unit_err)]
 pub trait Wait {
-    fn wait_until<C>(condition: C, deadline: Deadline) -> WaitResult
+    fn wait_until<C>(condition: C, deadline: impl Into<Deadline>) -> WaitResult
     where
         C: FnMut() -> bool;
 
@@ -23,22 +24,36 @@ pub trait Wait {
 
     #[inline(always)]
     fn wait_for_inequality<T, G>(
+        lhs_f: G,
+        cmp: impl FnMut(Ordering) -> bool,
+        rhs: &T,
+        duration: Duration,
+    ) -> WaitResult
+    where
+        T: Ord,
+        G: FnMut() -> T,
+    {
+        Self::wait_for_inequality_until(lhs_f, cmp, rhs, Deadline::lazy_after(duration))
+    }
+
+    #[inline(always)]
+    fn wait_for_inequality_until<T, G>(
         mut lhs_f: G,
         mut cmp: impl FnMut(Ordering) -> bool,
         rhs: &T,
-        duration: Duration,
+        deadline: impl Into<Deadline>,
     ) -> WaitResult
     where
         T: Ord,
         G: FnMut() -> T,
     {
-        Self::wait_for(
+        Self::wait_until(
             || {
                 let lhs = lhs_f();
                 let ord = lhs.cmp(rhs);
                 cmp(ord)
             },
-            duration,
+            deadline,
         )
     }
 }
@@ -47,10 +62,11 @@ pub struct Spin {}
 
 impl Wait for Spin {
     #[inline(always)]
-    fn wait_until<C>(mut condition: C, mut deadline: Deadline) -> WaitResult
+    fn wait_until<C>(mut condition: C, deadline: impl Into<Deadline>) -> WaitResult
     where
         C: FnMut() -> bool,
     {
+        let mut deadline = deadline.into();
         let mut rng = FIXED_DURATION;
         let mut backoff = ExpBackoff::sleepy().into_inf_iter();
         while !condition() {
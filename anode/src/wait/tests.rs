@@ -1,5 +1,5 @@
 use std::cmp::Ordering;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use crate::deadline::Deadline;
 use crate::wait::Spin;
 use crate::wait::Wait;
@@ -34,4 +34,16 @@ fn wait_for_inequality() {
 
     let result = Spin::wait_for_inequality(|| 69, Ordering::is_lt, &70, Duration::ZERO);
     assert!(result.is_ok());
-}
\ No newline at end of file
+}
+
+#[test]
+fn wait_for_inequality_until() {
+    let result = Spin::wait_for_inequality_until(|| 69, Ordering::is_eq, &42, Deadline::Elapsed);
+    assert!(result.is_err());
+
+    let result = Spin::wait_for_inequality_until(|| 69, Ordering::is_eq, &42, Instant::now());
+    assert!(result.is_err());
+
+    let result = Spin::wait_for_inequality_until(|| 69, Ordering::is_gt, &42, Deadline::Forever);
+    assert!(result.is_ok());
+}
@@ -1,23 +1,61 @@
+// Every moderator's `Moderator::INIT` is a `const` holding atomics and monitors, which is
+// what lets `ZLock::new` be `const`. Each use of it is meant to produce a fresh, unlocked
+// lock, so the lint against interior-mutable constants is silenced once for the module.
+#![allow(clippy::declare_interior_mutable_const)]
+
 use std::cell::UnsafeCell;
 use std::fmt;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::ptr::NonNull;
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
-
+use crate::deadline::Deadline;
+use crate::deadlock::{LockId, LockKind};
+use crate::monitor::SpeculativeMonitor;
+
+mod arc;
+mod condvar;
+mod dynamic;
+mod future;
+mod mapped;
+mod multi;
+mod poison;
+mod reentrant;
+mod seq;
+mod stats;
+mod upgradable;
+mod word;
 mod read_biased;
 mod write_biased;
+mod phase_fair;
+mod prioritized;
 mod arrival_ordered;
 mod stochastic;
+mod sharded;
 mod legacy_read_biased;
 mod legacy_write_biased;
 mod legacy_arrival_ordered;
 
+pub use arc::{ArcLockReadGuard, ArcLockUpgradeOutcome, ArcLockWriteGuard};
+pub use condvar::ZCondvar;
+pub use dynamic::{DynZLock, DynZLockReadGuard, DynZLockUpgradeOutcome, DynZLockWriteGuard};
+pub use future::{ReadFuture, UpgradeFuture, WriteFuture};
+pub use mapped::{MappedLockReadGuard, MappedLockWriteGuard};
+pub use multi::{lock_all, try_lock_all, Intent, IntentGuard, LockIntent, LockSet, ReadIntent, WriteIntent};
+pub use poison::{PoisonReadGuard, PoisonUpgradeOutcome, PoisonWriteGuard, PoisonZLock};
+pub use reentrant::{ReentrantReadGuard, ReentrantWriteGuard, ReentrantZLock};
+pub use seq::{OptimisticRead, SeqReadGuard, SeqUpgradeOutcome, SeqWriteGuard, SeqZLock, Stamp};
+pub use stats::{Instrumented, StatsSnapshot};
+pub use upgradable::{UpgradableLockReadGuard, UpgradableUpgradeOutcome};
 pub use read_biased::ReadBiased;
-pub use write_biased::WriteBiased;
+pub use write_biased::{WriteBiased, WriteBiasedConfig};
+pub use phase_fair::PhaseFair;
+pub use prioritized::Prioritized;
 pub use arrival_ordered::ArrivalOrdered;
-pub use stochastic::Stochastic;
+pub use stochastic::{Stochastic, StochasticConfig};
+pub use sharded::{Sharded, ShardedZLock};
 pub use legacy_read_biased::LegacyReadBiased;
 pub use legacy_write_biased::LegacyWriteBiased;
 pub use legacy_arrival_ordered::LegacyArrivalOrdered;
@@ -30,31 +68,139 @@ unsafe impl<T: ?Sized + Sync, M: Moderator> Sync for LockWriteGuard<'_, T, M> {}
 pub trait Moderator: Debug {
     type Sync;
 
-    fn new() -> Self::Sync;
+    /// Tunes the behaviour of an individual lock; see [`ZLock::with_config`]. Moderators
+    /// that have nothing to tune take `()`.
+    type ModeratorConfig;
+
+    /// The state of an unlocked lock. A constant, rather than a constructor function, so
+    /// that [`ZLock::new`] may be evaluated in a `const` context.
+    const INIT: Self::Sync;
+
+    /// The state of an unlocked lock, tuned with the given configuration.
+    fn with_config(config: Self::ModeratorConfig) -> Self::Sync;
 
     fn try_read(sync: &Self::Sync, duration: Duration) -> bool;
 
+    /// As per [`try_read`](Self::try_read), but bounded by a deadline that may be shared with
+    /// other timed operations. The default passes on the remaining time; moderators that
+    /// wait on a monitor bound their waits by the deadline itself.
+    #[inline]
+    fn try_read_until(sync: &Self::Sync, mut deadline: Deadline) -> bool {
+        Self::try_read(sync, deadline.remaining())
+    }
+
     fn read_unlock(sync: &Self::Sync);
 
     fn try_write(sync: &Self::Sync, duration: Duration) -> bool;
 
+    /// As per [`try_write`](Self::try_write), but bounded by a deadline; see
+    /// [`try_read_until`](Self::try_read_until).
+    #[inline]
+    fn try_write_until(sync: &Self::Sync, mut deadline: Deadline) -> bool {
+        Self::try_write(sync, deadline.remaining())
+    }
+
     fn write_unlock(sync: &Self::Sync);
 
     fn downgrade(sync: &Self::Sync);
 
     fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool;
+
+    /// As per [`try_upgrade`](Self::try_upgrade), but bounded by a deadline; see
+    /// [`try_read_until`](Self::try_read_until).
+    #[inline]
+    fn try_upgrade_until(sync: &Self::Sync, mut deadline: Deadline) -> bool {
+        Self::try_upgrade(sync, deadline.remaining())
+    }
+
+    /// Takes a snapshot of the lock's state, for diagnostic purposes.
+    fn inspect(sync: &Self::Sync) -> LockState;
+}
+
+/// Marks a [`Moderator`] whose locks may be released by a thread other than the one that
+/// acquired them. Owned guards (see [`ZLock::read_arc`] and [`ZLock::write_arc`]) over such
+/// a moderator are [`Send`].
+///
+/// # Safety
+/// The implementor must not associate a held lock with the acquiring thread; e.g., by
+/// relying on thread-local state or on OS primitives that must be unlocked by their owner.
+pub unsafe trait ThreadAgnostic: Moderator {}
+
+/// A [`Moderator`] that supports a third, _upgradable read_ mode. An upgradable reader
+/// coexists with ordinary readers but excludes writers and other upgradable readers. As
+/// such, it is guaranteed to eventually upgrade to a write lock, once the ordinary readers
+/// have drained.
+pub trait UpgradableModerator: Moderator {
+    fn try_read_upgradable(sync: &Self::Sync, duration: Duration) -> bool;
+
+    fn upgradable_unlock(sync: &Self::Sync);
+
+    fn try_upgrade_upgradable(sync: &Self::Sync, duration: Duration) -> bool;
+
+    /// Converts an upgradable read lock into an ordinary read lock.
+    fn downgrade_upgradable(sync: &Self::Sync);
+
+    /// Converts a write lock into an upgradable read lock.
+    fn downgrade_to_upgradable(sync: &Self::Sync);
+}
+
+/// A [`Moderator`] that can also acquire locks asynchronously, by registering task wakers
+/// in lieu of blocking on a condition variable.
+pub trait AsyncModerator: Moderator {
+    type ReadWaiter: Waiter<Self::Sync>;
+
+    type WriteWaiter: Waiter<Self::Sync>;
+
+    type UpgradeWaiter: Waiter<Self::Sync>;
+
+    /// Withdraws the waker registered by an acquisition that was abandoned while pending.
+    fn forget(sync: &Self::Sync, waker: &Waker);
+}
+
+/// Tracks the progress of a single asynchronous acquisition.
+///
+/// A waiter is polled until it completes, at which point the lock is held. A waiter that
+/// is abandoned before completing must be cancelled, so that it may relinquish whatever
+/// claims it has staked on the lock (pending flags, tickets, queue positions, etc.).
+pub trait Waiter<S>: Default + Unpin {
+    fn poll_acquire(&mut self, sync: &S, cx: &mut Context<'_>) -> Poll<()>;
+
+    fn cancel(&mut self, sync: &S);
 }
 
+/// The queue of a moderator's monitor on which readers (ordinary and upgradable) wait.
+const READ_QUEUE: usize = 0;
+
+/// The queue of a moderator's monitor on which writers and upgraders wait.
+const WRITE_QUEUE: usize = 1;
+
+/// The monitor of a moderator, whose readers wait apart from its writers and upgraders, so
+/// that a release need only notify the waiters it may have admitted.
+type ModeratorMonitor<S> = SpeculativeMonitor<S, 2>;
+
 pub struct ZLock<T: ?Sized, M: Moderator> {
     sync: M::Sync,
+    id: LockId,
     data: UnsafeCell<T>,
 }
 
 impl<T, M: Moderator> ZLock<T, M> {
     #[inline]
-    pub fn new(t: T) -> Self {
+    pub const fn new(t: T) -> Self {
+        Self {
+            sync: M::INIT,
+            id: LockId::new(LockKind::ZLock),
+            data: UnsafeCell::new(t),
+        }
+    }
+
+    /// Creates a lock whose moderator is tuned with the given configuration. Locks
+    /// created with [`new`](Self::new) take the moderator's default configuration.
+    #[inline]
+    pub fn with_config(t: T, config: M::ModeratorConfig) -> Self {
         Self {
-            sync: M::new(),
+            sync: M::with_config(config),
+            id: LockId::new(LockKind::ZLock),
             data: UnsafeCell::new(t),
         }
     }
@@ -72,21 +218,44 @@ impl<T: ?Sized, M: Moderator> ZLock<T, M> {
 
     #[inline]
     pub fn try_read(&self, duration: Duration) -> Option<LockReadGuard<'_, T, M>> {
+        self.id.attempt(self, duration);
         if M::try_read(&self.sync, duration) {
-            let data = unsafe { NonNull::new_unchecked(self.data.get()) };
-            Some(LockReadGuard {
-                data,
-                lock: self,
-                locked: true,
-                __no_send: PhantomData::default(),
-            })
+            self.id.acquired(self);
+            Some(self.read_guard())
+        } else {
+            None
+        }
+    }
+
+    /// Attempts to acquire a read lock by the given deadline (or [`Instant`](std::time::Instant)),
+    /// which may be shared with other timed operations.
+    #[inline]
+    pub fn try_read_until(&self, deadline: impl Into<Deadline>) -> Option<LockReadGuard<'_, T, M>> {
+        let mut deadline = deadline.into();
+        self.id.attempt(self, deadline.remaining());
+        if M::try_read_until(&self.sync, deadline) {
+            self.id.acquired(self);
+            Some(self.read_guard())
         } else {
             None
         }
     }
 
+    /// Wraps a read lock that has already been acquired through the moderator.
+    #[inline]
+    fn read_guard(&self) -> LockReadGuard<'_, T, M> {
+        let data = unsafe { NonNull::new_unchecked(self.data.get()) };
+        LockReadGuard {
+            data,
+            lock: self,
+            locked: true,
+            __no_send: PhantomData,
+        }
+    }
+
     #[inline]
     fn read_unlock(&self) {
+        self.id.released();
         M::read_unlock(&self.sync);
     }
 
@@ -97,34 +266,51 @@ impl<T: ?Sized, M: Moderator> ZLock<T, M> {
 
     #[inline]
     pub fn try_write(&self, duration: Duration) -> Option<LockWriteGuard<'_, T, M>> {
+        self.id.attempt(self, duration);
         if M::try_write(&self.sync, duration) {
-            Some(LockWriteGuard {
-                lock: self,
-                locked: true,
-                __no_send: PhantomData::default(),
-            })
+            self.id.acquired(self);
+            Some(self.write_guard())
         } else {
             None
         }
     }
 
+    /// Attempts to acquire a write lock by the given deadline (or [`Instant`](std::time::Instant)),
+    /// which may be shared with other timed operations.
     #[inline]
-    fn write_unlock(&self) {
-        M::write_unlock(&self.sync);
+    pub fn try_write_until(&self, deadline: impl Into<Deadline>) -> Option<LockWriteGuard<'_, T, M>> {
+        let mut deadline = deadline.into();
+        self.id.attempt(self, deadline.remaining());
+        if M::try_write_until(&self.sync, deadline) {
+            self.id.acquired(self);
+            Some(self.write_guard())
+        } else {
+            None
+        }
     }
 
+    /// Wraps a write lock that has already been acquired through the moderator.
     #[inline]
-    pub fn downgrade(&self) -> LockReadGuard<T, M> {
-        M::downgrade(&self.sync);
-        let data = unsafe { NonNull::new_unchecked(self.data.get()) };
-        LockReadGuard {
-            data,
+    fn write_guard(&self) -> LockWriteGuard<'_, T, M> {
+        LockWriteGuard {
             lock: self,
             locked: true,
-            __no_send: PhantomData::default(),
+            __no_send: PhantomData,
         }
     }
 
+    #[inline]
+    fn write_unlock(&self) {
+        self.id.released();
+        M::write_unlock(&self.sync);
+    }
+
+    #[inline]
+    pub fn downgrade(&self) -> LockReadGuard<'_, T, M> {
+        M::downgrade(&self.sync);
+        self.read_guard()
+    }
+
     #[inline]
     fn upgrade(&self) -> LockWriteGuard<'_, T, M> {
         self.try_upgrade(Duration::MAX).unwrap()
@@ -133,16 +319,28 @@ impl<T: ?Sized, M: Moderator> ZLock<T, M> {
     #[inline]
     fn try_upgrade(&self, duration: Duration) -> Option<LockWriteGuard<'_, T, M>> {
         if M::try_upgrade(&self.sync, duration) {
-            Some(LockWriteGuard {
-                lock: self,
-                locked: true,
-                __no_send: PhantomData::default(),
-            })
+            Some(self.write_guard())
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn try_upgrade_until(&self, deadline: Deadline) -> Option<LockWriteGuard<'_, T, M>> {
+        if M::try_upgrade_until(&self.sync, deadline) {
+            Some(self.write_guard())
         } else {
             None
         }
     }
 
+    /// Takes a snapshot of the lock's state. The snapshot is only indicative, as the state
+    /// may have changed by the time it is observed.
+    #[inline]
+    pub fn state(&self) -> LockState {
+        M::inspect(&self.sync)
+    }
+
     /// Returns a mutable reference to the underlying data.
     ///
     /// Since this call borrows the [`MultiLock`] mutably, no actual locking needs to
@@ -172,6 +370,13 @@ impl<T: ?Sized, M: Moderator> Drop for LockReadGuard<'_, T, M> {
 }
 
 impl<'a, T: ?Sized, M: Moderator> LockReadGuard<'a, T, M> {
+    /// Upgrades to a write lock once this guard is the sole holder of the lock.
+    ///
+    /// Blocks forever if another reader upgrades concurrently, or if another thread holds
+    /// an upgradable read lock (see [`ZLock::read_upgradable`]) and either upgrades it or
+    /// keeps it indefinitely, as neither party releases its read lock while it waits. Use
+    /// [`try_upgrade`](Self::try_upgrade) where such contention is possible, releasing the
+    /// read lock on failure.
     #[inline]
     pub fn upgrade(mut self) -> LockWriteGuard<'a, T, M> {
         self.locked = false;
@@ -188,6 +393,19 @@ impl<'a, T: ?Sized, M: Moderator> LockReadGuard<'a, T, M> {
             }
         }
     }
+
+    /// Attempts to upgrade the read lock by the given deadline, returning the read guard
+    /// unchanged if the deadline elapses.
+    #[inline]
+    pub fn try_upgrade_until(mut self, deadline: impl Into<Deadline>) -> LockUpgradeOutcome<'a, T, M> {
+        match self.lock.try_upgrade_until(deadline.into()) {
+            None => UpgradeOutcome::Unchanged(self),
+            Some(guard) => {
+                self.locked = false;
+                UpgradeOutcome::Upgraded(guard)
+            }
+        }
+    }
 }
 
 impl<T: ?Sized, M: Moderator> Deref for LockReadGuard<'_, T, M> {
@@ -282,8 +500,68 @@ impl<W, R> UpgradeOutcome<W, R> {
     }
 }
 
+/// A point-in-time view of the state of a lock, as reported by [`ZLock::state`]. Fields
+/// that a moderator does not track are left at their defaults.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockState {
+    /// The number of read locks held, excluding an upgradable read lock.
+    pub readers: u32,
+
+    /// Whether a write lock is held.
+    pub writer: bool,
+
+    /// Whether an upgradable read lock is held.
+    pub upgradable: bool,
+
+    /// Whether a writer (or an upgrader) is waiting, holding back arriving readers.
+    pub writer_pending: bool,
+
+    /// The number of threads (or tasks) waiting to acquire the lock.
+    pub waiting: u32,
+
+    /// The tickets of a moderator that admits waiters in the order of their arrival.
+    pub tickets: Option<Tickets>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tickets {
+    /// The ticket that will be issued to the next waiter.
+    pub next: u64,
+
+    /// The number of tickets that have been serviced.
+    pub serviced: u64,
+}
+
+/// The ticket bookkeeping shared by the moderators that order their waiters by arrival.
+/// Tickets are issued from 1, so that 0 may stand for the absence of a ticket.
+impl Tickets {
+    pub(crate) const INIT: Self = Self { next: 1, serviced: 0 };
+
+    /// Issues the next ticket.
+    #[inline]
+    pub(crate) fn take(&mut self) -> u64 {
+        let next = self.next;
+        self.next = next + 1;
+        next
+    }
+
+    /// Whether every ticket issued ahead of the given one has been serviced.
+    #[inline]
+    pub(crate) fn is_turn(&self, ticket: u64) -> bool {
+        self.serviced >= ticket - 1
+    }
+
+    /// Services a ticket, whether its holder acquired the lock or forfeited the ticket.
+    #[inline]
+    pub(crate) fn service(&mut self) {
+        self.serviced += 1;
+    }
+}
+
 impl<T: ?Sized + Debug, M: Moderator> Debug for ZLock<T, M> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the state is inspected first, so that it excludes our own read lock
+        let state = self.state();
         let mut d = f.debug_struct("ZLock");
         match self.try_read(Duration::ZERO) {
             None => {
@@ -299,11 +577,11 @@ impl<T: ?Sized + Debug, M: Moderator> Debug for ZLock<T, M> {
                 d.field("data", &&*guard);
             }
         }
+        d.field("state", &state);
         d.finish_non_exhaustive()
     }
 }
 
-#[cfg(test)]
 pub mod locklike;
 
 #[cfg(test)]
@@ -0,0 +1,175 @@
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::zlock::{Moderator, ThreadAgnostic, UpgradeOutcome, ZLock};
+
+unsafe impl<T: ?Sized + Sync, M: Moderator> Sync for ArcLockReadGuard<T, M> {}
+unsafe impl<T: ?Sized + Sync, M: Moderator> Sync for ArcLockWriteGuard<T, M> {}
+unsafe impl<T: ?Sized + Send + Sync, M: ThreadAgnostic> Send for ArcLockReadGuard<T, M> {}
+unsafe impl<T: ?Sized + Send + Sync, M: ThreadAgnostic> Send for ArcLockWriteGuard<T, M> {}
+
+impl<T: ?Sized, M: Moderator> ZLock<T, M> {
+    /// Acquires a read lock, returning an owned guard that keeps the lock alive through
+    /// its own reference count, rather than borrowing it.
+    #[inline]
+    pub fn read_arc(self: &Arc<Self>) -> ArcLockReadGuard<T, M> {
+        self.try_read_arc(Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    pub fn try_read_arc(self: &Arc<Self>, duration: Duration) -> Option<ArcLockReadGuard<T, M>> {
+        self.id.attempt(&**self, duration);
+        if M::try_read(&self.sync, duration) {
+            self.id.acquired(&**self);
+            Some(ArcLockReadGuard::new(self.clone()))
+        } else {
+            None
+        }
+    }
+
+    /// Acquires a write lock, returning an owned guard that keeps the lock alive through
+    /// its own reference count, rather than borrowing it.
+    #[inline]
+    pub fn write_arc(self: &Arc<Self>) -> ArcLockWriteGuard<T, M> {
+        self.try_write_arc(Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    pub fn try_write_arc(self: &Arc<Self>, duration: Duration) -> Option<ArcLockWriteGuard<T, M>> {
+        self.id.attempt(&**self, duration);
+        if M::try_write(&self.sync, duration) {
+            self.id.acquired(&**self);
+            Some(ArcLockWriteGuard::new(self.clone()))
+        } else {
+            None
+        }
+    }
+}
+
+pub struct ArcLockReadGuard<T: ?Sized, M: Moderator> {
+    lock: Arc<ZLock<T, M>>,
+
+    /// Emulates !Send for the struct, unless the moderator is [`ThreadAgnostic`].
+    __no_send: PhantomData<*const ()>,
+}
+
+impl<T: ?Sized, M: Moderator> ArcLockReadGuard<T, M> {
+    #[inline]
+    fn new(lock: Arc<ZLock<T, M>>) -> Self {
+        Self {
+            lock,
+            __no_send: PhantomData,
+        }
+    }
+
+    /// Relinquishes the guard without releasing the lock.
+    #[inline]
+    fn into_lock(self) -> Arc<ZLock<T, M>> {
+        let this = ManuallyDrop::new(self);
+        unsafe { ptr::read(&this.lock) }
+    }
+
+    /// Returns the lock that this guard was acquired from.
+    #[inline]
+    pub fn lock(guard: &Self) -> &Arc<ZLock<T, M>> {
+        &guard.lock
+    }
+
+    #[inline]
+    pub fn upgrade(self) -> ArcLockWriteGuard<T, M> {
+        self.try_upgrade(Duration::MAX).upgraded().unwrap()
+    }
+
+    #[inline]
+    pub fn try_upgrade(self, duration: Duration) -> ArcLockUpgradeOutcome<T, M> {
+        if M::try_upgrade(&self.lock.sync, duration) {
+            UpgradeOutcome::Upgraded(ArcLockWriteGuard::new(self.into_lock()))
+        } else {
+            UpgradeOutcome::Unchanged(self)
+        }
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Drop for ArcLockReadGuard<T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.read_unlock();
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Deref for ArcLockReadGuard<T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+pub struct ArcLockWriteGuard<T: ?Sized, M: Moderator> {
+    lock: Arc<ZLock<T, M>>,
+
+    /// Emulates !Send for the struct, unless the moderator is [`ThreadAgnostic`].
+    __no_send: PhantomData<*const ()>,
+}
+
+impl<T: ?Sized, M: Moderator> ArcLockWriteGuard<T, M> {
+    #[inline]
+    fn new(lock: Arc<ZLock<T, M>>) -> Self {
+        Self {
+            lock,
+            __no_send: PhantomData,
+        }
+    }
+
+    /// Relinquishes the guard without releasing the lock.
+    #[inline]
+    fn into_lock(self) -> Arc<ZLock<T, M>> {
+        let this = ManuallyDrop::new(self);
+        unsafe { ptr::read(&this.lock) }
+    }
+
+    /// Returns the lock that this guard was acquired from.
+    #[inline]
+    pub fn lock(guard: &Self) -> &Arc<ZLock<T, M>> {
+        &guard.lock
+    }
+
+    #[inline]
+    pub fn downgrade(self) -> ArcLockReadGuard<T, M> {
+        let lock = self.into_lock();
+        M::downgrade(&lock.sync);
+        ArcLockReadGuard::new(lock)
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Drop for ArcLockWriteGuard<T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.write_unlock();
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Deref for ArcLockWriteGuard<T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized, M: Moderator> DerefMut for ArcLockWriteGuard<T, M> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+pub type ArcLockUpgradeOutcome<T, M> = UpgradeOutcome<ArcLockWriteGuard<T, M>, ArcLockReadGuard<T, M>>;
+
+#[cfg(test)]
+mod tests;
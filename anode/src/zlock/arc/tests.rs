@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use std::time::Duration;
+use crate::executor::{Executor, Queue, Submitter, ThreadPool};
+use crate::zlock::{ArcLockReadGuard, ArcLockWriteGuard, ArrivalOrdered, PhaseFair, Prioritized, ReadBiased, Stochastic, ThreadAgnostic, WriteBiased, ZLock};
+
+#[test]
+fn guards_are_send() {
+    fn send<T: Send>(_: T) {}
+
+    let lock = Arc::new(ZLock::<_, ReadBiased>::new(()));
+    send(lock.read_arc());
+    send(lock.write_arc());
+}
+
+#[test]
+fn read_write_cycle() {
+    __read_write_cycle::<ReadBiased>();
+    __read_write_cycle::<WriteBiased>();
+    __read_write_cycle::<ArrivalOrdered>();
+    __read_write_cycle::<Stochastic>();
+    __read_write_cycle::<PhaseFair>();
+    __read_write_cycle::<Prioritized>();
+}
+
+fn __read_write_cycle<M: ThreadAgnostic>() {
+    let lock = Arc::new(ZLock::<_, M>::new(0));
+
+    let guard = lock.read_arc();
+    assert_eq!(0, *guard);
+    assert!(Arc::ptr_eq(&lock, ArcLockReadGuard::lock(&guard)));
+    drop(guard);
+
+    let mut guard = lock.write_arc();
+    *guard = 42;
+    assert!(Arc::ptr_eq(&lock, ArcLockWriteGuard::lock(&guard)));
+    let guard = guard.downgrade();
+    assert_eq!(42, *guard);
+
+    // downgraded guard still holds a read lock
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    assert!(lock.try_read(Duration::ZERO).is_some());
+
+    let mut guard = guard.upgrade();
+    *guard = 69;
+    drop(guard);
+
+    // all locks released
+    assert!(lock.try_write(Duration::ZERO).is_some());
+    assert_eq!(69, *lock.read());
+}
+
+#[test]
+fn try_upgrade_while_read_locked() {
+    __try_upgrade_while_read_locked::<ReadBiased>();
+    __try_upgrade_while_read_locked::<WriteBiased>();
+    __try_upgrade_while_read_locked::<ArrivalOrdered>();
+    __try_upgrade_while_read_locked::<Stochastic>();
+    __try_upgrade_while_read_locked::<PhaseFair>();
+    __try_upgrade_while_read_locked::<Prioritized>();
+}
+
+fn __try_upgrade_while_read_locked<M: ThreadAgnostic>() {
+    let lock = Arc::new(ZLock::<_, M>::new(0));
+    let guard_1 = lock.read_arc();
+    let guard_2 = lock.try_read_arc(Duration::ZERO).unwrap();
+
+    let guard_2 = guard_2.try_upgrade(Duration::ZERO);
+    assert!(guard_2.is_unchanged());
+    let guard_2 = guard_2.unchanged().unwrap();
+
+    drop(guard_1);
+    let guard_2 = guard_2.try_upgrade(Duration::ZERO);
+    assert!(guard_2.is_upgraded());
+    assert!(lock.try_read(Duration::ZERO).is_none());
+    assert!(lock.try_write_arc(Duration::ZERO).is_none());
+}
+
+#[test]
+fn guard_outlives_handle() {
+    let lock = Arc::new(ZLock::<_, WriteBiased>::new(String::from("foo")));
+    let mut guard = lock.write_arc();
+    drop(lock);
+
+    // the guard keeps the lock alive
+    guard.push_str("bar");
+    let guard = guard.downgrade();
+    assert_eq!("foobar", *guard);
+}
+
+#[test]
+fn release_on_worker_thread() {
+    __release_on_worker_thread::<ReadBiased>();
+    __release_on_worker_thread::<WriteBiased>();
+    __release_on_worker_thread::<ArrivalOrdered>();
+    __release_on_worker_thread::<Stochastic>();
+    __release_on_worker_thread::<PhaseFair>();
+    __release_on_worker_thread::<Prioritized>();
+}
+
+fn __release_on_worker_thread<M: ThreadAgnostic + 'static>() {
+    let lock = Arc::new(ZLock::<_, M>::new(0));
+    let pool = ThreadPool::new(1, Queue::Unbounded);
+
+    // hand a held write lock to the worker, which updates and releases it
+    let mut guard = lock.write_arc();
+    *guard = 42;
+    let released = pool.submitter().submit(move || {
+        *guard += 1;
+        drop(guard);
+    });
+    assert!(released.get().is_success());
+    assert_eq!(43, *lock.try_read(Duration::ZERO).unwrap());
+
+    // likewise for a held read lock
+    let guard = lock.read_arc();
+    let released = pool.submitter().submit(move || *guard);
+    assert_eq!(43, released.get().clone().into_option().unwrap());
+    assert!(lock.try_write(Duration::ZERO).is_some());
+}
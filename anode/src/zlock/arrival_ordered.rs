@@ -1,92 +1,83 @@
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
+use crate::backoff::ExpBackoff;
 use crate::deadline::Deadline;
-use crate::monitor::{Directive, Monitor, SpeculativeMonitor};
-use crate::zlock::Moderator;
+use crate::monitor::{Directive, Monitor, Notification, SpeculativeMonitor};
+use crate::zlock::{AsyncModerator, LockState, Moderator, ModeratorMonitor, ThreadAgnostic, Tickets, UpgradableModerator, Waiter, READ_QUEUE, WRITE_QUEUE};
+use crate::zlock::word::{Enlistment, LockWord, READER, UPGRADABLE, WRITER};
 
 #[derive(Debug)]
 pub struct ArrivalOrdered;
 
 pub struct ArrivalOrderedSync {
-    monitor: SpeculativeMonitor<ArrivalOrderedState>,
+    word: LockWord,
+    monitor: ModeratorMonitor<ArrivalOrderedState>,
 }
 
-#[derive(Debug)]
-struct ArrivalOrderedState {
-    readers: u32,
-    writer: bool,
-    next_ticket: u64,
-    serviced_tickets: u64
+impl ArrivalOrderedSync {
+    const fn new(backoff: &'static ExpBackoff) -> Self {
+        Self {
+            word: LockWord::new(),
+            monitor: SpeculativeMonitor::with_queues(ArrivalOrderedState {
+                upgrade_pending: false,
+                tickets: Tickets::INIT,
+            }, backoff),
+        }
+    }
 }
 
-impl ArrivalOrderedState {
-    #[inline]
-    fn take_ticket(&mut self) -> u64 {
-        let next = self.next_ticket;
-        self.next_ticket = next + 1;
-        next
-    }
+#[derive(Debug)]
+struct ArrivalOrderedState {
+    /// Set while the upgradable reader awaits its upgrade, holding back readers that arrive
+    /// in the meantime.
+    upgrade_pending: bool,
+    tickets: Tickets,
 }
 
 impl Moderator for ArrivalOrdered {
     type Sync = ArrivalOrderedSync;
+    type ModeratorConfig = &'static ExpBackoff;
+
+    const INIT: Self::Sync = ArrivalOrderedSync::new(&ExpBackoff::SLEEPY);
 
+    /// The backoff policy applies to the monitor's tracker.
     #[inline]
-    fn new() -> Self::Sync {
-        Self::Sync {
-            monitor: SpeculativeMonitor::new(ArrivalOrderedState { readers: 0, writer: false, next_ticket: 1, serviced_tickets: 0 }),
-        }
+    fn with_config(config: Self::ModeratorConfig) -> Self::Sync {
+        ArrivalOrderedSync::new(config)
     }
 
+    /// Without contenders, there are no outstanding tickets; hence, an uncontended reader
+    /// may bypass the ticketing system without jumping the queue.
     #[inline]
     fn try_read(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        let mut ticket = 0;
-        sync.monitor.enter(|state| {
-            if ticket == 0 {
-                ticket = state.take_ticket();
-            }
-            if !acquired && !state.writer && state.serviced_tickets >= ticket - 1 {
-                acquired = true;
-                state.readers += 1;
-                state.serviced_tickets += 1;
-            }
-
-            if acquired {
-                Directive::NotifyAll
-            } else {
-                Directive::Wait(deadline.remaining())
-            }
-        });
+        Self::try_read_until(sync, Deadline::lazy_after(duration))
+    }
 
-        if !acquired {
-            let mut inc_serviced = false;
-            sync.monitor.enter(|state| {
-                if !inc_serviced {
-                    inc_serviced = true;
-                    state.serviced_tickets += 1;
-                }
-                Directive::NotifyAll
-            });
+    #[inline]
+    fn try_read_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_read() {
+            return true;
         }
 
-        acquired
+        let mut waiter = ReadWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
     }
 
     #[inline]
     fn read_unlock(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers > 0, "readers: {}", state.readers);
-                debug_assert!(!state.writer);
-
-                released = true;
-                state.readers -= 1;
-            }
+        if sync.word.try_fast_read_unlock() {
+            return;
+        }
+
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() > 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(!sync.word.load().writer());
 
-            match state.readers {
-                0 | 1 => Directive::NotifyAll,
+            // readers are never held back by other readers
+            match sync.word.shift(READER, 0).readers() {
+                0 | 1 => Directive::NotifyAndReturn(Notification::all(WRITE_QUEUE)),
                 _ => Directive::Return
             }
         });
@@ -94,93 +85,381 @@ impl Moderator for ArrivalOrdered {
 
     #[inline]
     fn try_write(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        let mut ticket = 0;
-        sync.monitor.enter(|state| {
-            if ticket == 0 {
-                ticket = state.take_ticket();
-            }
-            if !acquired && state.readers == 0 && !state.writer && state.serviced_tickets >= ticket - 1 {
-                acquired = true;
-                state.writer = true;
-                state.serviced_tickets += 1;
-            }
-
-            if acquired {
-                Directive::NotifyAll
-            } else {
-                Directive::Wait(deadline.remaining())
-            }
-        });
+        Self::try_write_until(sync, Deadline::lazy_after(duration))
+    }
 
-        if !acquired {
-            let mut inc_serviced = false;
-            sync.monitor.enter(|state| {
-                if !inc_serviced {
-                    inc_serviced = true;
-                    state.serviced_tickets += 1;
-                }
-                Directive::NotifyAll
-            });
+    #[inline]
+    fn try_write_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_write() {
+            return true;
         }
 
-        acquired
+        let mut waiter = WriteWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
     }
 
     #[inline]
     fn write_unlock(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers == 0, "readers: {}", state.readers);
-                debug_assert!(state.writer);
-
-                released = true;
-                state.writer = false;
-            }
+        if sync.word.try_fast_write_unlock() {
+            return;
+        }
 
-            Directive::NotifyAll
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, 0);
+            Directive::NotifyAllAndReturn
         });
     }
 
     fn downgrade(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers == 0, "readers: {}", state.readers);
-                debug_assert!(state.writer);
-
-                released = true;
-                state.writer = false;
-                state.readers = 1;
-            }
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
 
-            Directive::NotifyAll
+            sync.word.shift(WRITER, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
         });
     }
 
     fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        sync.monitor.enter(|state| {
-            if !acquired && state.readers == 1 {
-                debug_assert!(!state.writer);
-
-                acquired = true;
-                state.readers = 0;
-                state.writer = true;
+        Self::try_upgrade_until(sync, Deadline::lazy_after(duration))
+    }
+
+    fn try_upgrade_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        let mut waiter = UpgradeWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn inspect(sync: &Self::Sync) -> LockState {
+        sync.monitor.compute(|state| LockState {
+            writer_pending: state.upgrade_pending,
+            tickets: Some(state.tickets),
+            ..sync.word.load().inspect()
+        })
+    }
+}
+
+unsafe impl ThreadAgnostic for ArrivalOrdered {}
+
+impl UpgradableModerator for ArrivalOrdered {
+    #[inline]
+    fn try_read_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = ReadWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn upgradable_unlock(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, 0);
+            Directive::NotifyAllAndReturn
+        });
+    }
+
+    fn try_upgrade_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = UpgradeWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn downgrade_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+
+    fn downgrade_to_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, UPGRADABLE);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+}
+
+impl AsyncModerator for ArrivalOrdered {
+    type ReadWaiter = ReadWaiter;
+    type WriteWaiter = WriteWaiter;
+    type UpgradeWaiter = UpgradeWaiter;
+
+    #[inline]
+    fn forget(sync: &Self::Sync, waker: &Waker) {
+        sync.monitor.forget(waker);
+    }
+}
+
+/// Services the ticket of a reader or writer that gave up waiting, so that subsequent
+/// ticket holders are not held up.
+#[inline]
+fn forfeit_ticket(sync: &ArrivalOrderedSync) {
+    sync.monitor.enter(|state| {
+        state.tickets.service();
+        Directive::NotifyAllAndReturn
+    });
+}
+
+/// Clears the `upgrade_pending` flag raised by an upgradable reader that gave up on its
+/// upgrade, releasing the readers that were held back by it.
+#[inline]
+fn withdraw_upgrade(sync: &ArrivalOrderedSync) {
+    sync.monitor.enter(|state| {
+        state.upgrade_pending = false;
+        Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+    });
+}
+
+pub struct ReadWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    upgradable: bool,
+    ticket: u64,
+    enlistment: Enlistment,
+}
+
+impl ReadWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, upgradable: false, ticket: 0, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, upgradable: true, ticket: 0, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut ArrivalOrderedState) -> Directive {
+        if self.ticket == 0 {
+            self.enlistment.enlist(word);
+            self.ticket = state.tickets.take();
+        }
+        if !self.acquired {
+            let word_state = word.load();
+            if !word_state.writer()
+                && !state.upgrade_pending
+                && (!self.upgradable || !word_state.upgradable())
+                && state.tickets.is_turn(self.ticket)
+            {
+                self.acquired = true;
+                self.enlistment.acquire(word, 0, if self.upgradable { UPGRADABLE } else { READER });
+                state.tickets.service();
             }
+        }
 
-            if acquired {
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
+        // only a reader may follow a reader
+        if self.acquired {
+            Directive::Notify(Notification::all(READ_QUEUE))
+        } else {
+            Directive::WaitOn(READ_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &ArrivalOrderedSync) {
+        if !self.acquired && self.ticket != 0 {
+            self.ticket = 0;
+            forfeit_ticket(sync);
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for ReadWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<ArrivalOrderedSync> for ReadWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &ArrivalOrderedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &ArrivalOrderedSync) {
+        self.conclude(sync);
+    }
+}
+
+pub struct WriteWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    ticket: u64,
+    enlistment: Enlistment,
+}
+
+impl WriteWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, ticket: 0, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut ArrivalOrderedState) -> Directive {
+        if self.ticket == 0 {
+            self.enlistment.enlist(word);
+            self.ticket = state.tickets.take();
+        }
+        if !self.acquired {
+            let word_state = word.load();
+            if word_state.readers() == 0
+                && !word_state.writer()
+                && !word_state.upgradable()
+                && state.tickets.is_turn(self.ticket)
+            {
+                self.acquired = true;
+                self.enlistment.acquire(word, 0, WRITER);
+                state.tickets.service();
             }
-        });
-        acquired
+        }
+
+        // the successor is held back by the writer until it releases the lock
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &ArrivalOrderedSync) {
+        if !self.acquired && self.ticket != 0 {
+            self.ticket = 0;
+            forfeit_ticket(sync);
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for WriteWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<ArrivalOrderedSync> for WriteWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &ArrivalOrderedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &ArrivalOrderedSync) {
+        self.conclude(sync);
+    }
+}
+
+pub struct UpgradeWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    self_upgrade_pending: bool,
+    enlistment: Enlistment,
+
+    /// Whether the lock being upgraded is an upgradable read lock, as opposed to an
+    /// ordinary read lock.
+    upgradable: bool,
+}
+
+impl UpgradeWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_upgrade_pending: false, enlistment: Enlistment::default(), upgradable: false }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_upgrade_pending: false, enlistment: Enlistment::default(), upgradable: true }
+    }
+
+    /// An upgradable reader need only wait for the ordinary readers to drain, whereas an
+    /// ordinary reader must be the sole holder of the lock.
+    #[inline]
+    fn can_acquire(&self, readers: u32, upgradable: bool) -> bool {
+        if self.upgradable {
+            debug_assert!(upgradable);
+            readers == 0
+        } else {
+            readers == 1 && !upgradable
+        }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut ArrivalOrderedState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let word_state = word.load();
+            debug_assert!(!word_state.writer());
+
+            if self.can_acquire(word_state.readers(), word_state.upgradable()) {
+                self.acquired = true;
+                if self.upgradable {
+                    self.self_upgrade_pending = false;
+                    state.upgrade_pending = false;
+                }
+                self.enlistment.acquire(word, if self.upgradable { UPGRADABLE } else { READER }, WRITER);
+            } else if self.upgradable && !self.self_upgrade_pending {
+                // readers that arrive after the upgrade request must wait their turn
+                self.self_upgrade_pending = true;
+                state.upgrade_pending = true;
+            }
+        }
+
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &ArrivalOrderedSync) {
+        if self.self_upgrade_pending {
+            self.self_upgrade_pending = false;
+            withdraw_upgrade(sync);
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for UpgradeWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<ArrivalOrderedSync> for UpgradeWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &ArrivalOrderedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &ArrivalOrderedSync) {
+        self.conclude(sync);
     }
 }
 
 #[cfg(test)]
-mod tests;
\ No newline at end of file
+mod tests;
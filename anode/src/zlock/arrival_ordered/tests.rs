@@ -14,8 +14,9 @@ fn readers_do_not_block_without_writer() {
     let _guard_1 = lock.read();
     let _guard_2 = lock.read();
 
-    assert_eq!(3, lock.next_ticket());
-    assert_eq!(2, lock.serviced_tickets());
+    // uncontended readers take the fast path, bypassing the ticketing system
+    assert_eq!(1, lock.next_ticket());
+    assert_eq!(0, lock.serviced_tickets());
 }
 
 #[test]
@@ -23,9 +24,9 @@ fn interleaving_writer_blocks_reader() {
     let lock = Arc::new(ZLock::<_, ArrivalOrdered>::new(0));
     let guard_1 = lock.read();
 
-    // after acquiring the lock, both the ticket and the service count should increase
-    assert_eq!(2, lock.next_ticket());
-    assert_eq!(1, lock.serviced_tickets());
+    // an uncontended reader does not take a ticket
+    assert_eq!(1, lock.next_ticket());
+    assert_eq!(0, lock.serviced_tickets());
 
     let t_2 = ThreadPool::new(1, Queue::Unbounded);
 
@@ -37,26 +38,26 @@ fn interleaving_writer_blocks_reader() {
     };
 
     // t_2 will block trying to acquire a write lock; it should increase the next_ticket count
-    lock.wait_for_next_ticket(Ordering::is_ge, 3, LONG_WAIT).unwrap();
+    lock.wait_for_next_ticket(Ordering::is_ge, 2, LONG_WAIT).unwrap();
 
     // the serviced_ticket count should remain
-    assert_eq!(1, lock.serviced_tickets());
+    assert_eq!(0, lock.serviced_tickets());
 
     // should not be able to read-acquire
     let guard_3 = lock.try_read(Duration::ZERO);
     assert!(guard_3.is_none());
 
     // but timing in read-acquire should bump the serviced_tickets count
-    assert_eq!(4, lock.next_ticket());
-    assert_eq!(2, lock.serviced_tickets());
+    assert_eq!(3, lock.next_ticket());
+    assert_eq!(1, lock.serviced_tickets());
 
     // read-release
     drop(guard_1);
 
     // t_2 should eventually succeed
     assert!(t_2_write.get().is_success());
-    assert_eq!(4, lock.next_ticket());
-    assert_eq!(3, lock.serviced_tickets());
+    assert_eq!(3, lock.next_ticket());
+    assert_eq!(2, lock.serviced_tickets());
 
     // main can now read-acquire
     let guard_4 = lock.try_read(Duration::ZERO);
@@ -69,9 +70,9 @@ fn queuing_order() {
     let lock = Arc::new(ZLock::<_, ArrivalOrdered>::new(0));
     let guard_1 = lock.read();
 
-    // after acquiring the lock, both the ticket and the service count should increase
-    assert_eq!(2, lock.next_ticket());
-    assert_eq!(1, lock.serviced_tickets());
+    // an uncontended reader does not take a ticket
+    assert_eq!(1, lock.next_ticket());
+    assert_eq!(0, lock.serviced_tickets());
 
     let t_2 = ThreadPool::new(1, Queue::Unbounded);
     let t_3 = ThreadPool::new(1, Queue::Unbounded);
@@ -90,8 +91,8 @@ fn queuing_order() {
     };
 
     // t_2 will block trying to acquire a write lock; it should increase the next_ticket count
-    lock.wait_for_next_ticket(Ordering::is_ge, 3, LONG_WAIT).unwrap();
-    assert_eq!(1, lock.serviced_tickets());
+    lock.wait_for_next_ticket(Ordering::is_ge, 2, LONG_WAIT).unwrap();
+    assert_eq!(0, lock.serviced_tickets());
 
     let t_3_read = {
         let lock = lock.clone();
@@ -101,8 +102,8 @@ fn queuing_order() {
     };
 
     // t_3 will block trying to acquire a read lock; it should increase the next_ticket count
-    lock.wait_for_next_ticket(Ordering::is_ge, 4, LONG_WAIT).unwrap();
-    assert_eq!(1, lock.serviced_tickets());
+    lock.wait_for_next_ticket(Ordering::is_ge, 3, LONG_WAIT).unwrap();
+    assert_eq!(0, lock.serviced_tickets());
 
     let t_4_read_release = Arc::new(Barrier::new(2));
     let t_4_read = {
@@ -116,8 +117,8 @@ fn queuing_order() {
     };
 
     // t_4 will block trying to acquire a read lock; it should increase the next_ticket count
-    lock.wait_for_next_ticket(Ordering::is_ge, 5, LONG_WAIT).unwrap();
-    assert_eq!(1, lock.serviced_tickets());
+    lock.wait_for_next_ticket(Ordering::is_ge, 4, LONG_WAIT).unwrap();
+    assert_eq!(0, lock.serviced_tickets());
 
     let t_5_write = {
         let lock = lock.clone();
@@ -127,8 +128,8 @@ fn queuing_order() {
     };
 
     // t_5 will block trying to acquire a read lock; it should increase the next_ticket count
-    lock.wait_for_next_ticket(Ordering::is_ge, 6, LONG_WAIT).unwrap();
-    assert_eq!(1, lock.serviced_tickets());
+    lock.wait_for_next_ticket(Ordering::is_ge, 5, LONG_WAIT).unwrap();
+    assert_eq!(0, lock.serviced_tickets());
 
     // t_2-5 are definitely blocked
     assert!(!t_2_write.is_complete());
@@ -159,16 +160,16 @@ fn queuing_order() {
 
     // this unblocks t_5
     assert!(t_5_write.get().is_success());
-    assert_eq!(5, lock.serviced_tickets());
+    assert_eq!(4, lock.serviced_tickets());
 }
 
 impl<T> ZLock<T, ArrivalOrdered> {
     fn next_ticket(&self) -> u64 {
-        self.sync.monitor.compute(|state| state.next_ticket)
+        self.sync.monitor.compute(|state| state.tickets.next)
     }
 
     fn serviced_tickets(&self) -> u64 {
-        self.sync.monitor.compute(|state| state.serviced_tickets)
+        self.sync.monitor.compute(|state| state.tickets.serviced)
     }
 
     fn wait_for_next_ticket(&self, cmp: impl FnMut(Ordering) -> bool, target: u64, duration: Duration) -> WaitResult {
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::mem;
+use std::time::Duration;
+use crate::deadline::Deadline;
+use crate::monitor::{Directive, Monitor, SpeculativeMonitor};
+use crate::wait::WaitResult;
+use crate::zlock::{LockWriteGuard, Moderator};
+
+/// A condition variable for use with [`ZLock`](crate::zlock::ZLock) write guards, under
+/// any [`Moderator`].
+///
+/// Waiting releases the write lock and blocks the thread until the condition variable is
+/// notified, reacquiring the write lock before returning. As with [`std::sync::Condvar`],
+/// a waiter may occasionally wake without having been notified, so the awaited condition
+/// should be rechecked upon return; [`wait_while`](Self::wait_while) does so.
+///
+/// A notifier should alter the awaited state while holding the write lock, so that the
+/// change cannot fall between a waiter checking the condition and beginning its wait.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use anode::zlock::{ReadBiased, ZCondvar, ZLock};
+///
+/// let pair = Arc::new((ZLock::<_, ReadBiased>::new(false), ZCondvar::new()));
+/// let notifier = {
+///     let pair = pair.clone();
+///     thread::spawn(move || {
+///         let (lock, cvar) = &*pair;
+///         *lock.write() = true;
+///         cvar.notify_one();
+///     })
+/// };
+///
+/// let (lock, cvar) = &*pair;
+/// let mut guard = lock.write();
+/// cvar.wait_while(&mut guard, |started| !*started);
+/// assert!(*guard);
+/// # drop(guard);
+/// # notifier.join().unwrap();
+/// ```
+pub struct ZCondvar {
+    /// Advanced by every notification, which allows a waiter to tell whether a
+    /// notification has occurred since it released the lock.
+    generation: SpeculativeMonitor<u64>,
+}
+
+#[allow(clippy::result_unit_err)]
+impl ZCondvar {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            generation: SpeculativeMonitor::new(0),
+        }
+    }
+
+    /// Releases the write lock and blocks until notified, reacquiring the lock before
+    /// returning.
+    #[inline]
+    pub fn wait<T: ?Sized, M: Moderator>(&self, guard: &mut LockWriteGuard<'_, T, M>) {
+        let _ = self.__wait(guard, Deadline::Forever);
+    }
+
+    /// Releases the write lock and blocks until notified or until the given duration
+    /// elapses, reacquiring the lock before returning. Returns `Err` if the wait timed out.
+    #[inline]
+    pub fn wait_timeout<T: ?Sized, M: Moderator>(&self, guard: &mut LockWriteGuard<'_, T, M>, duration: Duration) -> WaitResult {
+        self.__wait(guard, Deadline::lazy_after(duration))
+    }
+
+    /// Waits repeatedly for as long as the given condition holds, evaluating it under the
+    /// write lock.
+    #[inline]
+    pub fn wait_while<T: ?Sized, M: Moderator>(&self, guard: &mut LockWriteGuard<'_, T, M>, condition: impl FnMut(&mut T) -> bool) {
+        let _ = self.__wait_while(guard, Deadline::Forever, condition);
+    }
+
+    /// Waits repeatedly for as long as the given condition holds, evaluating it under the
+    /// write lock, for at most the given duration. Returns `Err` if the condition still
+    /// held when the duration elapsed.
+    #[inline]
+    pub fn wait_timeout_while<T: ?Sized, M: Moderator>(
+        &self,
+        guard: &mut LockWriteGuard<'_, T, M>,
+        duration: Duration,
+        condition: impl FnMut(&mut T) -> bool,
+    ) -> WaitResult {
+        self.__wait_while(guard, Deadline::lazy_after(duration), condition)
+    }
+
+    /// Wakes at least one of the waiting threads.
+    #[inline]
+    pub fn notify_one(&self) {
+        self.__notify(false);
+    }
+
+    /// Wakes all waiting threads.
+    #[inline]
+    pub fn notify_all(&self) {
+        self.__notify(true);
+    }
+
+    #[inline]
+    fn __notify(&self, all: bool) {
+        self.generation.enter(|generation| {
+            // the closure may be evaluated more than once; it suffices that the
+            // generation changes
+            *generation = generation.wrapping_add(1);
+            if all {
+                Directive::NotifyAll
+            } else {
+                Directive::NotifyOne
+            }
+        });
+    }
+
+    #[inline]
+    fn __wait_while<T: ?Sized, M: Moderator>(
+        &self,
+        guard: &mut LockWriteGuard<'_, T, M>,
+        mut deadline: Deadline,
+        mut condition: impl FnMut(&mut T) -> bool,
+    ) -> WaitResult {
+        while condition(guard) {
+            // a lazy deadline is resolved once, so that successive waits share it
+            if self.__wait(guard, deadline.resolved()).is_err() {
+                return if condition(guard) { Err(()) } else { Ok(()) };
+            }
+        }
+        Ok(())
+    }
+
+    fn __wait<T: ?Sized, M: Moderator>(&self, guard: &mut LockWriteGuard<'_, T, M>, deadline: Deadline) -> WaitResult {
+        // the generation is sampled under the write lock, so that a notification that
+        // follows the release of the lock is never missed
+        let generation = self.generation.compute(|generation| *generation);
+        let lock = guard.lock;
+        lock.write_unlock();
+
+        let notified = self.generation.enter_until(deadline, |current| {
+            if *current == generation {
+                Directive::Wait(Duration::MAX)
+            } else {
+                Directive::Return
+            }
+        }).is_returned();
+
+        // the guard continues to represent the reacquired lock
+        mem::forget(lock.write());
+        if notified {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl Default for ZCondvar {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for ZCondvar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZCondvar").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use crate::test_utils::{LONG_WAIT, SHORT_WAIT};
+use crate::zlock::{ArrivalOrdered, LegacyReadBiased, Moderator, PhaseFair, Prioritized, ReadBiased, Stochastic, WriteBiased, ZCondvar, ZLock};
+
+#[test]
+fn wait_for_notify() {
+    __wait_for_notify::<ReadBiased>();
+    __wait_for_notify::<WriteBiased>();
+    __wait_for_notify::<ArrivalOrdered>();
+    __wait_for_notify::<Stochastic>();
+    __wait_for_notify::<PhaseFair>();
+    __wait_for_notify::<Prioritized>();
+    __wait_for_notify::<LegacyReadBiased>();
+}
+
+/// Two threads take turns incrementing a counter, each waiting for the other's turn. The
+/// exchange can only progress if waiting releases the lock.
+fn __wait_for_notify<M: Moderator + 'static>() {
+    const TURNS: u32 = 100;
+    let pair = Arc::new((ZLock::<_, M>::new(0u32), ZCondvar::new()));
+
+    let threads = (0..2).map(|parity| {
+        let pair = pair.clone();
+        thread::spawn(move || {
+            let (lock, cvar) = &*pair;
+            for _ in 0..TURNS / 2 {
+                let mut guard = lock.write();
+                cvar.wait_while(&mut guard, |turn| *turn % 2 != parity);
+                *guard += 1;
+                drop(guard);
+                cvar.notify_all();
+            }
+        })
+    }).collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(TURNS, *pair.0.read());
+}
+
+#[test]
+fn wait_timeout() {
+    let lock = ZLock::<_, WriteBiased>::new(42);
+    let cvar = ZCondvar::new();
+    let mut guard = lock.write();
+
+    assert!(cvar.wait_timeout(&mut guard, SHORT_WAIT).is_err());
+    assert!(cvar.wait_timeout(&mut guard, Duration::ZERO).is_err());
+
+    // the lock is held again upon returning
+    assert_eq!(42, *guard);
+    assert!(lock.try_read(Duration::ZERO).is_none());
+    *guard = 69;
+    drop(guard);
+    assert_eq!(69, *lock.try_read(Duration::ZERO).unwrap());
+}
+
+#[test]
+fn wait_timeout_while() {
+    let pair = Arc::new((ZLock::<_, ArrivalOrdered>::new(false), ZCondvar::new()));
+    let (lock, cvar) = &*pair;
+    let mut guard = lock.write();
+    assert!(cvar.wait_timeout_while(&mut guard, SHORT_WAIT, |ready| !*ready).is_err());
+
+    // a condition that is already satisfied does not wait
+    *guard = true;
+    assert!(cvar.wait_timeout_while(&mut guard, Duration::ZERO, |ready| !*ready).is_ok());
+    *guard = false;
+
+    let notifier = {
+        let pair = pair.clone();
+        thread::spawn(move || {
+            let (lock, cvar) = &*pair;
+            *lock.write() = true;
+            cvar.notify_one();
+        })
+    };
+    assert!(cvar.wait_timeout_while(&mut guard, LONG_WAIT, |ready| !*ready).is_ok());
+    assert!(*guard);
+    drop(guard);
+    notifier.join().unwrap();
+}
+
+#[test]
+fn notify_without_waiters() {
+    let cvar = ZCondvar::default();
+    cvar.notify_one();
+    cvar.notify_all();
+    assert_eq!("ZCondvar { .. }", format!("{cvar:?}"));
+}
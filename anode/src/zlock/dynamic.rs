@@ -0,0 +1,251 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+use std::time::Duration;
+use crate::deadline::Deadline;
+use crate::zlock::locklike::ModeratorKind;
+use crate::zlock::{ArrivalOrdered, LockReadGuard, LockState, LockWriteGuard, PhaseFair, Prioritized, ReadBiased, Sharded, Stochastic, UpgradeOutcome, WriteBiased, ZLock};
+
+/// Expands to a `match` over the moderators of a dynamic lock or guard, binding the
+/// underlying lock or guard to `$inner`.
+macro_rules! dispatch {
+    ($value:expr, $source:ident, $inner:ident => $body:expr) => {
+        match $value {
+            $source::ReadBiased($inner) => $body,
+            $source::WriteBiased($inner) => $body,
+            $source::ArrivalOrdered($inner) => $body,
+            $source::Stochastic($inner) => $body,
+            $source::PhaseFair($inner) => $body,
+            $source::Prioritized($inner) => $body,
+            $source::Sharded($inner) => $body,
+        }
+    };
+}
+
+/// A [`ZLock`] whose moderator is chosen at runtime, from a [`ModeratorKind`].
+///
+/// The lock and its guards are enums over the moderators, so that every operation
+/// dispatches with a `match` rather than through a vtable, and no guard is boxed.
+///
+/// # Examples
+/// ```
+/// use anode::zlock::DynZLock;
+///
+/// let kind = "arrival_ordered".parse().unwrap();
+/// let lock = DynZLock::new(42, kind);
+/// assert_eq!(kind, lock.kind());
+///
+/// let mut guard = lock.read().upgrade();
+/// *guard += 1;
+/// drop(guard);
+/// assert_eq!(43, lock.into_inner());
+/// ```
+pub enum DynZLock<T> {
+    ReadBiased(ZLock<T, ReadBiased>),
+    WriteBiased(ZLock<T, WriteBiased>),
+    ArrivalOrdered(ZLock<T, ArrivalOrdered>),
+    Stochastic(ZLock<T, Stochastic>),
+    PhaseFair(ZLock<T, PhaseFair>),
+    Prioritized(ZLock<T, Prioritized>),
+    /// Boxed, as the reader slots of the [`Sharded`] moderator would otherwise inflate
+    /// the lock under every other moderator.
+    Sharded(Box<ZLock<T, Sharded>>),
+}
+
+impl<T> DynZLock<T> {
+    /// Creates a lock with the moderator of the given kind, under its default
+    /// configuration.
+    pub fn new(t: T, kind: ModeratorKind) -> Self {
+        match kind {
+            ModeratorKind::ReadBiased => DynZLock::ReadBiased(ZLock::new(t)),
+            ModeratorKind::WriteBiased => DynZLock::WriteBiased(ZLock::new(t)),
+            ModeratorKind::ArrivalOrdered => DynZLock::ArrivalOrdered(ZLock::new(t)),
+            ModeratorKind::Stochastic => DynZLock::Stochastic(ZLock::new(t)),
+            ModeratorKind::PhaseFair => DynZLock::PhaseFair(ZLock::new(t)),
+            ModeratorKind::Prioritized => DynZLock::Prioritized(ZLock::new(t)),
+            ModeratorKind::Sharded => DynZLock::Sharded(Box::new(ZLock::new(t))),
+        }
+    }
+
+    pub fn kind(&self) -> ModeratorKind {
+        match self {
+            DynZLock::ReadBiased(_) => ModeratorKind::ReadBiased,
+            DynZLock::WriteBiased(_) => ModeratorKind::WriteBiased,
+            DynZLock::ArrivalOrdered(_) => ModeratorKind::ArrivalOrdered,
+            DynZLock::Stochastic(_) => ModeratorKind::Stochastic,
+            DynZLock::PhaseFair(_) => ModeratorKind::PhaseFair,
+            DynZLock::Prioritized(_) => ModeratorKind::Prioritized,
+            DynZLock::Sharded(_) => ModeratorKind::Sharded,
+        }
+    }
+
+    #[inline]
+    pub fn read(&self) -> DynZLockReadGuard<'_, T> {
+        dispatch!(self, DynZLock, lock => DynZLockReadGuard::from(lock.read()))
+    }
+
+    #[inline]
+    pub fn try_read(&self, duration: Duration) -> Option<DynZLockReadGuard<'_, T>> {
+        dispatch!(self, DynZLock, lock => lock.try_read(duration).map(DynZLockReadGuard::from))
+    }
+
+    #[inline]
+    pub fn try_read_until(&self, deadline: impl Into<Deadline>) -> Option<DynZLockReadGuard<'_, T>> {
+        dispatch!(self, DynZLock, lock => lock.try_read_until(deadline).map(DynZLockReadGuard::from))
+    }
+
+    #[inline]
+    pub fn write(&self) -> DynZLockWriteGuard<'_, T> {
+        dispatch!(self, DynZLock, lock => DynZLockWriteGuard::from(lock.write()))
+    }
+
+    #[inline]
+    pub fn try_write(&self, duration: Duration) -> Option<DynZLockWriteGuard<'_, T>> {
+        dispatch!(self, DynZLock, lock => lock.try_write(duration).map(DynZLockWriteGuard::from))
+    }
+
+    #[inline]
+    pub fn try_write_until(&self, deadline: impl Into<Deadline>) -> Option<DynZLockWriteGuard<'_, T>> {
+        dispatch!(self, DynZLock, lock => lock.try_write_until(deadline).map(DynZLockWriteGuard::from))
+    }
+
+    /// Returns a snapshot of the lock's state, as per [`ZLock::state`].
+    #[inline]
+    pub fn state(&self) -> LockState {
+        dispatch!(self, DynZLock, lock => lock.state())
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        dispatch!(self, DynZLock, lock => lock.get_mut())
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        dispatch!(self, DynZLock, lock => lock.into_inner())
+    }
+}
+
+pub enum DynZLockReadGuard<'a, T> {
+    ReadBiased(LockReadGuard<'a, T, ReadBiased>),
+    WriteBiased(LockReadGuard<'a, T, WriteBiased>),
+    ArrivalOrdered(LockReadGuard<'a, T, ArrivalOrdered>),
+    Stochastic(LockReadGuard<'a, T, Stochastic>),
+    PhaseFair(LockReadGuard<'a, T, PhaseFair>),
+    Prioritized(LockReadGuard<'a, T, Prioritized>),
+    Sharded(LockReadGuard<'a, T, Sharded>),
+}
+
+impl<'a, T> DynZLockReadGuard<'a, T> {
+    #[inline]
+    pub fn upgrade(self) -> DynZLockWriteGuard<'a, T> {
+        dispatch!(self, DynZLockReadGuard, guard => DynZLockWriteGuard::from(guard.upgrade()))
+    }
+
+    #[inline]
+    pub fn try_upgrade(self, duration: Duration) -> DynZLockUpgradeOutcome<'a, T> {
+        dispatch!(self, DynZLockReadGuard, guard => {
+            guard.try_upgrade(duration).map(DynZLockWriteGuard::from, DynZLockReadGuard::from)
+        })
+    }
+
+    #[inline]
+    pub fn try_upgrade_until(self, deadline: impl Into<Deadline>) -> DynZLockUpgradeOutcome<'a, T> {
+        dispatch!(self, DynZLockReadGuard, guard => {
+            guard.try_upgrade_until(deadline).map(DynZLockWriteGuard::from, DynZLockReadGuard::from)
+        })
+    }
+}
+
+impl<T> Deref for DynZLockReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        dispatch!(self, DynZLockReadGuard, guard => guard)
+    }
+}
+
+pub enum DynZLockWriteGuard<'a, T> {
+    ReadBiased(LockWriteGuard<'a, T, ReadBiased>),
+    WriteBiased(LockWriteGuard<'a, T, WriteBiased>),
+    ArrivalOrdered(LockWriteGuard<'a, T, ArrivalOrdered>),
+    Stochastic(LockWriteGuard<'a, T, Stochastic>),
+    PhaseFair(LockWriteGuard<'a, T, PhaseFair>),
+    Prioritized(LockWriteGuard<'a, T, Prioritized>),
+    Sharded(LockWriteGuard<'a, T, Sharded>),
+}
+
+impl<'a, T> DynZLockWriteGuard<'a, T> {
+    #[inline]
+    pub fn downgrade(self) -> DynZLockReadGuard<'a, T> {
+        dispatch!(self, DynZLockWriteGuard, guard => DynZLockReadGuard::from(guard.downgrade()))
+    }
+}
+
+impl<T> Deref for DynZLockWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        dispatch!(self, DynZLockWriteGuard, guard => guard)
+    }
+}
+
+impl<T> DerefMut for DynZLockWriteGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        dispatch!(self, DynZLockWriteGuard, guard => guard)
+    }
+}
+
+pub type DynZLockUpgradeOutcome<'a, T> = UpgradeOutcome<DynZLockWriteGuard<'a, T>, DynZLockReadGuard<'a, T>>;
+
+/// Conversions from the guards of each moderator, which allow a guard to be wrapped without
+/// naming its variant.
+macro_rules! impl_from_guard {
+    ($($moderator:ident),+) => {
+        $(
+            impl<'a, T> From<LockReadGuard<'a, T, $moderator>> for DynZLockReadGuard<'a, T> {
+                #[inline]
+                fn from(guard: LockReadGuard<'a, T, $moderator>) -> Self {
+                    DynZLockReadGuard::$moderator(guard)
+                }
+            }
+
+            impl<'a, T> From<LockWriteGuard<'a, T, $moderator>> for DynZLockWriteGuard<'a, T> {
+                #[inline]
+                fn from(guard: LockWriteGuard<'a, T, $moderator>) -> Self {
+                    DynZLockWriteGuard::$moderator(guard)
+                }
+            }
+        )+
+    };
+}
+
+impl_from_guard!(ReadBiased, WriteBiased, ArrivalOrdered, Stochastic, PhaseFair, Prioritized, Sharded);
+
+impl<T: Debug> Debug for DynZLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lock: &dyn Debug = dispatch!(self, DynZLock, lock => lock);
+        f.debug_struct("DynZLock")
+            .field("kind", &self.kind())
+            .field("lock", lock)
+            .finish()
+    }
+}
+
+impl<T: Debug> Debug for DynZLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: Debug> Debug for DynZLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use crate::deadline::Deadline;
+use crate::zlock::DynZLock;
+use crate::zlock::locklike::{ModeratorKind, MODERATOR_KINDS};
+
+#[test]
+fn parse_kind() {
+    for kind in MODERATOR_KINDS {
+        assert_eq!(kind, kind.name().parse().unwrap());
+        assert_eq!(kind.name(), kind.to_string());
+    }
+    assert_eq!(ModeratorKind::ReadBiased, "read_biased".parse().unwrap());
+    assert_eq!(ModeratorKind::ArrivalOrdered, "arrival_ordered".parse().unwrap());
+
+    let err = "ReadBiased".parse::<ModeratorKind>().unwrap_err();
+    assert!(err.to_string().starts_with("unknown moderator 'ReadBiased'; expected one of: read_biased, write_biased"), "{err}");
+}
+
+#[test]
+fn cycle() {
+    for kind in MODERATOR_KINDS {
+        let mut lock = DynZLock::new(0, kind);
+        assert_eq!(kind, lock.kind());
+
+        let guard_1 = lock.read();
+        let guard_2 = lock.try_read(Duration::ZERO).unwrap();
+        assert_eq!(2, lock.state().readers);
+        assert!(lock.try_write(Duration::ZERO).is_none());
+
+        let guard_1 = guard_1.try_upgrade(Duration::ZERO).unchanged().unwrap();
+        drop(guard_2);
+        let mut guard = guard_1.try_upgrade_until(Deadline::Forever).upgraded().unwrap();
+        *guard = 42;
+        assert!(lock.state().writer);
+
+        let guard = guard.downgrade();
+        assert_eq!(42, *guard);
+        assert!(lock.try_read_until(Deadline::after(Duration::ZERO)).is_some());
+        drop(guard);
+
+        *lock.write() += 1;
+        *lock.get_mut() += 1;
+        assert_eq!(44, lock.into_inner());
+    }
+}
+
+#[test]
+fn contended() {
+    const THREADS: usize = 4;
+    const INCREMENTS: usize = 100;
+    for kind in MODERATOR_KINDS {
+        let lock = Arc::new(DynZLock::new(0, kind));
+        let threads = (0..THREADS)
+            .map(|_| {
+                let lock = lock.clone();
+                thread::spawn(move || {
+                    for _ in 0..INCREMENTS {
+                        let before = *lock.read();
+                        let mut guard = lock.write();
+                        assert!(*guard >= before);
+                        *guard += 1;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(THREADS * INCREMENTS, *lock.read());
+    }
+}
+
+#[test]
+fn debug() {
+    let lock = DynZLock::new(42, ModeratorKind::WriteBiased);
+    assert!(format!("{lock:?}").starts_with("DynZLock { kind: WriteBiased, lock: ZLock { data: 42"), "{lock:?}");
+    assert_eq!("42", format!("{:?}", lock.read()));
+}
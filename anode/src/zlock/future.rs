@@ -0,0 +1,196 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use crate::zlock::{AsyncModerator, LockReadGuard, LockWriteGuard, Waiter, ZLock};
+
+impl<T: ?Sized, M: AsyncModerator> ZLock<T, M> {
+    /// Acquires a read lock asynchronously, resolving to a [`LockReadGuard`] once the
+    /// moderator admits the reader.
+    ///
+    /// Dropping the returned future before it resolves abandons the acquisition without
+    /// affecting other contenders.
+    #[inline]
+    pub fn read_async(&self) -> ReadFuture<'_, T, M> {
+        ReadFuture {
+            lock: self,
+            waiter: M::ReadWaiter::default(),
+            waker: None,
+            completed: false,
+        }
+    }
+
+    /// Acquires a write lock asynchronously, resolving to a [`LockWriteGuard`] once the
+    /// moderator admits the writer.
+    ///
+    /// Dropping the returned future before it resolves abandons the acquisition without
+    /// affecting other contenders.
+    #[inline]
+    pub fn write_async(&self) -> WriteFuture<'_, T, M> {
+        WriteFuture {
+            lock: self,
+            waiter: M::WriteWaiter::default(),
+            waker: None,
+            completed: false,
+        }
+    }
+}
+
+impl<'a, T: ?Sized, M: AsyncModerator> LockReadGuard<'a, T, M> {
+    /// Upgrades this read lock to a write lock asynchronously.
+    ///
+    /// The read lock is held for as long as the returned future is pending. Dropping the
+    /// future before it resolves releases the read lock.
+    #[inline]
+    pub fn upgrade_async(self) -> UpgradeFuture<'a, T, M> {
+        UpgradeFuture {
+            guard: Some(self),
+            waiter: M::UpgradeWaiter::default(),
+            waker: None,
+        }
+    }
+}
+
+pub struct ReadFuture<'a, T: ?Sized, M: AsyncModerator> {
+    lock: &'a ZLock<T, M>,
+    waiter: M::ReadWaiter,
+
+    /// The waker registered by the last pending poll.
+    waker: Option<Waker>,
+    completed: bool,
+}
+
+impl<'a, T: ?Sized, M: AsyncModerator> Future for ReadFuture<'a, T, M> {
+    type Output = LockReadGuard<'a, T, M>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        assert!(!this.completed, "future polled after completion");
+        this.lock.id.attempt(this.lock, Duration::MAX);
+        match this.waiter.poll_acquire(&this.lock.sync, cx) {
+            Poll::Ready(()) => {
+                this.completed = true;
+                this.lock.id.acquired(this.lock);
+                Poll::Ready(this.lock.read_guard())
+            }
+            Poll::Pending => {
+                remember(&mut this.waker, cx);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T: ?Sized, M: AsyncModerator> Drop for ReadFuture<'_, T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        if !self.completed {
+            self.waiter.cancel(&self.lock.sync);
+            forget::<M>(&self.lock.sync, &self.waker);
+        }
+    }
+}
+
+pub struct WriteFuture<'a, T: ?Sized, M: AsyncModerator> {
+    lock: &'a ZLock<T, M>,
+    waiter: M::WriteWaiter,
+
+    /// The waker registered by the last pending poll.
+    waker: Option<Waker>,
+    completed: bool,
+}
+
+impl<'a, T: ?Sized, M: AsyncModerator> Future for WriteFuture<'a, T, M> {
+    type Output = LockWriteGuard<'a, T, M>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        assert!(!this.completed, "future polled after completion");
+        this.lock.id.attempt(this.lock, Duration::MAX);
+        match this.waiter.poll_acquire(&this.lock.sync, cx) {
+            Poll::Ready(()) => {
+                this.completed = true;
+                this.lock.id.acquired(this.lock);
+                Poll::Ready(this.lock.write_guard())
+            }
+            Poll::Pending => {
+                remember(&mut this.waker, cx);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T: ?Sized, M: AsyncModerator> Drop for WriteFuture<'_, T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        if !self.completed {
+            self.waiter.cancel(&self.lock.sync);
+            forget::<M>(&self.lock.sync, &self.waker);
+        }
+    }
+}
+
+pub struct UpgradeFuture<'a, T: ?Sized, M: AsyncModerator> {
+    /// The read guard being upgraded; taken once the upgrade completes.
+    guard: Option<LockReadGuard<'a, T, M>>,
+    waiter: M::UpgradeWaiter,
+
+    /// The waker registered by the last pending poll.
+    waker: Option<Waker>,
+}
+
+impl<'a, T: ?Sized, M: AsyncModerator> Future for UpgradeFuture<'a, T, M> {
+    type Output = LockWriteGuard<'a, T, M>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let lock = this.guard.as_ref().expect("future polled after completion").lock;
+        match this.waiter.poll_acquire(&lock.sync, cx) {
+            Poll::Ready(()) => {
+                // the moderator has converted our read lock into a write lock
+                let mut guard = this.guard.take().unwrap();
+                guard.locked = false;
+                Poll::Ready(lock.write_guard())
+            }
+            Poll::Pending => {
+                remember(&mut this.waker, cx);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T: ?Sized, M: AsyncModerator> Drop for UpgradeFuture<'_, T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        // cancel before the read guard (if still held) is released
+        if let Some(guard) = &self.guard {
+            self.waiter.cancel(&guard.lock.sync);
+            forget::<M>(&guard.lock.sync, &self.waker);
+        }
+    }
+}
+
+/// Records the waker registered by a pending poll, unless it is already recorded.
+#[inline]
+fn remember(waker: &mut Option<Waker>, cx: &Context<'_>) {
+    if !waker.as_ref().is_some_and(|waker| waker.will_wake(cx.waker())) {
+        *waker = Some(cx.waker().clone());
+    }
+}
+
+/// Withdraws the waker of a dropped future, which would otherwise remain registered with
+/// the moderator until the next notification.
+#[inline]
+fn forget<M: AsyncModerator>(sync: &M::Sync, waker: &Option<Waker>) {
+    if let Some(waker) = waker {
+        M::forget(sync, waker);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,289 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+use crate::executor::block_on;
+use crate::test_utils;
+use crate::zlock::{ArrivalOrdered, AsyncModerator, PhaseFair, Prioritized, ReadBiased, Stochastic, WriteBiased, ZLock};
+
+fn poll_once<F: Future + Unpin>(f: &mut F) -> Poll<F::Output> {
+    Pin::new(f).poll(&mut Context::from_waker(Waker::noop()))
+}
+
+#[test]
+fn read_write_upgrade_cycle() {
+    __read_write_upgrade_cycle::<ReadBiased>();
+    __read_write_upgrade_cycle::<WriteBiased>();
+    __read_write_upgrade_cycle::<ArrivalOrdered>();
+    __read_write_upgrade_cycle::<Stochastic>();
+    __read_write_upgrade_cycle::<PhaseFair>();
+    __read_write_upgrade_cycle::<Prioritized>();
+}
+
+fn __read_write_upgrade_cycle<M: AsyncModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+    block_on(async {
+        let guard = lock.read_async().await;
+        assert_eq!(0, *guard);
+        drop(guard);
+
+        let mut guard = lock.write_async().await;
+        *guard = 42;
+        let guard = guard.downgrade();
+        assert_eq!(42, *guard);
+
+        let mut guard = guard.upgrade_async().await;
+        assert_eq!(42, *guard);
+        *guard = 69;
+    });
+    assert_eq!(69, lock.into_inner());
+}
+
+#[test]
+fn await_write_while_read_locked() {
+    __await_write_while_read_locked::<ReadBiased>();
+    __await_write_while_read_locked::<WriteBiased>();
+    __await_write_while_read_locked::<ArrivalOrdered>();
+    __await_write_while_read_locked::<Stochastic>();
+    __await_write_while_read_locked::<PhaseFair>();
+    __await_write_while_read_locked::<Prioritized>();
+}
+
+fn __await_write_while_read_locked<M: AsyncModerator + 'static>() {
+    let lock = Arc::new(ZLock::<_, M>::new(0));
+    let guard_1 = lock.read();
+
+    let lock_t_2 = lock.clone();
+    let t_2 = test_utils::spawn_blocked(move || {
+        // t_2 waits because main holds the read lock
+        block_on(async {
+            *lock_t_2.write_async().await = 42;
+        });
+    });
+    assert!(!t_2.is_finished());
+
+    // t_2 should be woken after main releases the read lock
+    drop(guard_1);
+    t_2.join().unwrap();
+    assert_eq!(42, *lock.read());
+}
+
+#[test]
+fn await_read_while_write_locked() {
+    __await_read_while_write_locked::<ReadBiased>();
+    __await_read_while_write_locked::<WriteBiased>();
+    __await_read_while_write_locked::<ArrivalOrdered>();
+    __await_read_while_write_locked::<Stochastic>();
+    __await_read_while_write_locked::<PhaseFair>();
+    __await_read_while_write_locked::<Prioritized>();
+}
+
+fn __await_read_while_write_locked<M: AsyncModerator + 'static>() {
+    let lock = Arc::new(ZLock::<_, M>::new(0));
+    let mut guard_1 = lock.write();
+
+    let lock_t_2 = lock.clone();
+    let t_2 = test_utils::spawn_blocked(move || {
+        // t_2 waits because main holds the write lock
+        block_on(async { *lock_t_2.read_async().await })
+    });
+    assert!(!t_2.is_finished());
+
+    // t_2 should be woken after main releases the write lock
+    *guard_1 = 42;
+    drop(guard_1);
+    assert_eq!(42, t_2.join().unwrap());
+}
+
+#[test]
+fn await_upgrade_while_read_locked() {
+    __await_upgrade_while_read_locked::<ReadBiased>();
+    __await_upgrade_while_read_locked::<WriteBiased>();
+    __await_upgrade_while_read_locked::<ArrivalOrdered>();
+    __await_upgrade_while_read_locked::<Stochastic>();
+    __await_upgrade_while_read_locked::<PhaseFair>();
+    __await_upgrade_while_read_locked::<Prioritized>();
+}
+
+fn __await_upgrade_while_read_locked<M: AsyncModerator + 'static>() {
+    let lock = Arc::new(ZLock::<_, M>::new(0));
+    let guard_1 = lock.read();
+
+    let lock_t_2 = lock.clone();
+    let t_2 = test_utils::spawn_blocked(move || {
+        block_on(async {
+            // t_2 waits because main holds a read lock (its own read lock will not affect it)
+            let guard_2 = lock_t_2.read_async().await;
+            *guard_2.upgrade_async().await = 42;
+        });
+    });
+    assert!(!t_2.is_finished());
+
+    // t_2 should be woken after main releases the read lock
+    drop(guard_1);
+    t_2.join().unwrap();
+    assert_eq!(42, *lock.read());
+}
+
+#[test]
+fn cancel_before_poll() {
+    __cancel_before_poll::<ReadBiased>();
+    __cancel_before_poll::<WriteBiased>();
+    __cancel_before_poll::<ArrivalOrdered>();
+    __cancel_before_poll::<Stochastic>();
+    __cancel_before_poll::<PhaseFair>();
+    __cancel_before_poll::<Prioritized>();
+}
+
+fn __cancel_before_poll<M: AsyncModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+    drop(lock.read_async());
+    drop(lock.write_async());
+    drop(lock.read().upgrade_async());
+
+    // the lock is unaffected by the abandoned futures
+    assert!(lock.try_write(Duration::ZERO).is_some());
+    assert!(lock.try_read(Duration::ZERO).is_some());
+}
+
+#[test]
+fn cancel_pending_write() {
+    __cancel_pending_write::<ReadBiased>();
+    __cancel_pending_write::<WriteBiased>();
+    __cancel_pending_write::<ArrivalOrdered>();
+    __cancel_pending_write::<Stochastic>();
+    __cancel_pending_write::<PhaseFair>();
+    __cancel_pending_write::<Prioritized>();
+}
+
+fn __cancel_pending_write<M: AsyncModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+    let guard_1 = lock.read();
+
+    let mut write = lock.write_async();
+    assert!(poll_once(&mut write).is_pending());
+    drop(write);
+
+    // the abandoned writer must not hold back readers
+    let guard_2 = lock.try_read(Duration::ZERO);
+    assert!(guard_2.is_some());
+    drop(guard_2);
+    drop(guard_1);
+
+    // nor subsequent writers
+    assert!(lock.try_write(Duration::ZERO).is_some());
+}
+
+#[test]
+fn cancel_pending_read() {
+    __cancel_pending_read::<ReadBiased>();
+    __cancel_pending_read::<WriteBiased>();
+    __cancel_pending_read::<ArrivalOrdered>();
+    __cancel_pending_read::<Stochastic>();
+    __cancel_pending_read::<PhaseFair>();
+    __cancel_pending_read::<Prioritized>();
+}
+
+fn __cancel_pending_read<M: AsyncModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+    let guard_1 = lock.write();
+
+    let mut read = lock.read_async();
+    assert!(poll_once(&mut read).is_pending());
+    drop(read);
+    drop(guard_1);
+
+    // the abandoned reader must not hold back writers
+    assert!(lock.try_write(Duration::ZERO).is_some());
+    assert!(lock.try_read(Duration::ZERO).is_some());
+}
+
+#[test]
+fn cancel_pending_upgrade() {
+    __cancel_pending_upgrade::<ReadBiased>();
+    __cancel_pending_upgrade::<WriteBiased>();
+    __cancel_pending_upgrade::<ArrivalOrdered>();
+    __cancel_pending_upgrade::<Stochastic>();
+    __cancel_pending_upgrade::<PhaseFair>();
+    __cancel_pending_upgrade::<Prioritized>();
+}
+
+fn __cancel_pending_upgrade<M: AsyncModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+    let guard_1 = lock.read();
+    let guard_2 = lock.read();
+
+    let mut upgrade = guard_2.upgrade_async();
+    assert!(poll_once(&mut upgrade).is_pending());
+
+    // dropping the future also releases guard_2's read lock
+    drop(upgrade);
+    assert!(guard_1.try_upgrade(Duration::ZERO).is_upgraded());
+}
+
+#[test]
+fn cancel_pending_write_forgets_waker() {
+    __cancel_pending_write_forgets_waker::<ReadBiased>();
+    __cancel_pending_write_forgets_waker::<WriteBiased>();
+    __cancel_pending_write_forgets_waker::<ArrivalOrdered>();
+    __cancel_pending_write_forgets_waker::<Stochastic>();
+    __cancel_pending_write_forgets_waker::<PhaseFair>();
+    __cancel_pending_write_forgets_waker::<Prioritized>();
+}
+
+/// Counts the number of times it has been woken.
+#[derive(Default)]
+struct CountingWaker(AtomicU32);
+
+impl Wake for CountingWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn __cancel_pending_write_forgets_waker<M: AsyncModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+    let guard_1 = lock.write();
+    let counter = Arc::new(CountingWaker::default());
+    let waker = Waker::from(counter.clone());
+
+    let mut write = lock.write_async();
+    assert!(Pin::new(&mut write).poll(&mut Context::from_waker(&waker)).is_pending());
+    assert!(Pin::new(&mut write).poll(&mut Context::from_waker(&waker)).is_pending());
+    assert_eq!(0, counter.0.load(Ordering::Relaxed));
+
+    // the withdrawn waker is woken once on drop, and no longer by the release
+    drop(write);
+    assert_eq!(1, counter.0.load(Ordering::Relaxed));
+    drop(guard_1);
+    assert_eq!(1, counter.0.load(Ordering::Relaxed));
+}
+
+#[test]
+fn pending_future_completes_after_release() {
+    __pending_future_completes_after_release::<ReadBiased>();
+    __pending_future_completes_after_release::<WriteBiased>();
+    __pending_future_completes_after_release::<ArrivalOrdered>();
+    __pending_future_completes_after_release::<Stochastic>();
+    __pending_future_completes_after_release::<PhaseFair>();
+    __pending_future_completes_after_release::<Prioritized>();
+}
+
+fn __pending_future_completes_after_release<M: AsyncModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+
+    let mut write_1 = lock.write_async();
+    let guard_1 = match poll_once(&mut write_1) {
+        Poll::Ready(guard) => guard,
+        Poll::Pending => unreachable!(),
+    };
+
+    let mut write_2 = lock.write_async();
+    assert!(poll_once(&mut write_2).is_pending());
+    assert!(poll_once(&mut write_2).is_pending());
+
+    drop(guard_1);
+    assert!(poll_once(&mut write_2).is_ready());
+}
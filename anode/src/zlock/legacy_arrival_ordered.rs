@@ -3,7 +3,7 @@ use std::time::Duration;
 use crate::deadline::Deadline;
 use crate::remedy;
 use crate::remedy::Remedy;
-use crate::zlock::Moderator;
+use crate::zlock::{LockState, Moderator, ThreadAgnostic, Tickets};
 
 #[derive(Debug)]
 pub struct LegacyArrivalOrdered;
@@ -33,13 +33,16 @@ impl LegacyArrivalOrderedState {
 
 impl Moderator for LegacyArrivalOrdered {
     type Sync = LegacyArrivalOrderedSync;
+    type ModeratorConfig = ();
+
+    const INIT: Self::Sync = LegacyArrivalOrderedSync {
+        state: Mutex::new(LegacyArrivalOrderedState { readers: 0, writer: false, next_ticket: 1, serviced_tickets: 0 }),
+        cond: Condvar::new()
+    };
 
     #[inline]
-    fn new() -> Self::Sync {
-        Self::Sync {
-            state: Mutex::new(LegacyArrivalOrderedState { readers: 0, writer: false, next_ticket: 1, serviced_tickets: 0 }),
-            cond: Condvar::new()
-        }
+    fn with_config(_config: Self::ModeratorConfig) -> Self::Sync {
+        Self::INIT
     }
 
     #[inline]
@@ -143,4 +146,16 @@ impl Moderator for LegacyArrivalOrdered {
         state.writer = true;
         true
     }
-}
\ No newline at end of file
+
+    fn inspect(sync: &Self::Sync) -> LockState {
+        let state = sync.state.lock().remedy();
+        LockState {
+            readers: state.readers,
+            writer: state.writer,
+            tickets: Some(Tickets { next: state.next_ticket, serviced: state.serviced_tickets }),
+            ..LockState::default()
+        }
+    }
+}
+
+unsafe impl ThreadAgnostic for LegacyArrivalOrdered {}
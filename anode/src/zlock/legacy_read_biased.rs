@@ -3,7 +3,7 @@ use std::time::Duration;
 use crate::deadline::Deadline;
 use crate::remedy;
 use crate::remedy::Remedy;
-use crate::zlock::Moderator;
+use crate::zlock::{LockState, Moderator, ThreadAgnostic};
 
 #[derive(Debug)]
 pub struct LegacyReadBiased;
@@ -22,13 +22,16 @@ struct LegacyReadBiasedState {
 
 impl Moderator for LegacyReadBiased {
     type Sync = LegacyReadBiasedSync;
+    type ModeratorConfig = ();
+
+    const INIT: Self::Sync = LegacyReadBiasedSync {
+        state: Mutex::new(LegacyReadBiasedState { readers: 0, writer: false }),
+        cond: Condvar::new()
+    };
 
     #[inline]
-    fn new() -> Self::Sync {
-        Self::Sync {
-            state: Mutex::new(LegacyReadBiasedState { readers: 0, writer: false }),
-            cond: Condvar::new()
-        }
+    fn with_config(_config: Self::ModeratorConfig) -> Self::Sync {
+        Self::INIT
     }
 
     #[inline]
@@ -120,4 +123,15 @@ impl Moderator for LegacyReadBiased {
         state.writer = true;
         true
     }
-}
\ No newline at end of file
+
+    fn inspect(sync: &Self::Sync) -> LockState {
+        let state = sync.state.lock().remedy();
+        LockState {
+            readers: state.readers,
+            writer: state.writer,
+            ..LockState::default()
+        }
+    }
+}
+
+unsafe impl ThreadAgnostic for LegacyReadBiased {}
@@ -3,7 +3,7 @@ use std::time::Duration;
 use crate::deadline::Deadline;
 use crate::remedy;
 use crate::remedy::Remedy;
-use crate::zlock::Moderator;
+use crate::zlock::{LockState, Moderator, ThreadAgnostic};
 
 #[derive(Debug)]
 pub struct LegacyWriteBiased;
@@ -23,13 +23,16 @@ struct LegacyWriteBiasedState {
 
 impl Moderator for LegacyWriteBiased {
     type Sync = LegacyWriteBiasedSync;
+    type ModeratorConfig = ();
+
+    const INIT: Self::Sync = LegacyWriteBiasedSync {
+        state: Mutex::new(LegacyWriteBiasedState { readers: 0, writer: false, writer_pending: false }),
+        cond: Condvar::new()
+    };
 
     #[inline]
-    fn new() -> Self::Sync {
-        Self::Sync {
-            state: Mutex::new(LegacyWriteBiasedState { readers: 0, writer: false, writer_pending: false }),
-            cond: Condvar::new()
-        }
+    fn with_config(_config: Self::ModeratorConfig) -> Self::Sync {
+        Self::INIT
     }
 
     #[inline]
@@ -149,4 +152,16 @@ impl Moderator for LegacyWriteBiased {
         state.writer = true;
         true
     }
+
+    fn inspect(sync: &Self::Sync) -> LockState {
+        let state = sync.state.lock().remedy();
+        LockState {
+            readers: state.readers,
+            writer: state.writer,
+            writer_pending: state.writer_pending,
+            ..LockState::default()
+        }
+    }
 }
+
+unsafe impl ThreadAgnostic for LegacyWriteBiased {}
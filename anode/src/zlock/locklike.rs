@@ -1,24 +1,66 @@
-use crate::zlock::{ArrivalOrdered, LockReadGuard, LockWriteGuard, Moderator, ReadBiased, Stochastic, UpgradeOutcome, WriteBiased, ZLock};
+//! Lock-agnostic traits for read-write locks with upgrade and downgrade support.
+//!
+//! [`Locklike`] abstracts over the acquisition of a lock, and [`LockReadGuardlike`] and
+//! [`LockWriteGuardlike`] over the upgrading and downgrading of its guards. Besides every
+//! [`ZLock`], the traits are implemented for [`std::sync::RwLock`] and [`std::sync::Mutex`],
+//! which lack upgrades and downgrades of their own:
+//!
+//! * An `RwLock` upgrades a read lock by releasing it and acquiring the write lock, and
+//!   downgrades by releasing the write lock and acquiring a read lock. Unlike a `ZLock`
+//!   upgrade, an emulated upgrade is not atomic: another writer may slip in between, so
+//!   the data should be revalidated after upgrading, even though the outcome is reported
+//!   as [`UpgradeOutcome::Upgraded`]. A timed upgrade that fails to acquire the write lock
+//!   reacquires the read lock, and reports the outcome as [`UpgradeOutcome::Unchanged`].
+//! * A `Mutex` grants the same exclusive lock to readers and writers alike, so its upgrades
+//!   and downgrades are immediate and never release the lock.
+//!
+//! Poisoning is disregarded by the adapters, in keeping with the non-poisoning `ZLock`.
+//!
+//! [`LockBoxSized`] type-erases the lock altogether, and [`LocklikeKind`] selects its
+//! implementation at runtime.
+
+use crate::backoff::{ExpBackoff, ExpBackoffAction};
+use crate::deadline::Deadline;
+use crate::inf_iterator::{InfIterator, IntoInfIterator};
+use crate::rand::FIXED_DURATION;
+use crate::zlock::{ArrivalOrdered, LockReadGuard, LockWriteGuard, MappedLockReadGuard, MappedLockWriteGuard, Moderator, PhaseFair, Prioritized, ReadBiased, Sharded, Stochastic, UpgradeOutcome, WriteBiased, ZLock};
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::str::FromStr;
+use std::sync::{LockResult, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError, TryLockResult};
 use std::time::Duration;
 
+/// A boxed lock of any [`Locklike`] implementation, whose guards are type-erased.
 pub type LockBox<T> =
     Box<dyn for<'a> Locklike<'a, T, R = DynLockReadGuard<'a, T>, W = DynLockWriteGuard<'a, T>>>;
 
+/// A [`LockBox`] over sized data, which may also be consumed to recover the data.
 pub type LockBoxSized<T> = Box<
     dyn for<'a> LocklikeSized<'a, T, R = DynLockReadGuard<'a, T>, W = DynLockWriteGuard<'a, T>>,
 >;
 
+/// A read guard that may be upgraded to a write guard. Upgrades need not be atomic; see the
+/// [module docs](self).
 pub trait LockReadGuardlike<'a, T: ?Sized>: Deref<Target = T> {
+    /// Upgrades to a write lock, blocking until it is acquired.
     fn upgrade(self) -> DynLockWriteGuard<'a, T>;
 
+    /// Attempts to upgrade to a write lock within the given duration, returning the read
+    /// guard unchanged if the write lock could not be acquired in time.
     fn try_upgrade(
         self,
         duration: Duration,
     ) -> UpgradeOutcome<DynLockWriteGuard<'a, T>, DynLockReadGuard<'a, T>>;
 }
 
+/// A write guard that may be downgraded to a read guard.
 pub trait LockWriteGuardlike<'a, T: ?Sized>: DerefMut<Target = T> {
+    /// Downgrades to a read lock. Like an upgrade, the downgrade of an [`RwLock`] guard is
+    /// emulated, and is not atomic.
     fn downgrade(self) -> DynLockReadGuard<'a, T>;
 }
 
@@ -35,21 +77,32 @@ trait LockWriteGuardSurrogate<'a, T: ?Sized>: DerefMut<Target = T> {
     fn downgrade_box(self: Box<Self>) -> DynLockReadGuard<'a, T>;
 }
 
+/// A read-write lock, abstracted over its implementation. The guards borrow the lock for
+/// `'a`.
 pub trait Locklike<'a, T: ?Sized>: Sync + Send {
+    /// The read guard.
     type R: LockReadGuardlike<'a, T>;
+
+    /// The write guard.
     type W: LockWriteGuardlike<'a, T>;
 
+    /// Acquires a read lock, blocking until it is available.
     fn read(&'a self) -> Self::R;
 
+    /// Attempts to acquire a read lock within the given duration.
     fn try_read(&'a self, duration: Duration) -> Option<Self::R>;
 
+    /// Acquires a write lock, blocking until it is available.
     fn write(&'a self) -> Self::W;
 
+    /// Attempts to acquire a write lock within the given duration.
     fn try_write(&'a self, duration: Duration) -> Option<Self::W>;
 
+    /// Returns a mutable reference to the underlying data, without locking.
     fn get_mut(&mut self) -> &mut T;
 }
 
+/// A [`Locklike`] over sized data, which may be consumed to recover the data.
 pub trait LocklikeSized<'a, T>: Locklike<'a, T> {
     fn into_inner(self: Box<Self>) -> T;
 }
@@ -183,6 +236,7 @@ impl<'a, T: Sync + Send + 'a, M: Moderator + 'a> LocklikeSized<'a, T> for PolyLo
     }
 }
 
+/// A type-erased read guard of any [`Locklike`] implementation.
 pub struct DynLockReadGuard<'a, T: ?Sized>(Box<dyn LockReadGuardSurrogate<'a, T> + 'a>);
 
 impl<'a, T: ?Sized> DynLockReadGuard<'a, T> {
@@ -195,6 +249,31 @@ impl<'a, T: ?Sized> DynLockReadGuard<'a, T> {
     }
 }
 
+impl<'a, T: ?Sized + 'a> DynLockReadGuard<'a, T> {
+    /// Projects the guard onto a component of the locked data, retaining the read lock.
+    #[inline]
+    pub fn map<U: ?Sized, F: FnOnce(&T) -> &U>(guard: Self, f: F) -> DynMappedLockReadGuard<'a, U> {
+        let data = NonNull::from(f(&*guard));
+        DynMappedLockReadGuard::new(data, Box::new(guard))
+    }
+
+    #[inline]
+    pub fn filter_map<U: ?Sized, F: FnOnce(&T) -> Option<&U>>(guard: Self, f: F) -> Result<DynMappedLockReadGuard<'a, U>, Self> {
+        match f(&*guard).map(NonNull::from) {
+            None => Err(guard),
+            Some(data) => Ok(DynMappedLockReadGuard::new(data, Box::new(guard))),
+        }
+    }
+
+    #[inline]
+    pub fn try_map<U: ?Sized, E, F: FnOnce(&T) -> Result<&U, E>>(guard: Self, f: F) -> Result<DynMappedLockReadGuard<'a, U>, (Self, E)> {
+        match f(&*guard).map(NonNull::from) {
+            Err(err) => Err((guard, err)),
+            Ok(data) => Ok(DynMappedLockReadGuard::new(data, Box::new(guard))),
+        }
+    }
+}
+
 impl<T: ?Sized> Deref for DynLockReadGuard<'_, T> {
     type Target = T;
 
@@ -207,7 +286,7 @@ impl<T: ?Sized> Deref for DynLockReadGuard<'_, T> {
 impl<'a, T: ?Sized> LockReadGuardlike<'a, T> for DynLockReadGuard<'a, T> {
     #[inline]
     fn upgrade(self) -> DynLockWriteGuard<'a, T> {
-        self.0.upgrade_box().into()
+        self.0.upgrade_box()
     }
 
     #[inline]
@@ -215,8 +294,7 @@ impl<'a, T: ?Sized> LockReadGuardlike<'a, T> for DynLockReadGuard<'a, T> {
         self,
         duration: Duration,
     ) -> UpgradeOutcome<DynLockWriteGuard<'a, T>, DynLockReadGuard<'a, T>> {
-        self.try_upgrade(duration)
-            .map(DynLockWriteGuard::from, DynLockReadGuard::from)
+        self.0.try_upgrade_box(duration)
     }
 }
 
@@ -227,6 +305,7 @@ impl<'a, T: ?Sized + 'a, M: Moderator> From<LockReadGuard<'a, T, M>> for DynLock
     }
 }
 
+/// A type-erased write guard of any [`Locklike`] implementation.
 pub struct DynLockWriteGuard<'a, T: ?Sized>(Box<dyn LockWriteGuardSurrogate<'a, T> + 'a>);
 
 impl<T: ?Sized> Deref for DynLockWriteGuard<'_, T> {
@@ -259,38 +338,507 @@ impl<'a, T: ?Sized, M: Moderator> From<LockWriteGuard<'a, T, M>> for DynLockWrit
     }
 }
 
-#[derive(Debug)]
+impl<'a, T: ?Sized + 'a> DynLockWriteGuard<'a, T> {
+    /// Projects the guard onto a component of the locked data, retaining the write lock.
+    #[inline]
+    pub fn map<U: ?Sized, F: FnOnce(&mut T) -> &mut U>(mut guard: Self, f: F) -> DynMappedLockWriteGuard<'a, U> {
+        let data = NonNull::from(f(&mut *guard));
+        DynMappedLockWriteGuard::new(data, Box::new(guard))
+    }
+
+    #[inline]
+    pub fn filter_map<U: ?Sized, F: FnOnce(&mut T) -> Option<&mut U>>(mut guard: Self, f: F) -> Result<DynMappedLockWriteGuard<'a, U>, Self> {
+        match f(&mut *guard).map(NonNull::from) {
+            None => Err(guard),
+            Some(data) => Ok(DynMappedLockWriteGuard::new(data, Box::new(guard))),
+        }
+    }
+
+    #[inline]
+    pub fn try_map<U: ?Sized, E, F: FnOnce(&mut T) -> Result<&mut U, E>>(mut guard: Self, f: F) -> Result<DynMappedLockWriteGuard<'a, U>, (Self, E)> {
+        match f(&mut *guard).map(NonNull::from) {
+            Err(err) => Err((guard, err)),
+            Ok(data) => Ok(DynMappedLockWriteGuard::new(data, Box::new(guard))),
+        }
+    }
+}
+
+/// Implemented by every type, so that any guard can be owned (and eventually dropped)
+/// without regard for its concrete type.
+trait Held {}
+
+impl<X: ?Sized> Held for X {}
+
+/// A type-erased read guard, projected onto a component of the locked data.
+///
+/// The projection points into the lock rather than the boxed guard, so it remains valid
+/// for as long as the guard is held.
+pub struct DynMappedLockReadGuard<'a, U: ?Sized> {
+    data: NonNull<U>,
+    _guard: Box<dyn Held + 'a>,
+}
+
+impl<'a, U: ?Sized> DynMappedLockReadGuard<'a, U> {
+    #[inline]
+    fn new(data: NonNull<U>, guard: Box<dyn Held + 'a>) -> Self {
+        Self { data, _guard: guard }
+    }
+}
+
+impl<U: ?Sized> Deref for DynMappedLockReadGuard<'_, U> {
+    type Target = U;
+
+    #[inline]
+    fn deref(&self) -> &U {
+        unsafe { self.data.as_ref() }
+    }
+}
+
+impl<'a, U: ?Sized + 'a, M: Moderator> From<MappedLockReadGuard<'a, U, M>> for DynMappedLockReadGuard<'a, U> {
+    #[inline]
+    fn from(guard: MappedLockReadGuard<'a, U, M>) -> Self {
+        let data = NonNull::from(&*guard);
+        DynMappedLockReadGuard::new(data, Box::new(guard))
+    }
+}
+
+/// A type-erased write guard, projected onto a component of the locked data.
+///
+/// The projection points into the lock rather than the boxed guard, so it remains valid
+/// for as long as the guard is held.
+pub struct DynMappedLockWriteGuard<'a, U: ?Sized> {
+    data: NonNull<U>,
+    _guard: Box<dyn Held + 'a>,
+    __variance: PhantomData<&'a mut U>,
+}
+
+impl<'a, U: ?Sized> DynMappedLockWriteGuard<'a, U> {
+    #[inline]
+    fn new(data: NonNull<U>, guard: Box<dyn Held + 'a>) -> Self {
+        Self { data, _guard: guard, __variance: PhantomData }
+    }
+}
+
+impl<U: ?Sized> Deref for DynMappedLockWriteGuard<'_, U> {
+    type Target = U;
+
+    #[inline]
+    fn deref(&self) -> &U {
+        unsafe { self.data.as_ref() }
+    }
+}
+
+impl<U: ?Sized> DerefMut for DynMappedLockWriteGuard<'_, U> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { self.data.as_mut() }
+    }
+}
+
+impl<'a, U: ?Sized + 'a, M: Moderator> From<MappedLockWriteGuard<'a, U, M>> for DynMappedLockWriteGuard<'a, U> {
+    #[inline]
+    fn from(mut guard: MappedLockWriteGuard<'a, U, M>) -> Self {
+        let data = NonNull::from(&mut *guard);
+        DynMappedLockWriteGuard::new(data, Box::new(guard))
+    }
+}
+
+/// Acquires a std lock within the given duration, blocking if the duration is unbounded.
+/// Otherwise, the lock is polled with an exponential backoff, whose sleeps are capped by
+/// the time remaining so as not to overshoot the deadline. A poisoned lock is acquired
+/// regardless.
+#[inline]
+fn try_acquire<G>(
+    duration: Duration,
+    block: impl FnOnce() -> LockResult<G>,
+    mut attempt: impl FnMut() -> TryLockResult<G>,
+) -> Option<G> {
+    if duration == Duration::MAX {
+        return Some(block().unwrap_or_else(PoisonError::into_inner));
+    }
+
+    let mut deadline = Deadline::lazy_after(duration);
+    let mut rng = FIXED_DURATION;
+    let mut backoff = ExpBackoff::sleepy().into_inf_iter();
+    loop {
+        match attempt() {
+            Ok(guard) => return Some(guard),
+            Err(TryLockError::Poisoned(err)) => return Some(err.into_inner()),
+            Err(TryLockError::WouldBlock) => {}
+        }
+
+        let remaining = deadline.remaining();
+        if remaining.is_zero() {
+            return None;
+        }
+        let action = match backoff.next() {
+            ExpBackoffAction::Sleep(sleep) => ExpBackoffAction::Sleep(sleep.min(remaining)),
+            action => action,
+        };
+        action.act(|| &mut rng);
+    }
+}
+
+#[inline]
+fn try_read_rw_lock<T: ?Sized>(lock: &RwLock<T>, duration: Duration) -> Option<RwLockReadGuard<'_, T>> {
+    try_acquire(duration, || lock.read(), || lock.try_read())
+}
+
+#[inline]
+fn try_write_rw_lock<T: ?Sized>(lock: &RwLock<T>, duration: Duration) -> Option<RwLockWriteGuard<'_, T>> {
+    try_acquire(duration, || lock.write(), || lock.try_write())
+}
+
+#[inline]
+fn try_lock_mutex<T: ?Sized>(lock: &Mutex<T>, duration: Duration) -> Option<MutexGuard<'_, T>> {
+    try_acquire(duration, || lock.lock(), || lock.try_lock())
+}
+
+/// A read guard of an [`RwLock`], which retains the lock for the purpose of upgrading.
+struct RwLockReadSurrogate<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+    guard: RwLockReadGuard<'a, T>,
+}
+
+impl<'a, T: ?Sized> RwLockReadSurrogate<'a, T> {
+    #[inline]
+    fn new(lock: &'a RwLock<T>, guard: RwLockReadGuard<'a, T>) -> Self {
+        Self { lock, guard }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadSurrogate<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<'a, T: ?Sized + 'a> LockReadGuardSurrogate<'a, T> for RwLockReadSurrogate<'a, T> {
+    #[inline]
+    fn upgrade_box(self: Box<Self>) -> DynLockWriteGuard<'a, T> {
+        let lock = self.lock;
+        drop(self);
+        RwLockWriteSurrogate::new(lock, try_write_rw_lock(lock, Duration::MAX).unwrap()).into()
+    }
+
+    #[inline]
+    fn try_upgrade_box(
+        self: Box<Self>,
+        duration: Duration,
+    ) -> UpgradeOutcome<DynLockWriteGuard<'a, T>, DynLockReadGuard<'a, T>> {
+        let lock = self.lock;
+        drop(self);
+        match try_write_rw_lock(lock, duration) {
+            Some(guard) => UpgradeOutcome::Upgraded(RwLockWriteSurrogate::new(lock, guard).into()),
+            None => {
+                let guard = try_read_rw_lock(lock, Duration::MAX).unwrap();
+                UpgradeOutcome::Unchanged(RwLockReadSurrogate::new(lock, guard).into())
+            }
+        }
+    }
+}
+
+impl<'a, T: ?Sized + 'a> From<RwLockReadSurrogate<'a, T>> for DynLockReadGuard<'a, T> {
+    #[inline]
+    fn from(guard: RwLockReadSurrogate<'a, T>) -> Self {
+        DynLockReadGuard(Box::new(guard))
+    }
+}
+
+/// A write guard of an [`RwLock`], which retains the lock for the purpose of downgrading.
+struct RwLockWriteSurrogate<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+    guard: RwLockWriteGuard<'a, T>,
+}
+
+impl<'a, T: ?Sized> RwLockWriteSurrogate<'a, T> {
+    #[inline]
+    fn new(lock: &'a RwLock<T>, guard: RwLockWriteGuard<'a, T>) -> Self {
+        Self { lock, guard }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteSurrogate<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteSurrogate<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<'a, T: ?Sized + 'a> LockWriteGuardSurrogate<'a, T> for RwLockWriteSurrogate<'a, T> {
+    #[inline]
+    fn downgrade_box(self: Box<Self>) -> DynLockReadGuard<'a, T> {
+        let lock = self.lock;
+        drop(self);
+        RwLockReadSurrogate::new(lock, try_read_rw_lock(lock, Duration::MAX).unwrap()).into()
+    }
+}
+
+impl<'a, T: ?Sized + 'a> From<RwLockWriteSurrogate<'a, T>> for DynLockWriteGuard<'a, T> {
+    #[inline]
+    fn from(guard: RwLockWriteSurrogate<'a, T>) -> Self {
+        DynLockWriteGuard(Box::new(guard))
+    }
+}
+
+/// Adapts an [`RwLock`], emulating upgrades and downgrades; see the [module docs](self).
+impl<'a, T: ?Sized + Sync + Send + 'a> Locklike<'a, T> for RwLock<T> {
+    type R = DynLockReadGuard<'a, T>;
+    type W = DynLockWriteGuard<'a, T>;
+
+    #[inline]
+    fn read(&'a self) -> Self::R {
+        Locklike::try_read(self, Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    fn try_read(&'a self, duration: Duration) -> Option<Self::R> {
+        try_read_rw_lock(self, duration).map(|guard| RwLockReadSurrogate::new(self, guard).into())
+    }
+
+    #[inline]
+    fn write(&'a self) -> Self::W {
+        Locklike::try_write(self, Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    fn try_write(&'a self, duration: Duration) -> Option<Self::W> {
+        try_write_rw_lock(self, duration).map(|guard| RwLockWriteSurrogate::new(self, guard).into())
+    }
+
+    #[inline]
+    fn get_mut(&mut self) -> &mut T {
+        RwLock::get_mut(self).unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<'a, T: Sync + Send + 'a> LocklikeSized<'a, T> for RwLock<T> {
+    #[inline]
+    fn into_inner(self: Box<Self>) -> T {
+        RwLock::into_inner(*self).unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A guard of a [`Mutex`], serving as both a read and a write guard. Since the mutex is
+/// held exclusively either way, the guard changes roles without releasing it.
+struct MutexSurrogate<'a, T: ?Sized>(MutexGuard<'a, T>);
+
+impl<T: ?Sized> Deref for MutexSurrogate<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexSurrogate<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<'a, T: ?Sized + 'a> LockReadGuardSurrogate<'a, T> for MutexSurrogate<'a, T> {
+    #[inline]
+    fn upgrade_box(self: Box<Self>) -> DynLockWriteGuard<'a, T> {
+        DynLockWriteGuard(self)
+    }
+
+    #[inline]
+    fn try_upgrade_box(
+        self: Box<Self>,
+        _duration: Duration,
+    ) -> UpgradeOutcome<DynLockWriteGuard<'a, T>, DynLockReadGuard<'a, T>> {
+        UpgradeOutcome::Upgraded(DynLockWriteGuard(self))
+    }
+}
+
+impl<'a, T: ?Sized + 'a> LockWriteGuardSurrogate<'a, T> for MutexSurrogate<'a, T> {
+    #[inline]
+    fn downgrade_box(self: Box<Self>) -> DynLockReadGuard<'a, T> {
+        DynLockReadGuard(self)
+    }
+}
+
+impl<'a, T: ?Sized + Send + 'a> Locklike<'a, T> for Mutex<T> {
+    type R = DynLockReadGuard<'a, T>;
+    type W = DynLockWriteGuard<'a, T>;
+
+    #[inline]
+    fn read(&'a self) -> Self::R {
+        Locklike::try_read(self, Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    fn try_read(&'a self, duration: Duration) -> Option<Self::R> {
+        try_lock_mutex(self, duration).map(|guard| DynLockReadGuard(Box::new(MutexSurrogate(guard))))
+    }
+
+    #[inline]
+    fn write(&'a self) -> Self::W {
+        Locklike::try_write(self, Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    fn try_write(&'a self, duration: Duration) -> Option<Self::W> {
+        try_lock_mutex(self, duration).map(|guard| DynLockWriteGuard(Box::new(MutexSurrogate(guard))))
+    }
+
+    #[inline]
+    fn get_mut(&mut self) -> &mut T {
+        Mutex::get_mut(self).unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<'a, T: Send + 'a> LocklikeSized<'a, T> for Mutex<T> {
+    #[inline]
+    fn into_inner(self: Box<Self>) -> T {
+        Mutex::into_inner(*self).unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The moderators that may be selected at runtime, for a [`ZLock`] boxed as a
+/// [`LockBoxSized`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ModeratorKind {
     ReadBiased,
     WriteBiased,
     ArrivalOrdered,
     Stochastic,
+    PhaseFair,
+    Prioritized,
+    Sharded,
 }
 
-pub const MODERATOR_KINDS: [ModeratorKind; 4] = [
+/// Every [`ModeratorKind`], in the order of declaration.
+pub const MODERATOR_KINDS: [ModeratorKind; 7] = [
     ModeratorKind::ReadBiased,
     ModeratorKind::WriteBiased,
     ModeratorKind::ArrivalOrdered,
     ModeratorKind::Stochastic,
+    ModeratorKind::PhaseFair,
+    ModeratorKind::Prioritized,
+    ModeratorKind::Sharded,
 ];
 
 impl ModeratorKind {
-    pub fn make_lock_for_test<T: Sync + Send + 'static>(&self, t: T) -> LockBoxSized<T> {
-        println!("test running with moderator {:?}", self);
+    /// Creates a boxed [`ZLock`] with the moderator of this kind.
+    pub fn make_lock<T: Sync + Send + 'static>(&self, t: T) -> LockBoxSized<T> {
         match self {
             ModeratorKind::ReadBiased => Box::new(PolyLock(ZLock::<_, ReadBiased>::new(t))),
             ModeratorKind::WriteBiased => Box::new(PolyLock(ZLock::<_, WriteBiased>::new(t))),
             ModeratorKind::ArrivalOrdered => Box::new(PolyLock(ZLock::<_, ArrivalOrdered>::new(t))),
             ModeratorKind::Stochastic => Box::new(PolyLock(ZLock::<_, Stochastic>::new(t))),
+            ModeratorKind::PhaseFair => Box::new(PolyLock(ZLock::<_, PhaseFair>::new(t))),
+            ModeratorKind::Prioritized => Box::new(PolyLock(ZLock::<_, Prioritized>::new(t))),
+            ModeratorKind::Sharded => Box::new(PolyLock(ZLock::<_, Sharded>::new(t))),
+        }
+    }
+
+    /// The name of the moderator, in the form accepted by [`FromStr`].
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ModeratorKind::ReadBiased => "read_biased",
+            ModeratorKind::WriteBiased => "write_biased",
+            ModeratorKind::ArrivalOrdered => "arrival_ordered",
+            ModeratorKind::Stochastic => "stochastic",
+            ModeratorKind::PhaseFair => "phase_fair",
+            ModeratorKind::Prioritized => "prioritized",
+            ModeratorKind::Sharded => "sharded",
         }
     }
+
+    #[cfg(test)]
+    pub fn make_lock_for_test<T: Sync + Send + 'static>(&self, t: T) -> LockBoxSized<T> {
+        println!("test running with moderator {:?}", self);
+        self.make_lock(t)
+    }
+}
+
+impl Display for ModeratorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses the snake-case name of a moderator, such as `"read_biased"` or
+/// `"arrival_ordered"`.
+impl FromStr for ModeratorKind {
+    type Err = ParseModeratorKindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MODERATOR_KINDS
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| ParseModeratorKindError(s.to_string()))
+    }
+}
+
+/// The error returned when parsing a [`ModeratorKind`] from an unrecognised name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseModeratorKindError(String);
+
+impl Display for ParseModeratorKindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown moderator '{}'; expected one of: ", self.0)?;
+        for (index, kind) in MODERATOR_KINDS.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(kind.name())?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseModeratorKindError {}
+
+/// The lock implementations that may be selected at runtime: a [`ZLock`] with any of the
+/// moderators of [`ModeratorKind`], or one of the std locks, with emulated upgrades and
+/// downgrades.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LocklikeKind {
+    Moderated(ModeratorKind),
+    StdRwLock,
+    StdMutex,
+}
+
+impl LocklikeKind {
+    /// Creates a boxed lock of this kind.
+    pub fn make_lock<T: Sync + Send + 'static>(&self, t: T) -> LockBoxSized<T> {
+        match self {
+            LocklikeKind::Moderated(moderator) => moderator.make_lock(t),
+            LocklikeKind::StdRwLock => Box::new(RwLock::new(t)),
+            LocklikeKind::StdMutex => Box::new(Mutex::new(t)),
+        }
+    }
+}
+
+impl From<ModeratorKind> for LocklikeKind {
+    #[inline]
+    fn from(moderator: ModeratorKind) -> Self {
+        LocklikeKind::Moderated(moderator)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::zlock::locklike::{LockBoxSized, LockReadGuardlike, LockWriteGuardlike, Locklike, MODERATOR_KINDS};
-    use crate::zlock::{ReadBiased, ZLock};
-    use std::sync::Arc;
+    use crate::zlock::locklike::{DynLockReadGuard, DynLockWriteGuard, DynMappedLockReadGuard, DynMappedLockWriteGuard, LockBoxSized, LockReadGuardlike, LockWriteGuardlike, Locklike, LocklikeKind, ModeratorKind, MODERATOR_KINDS};
+    use crate::zlock::{LockReadGuard, LockWriteGuard, ReadBiased, ZLock};
+    use std::sync::{Arc, Mutex, RwLock};
     use std::thread;
     use std::time::Duration;
 
@@ -307,6 +855,121 @@ mod tests {
             let lock = moderator.make_lock_for_test(0);
             takes_boxed(lock);
         }
+
+        takes_borrowed(&RwLock::new(0));
+        takes_owned(RwLock::new(0));
+        takes_boxed(LocklikeKind::StdRwLock.make_lock(0));
+
+        takes_borrowed(&Mutex::new(0));
+        takes_owned(Mutex::new(0));
+        takes_boxed(LocklikeKind::StdMutex.make_lock(0));
+
+        takes_boxed(LocklikeKind::from(ModeratorKind::ReadBiased).make_lock(0));
+    }
+
+    #[test]
+    fn std_rw_lock_emulated_upgrade() {
+        let lock = RwLock::new(0);
+        let guard_1 = Locklike::read(&lock);
+        let guard_2 = Locklike::read(&lock);
+
+        // the write lock cannot be acquired while the other reader remains
+        let guard_1 = guard_1.try_upgrade(Duration::ZERO).unchanged().unwrap();
+        assert_eq!(0, *guard_1);
+        assert!(Locklike::try_write(&lock, Duration::ZERO).is_none());
+        drop(guard_2);
+
+        let mut guard_1 = guard_1.try_upgrade(Duration::ZERO).upgraded().unwrap();
+        *guard_1 = 42;
+        assert!(Locklike::try_read(&lock, Duration::ZERO).is_none());
+
+        // a downgraded guard admits other readers
+        let guard_1 = guard_1.downgrade();
+        assert_eq!(42, *Locklike::try_read(&lock, Duration::ZERO).unwrap());
+        drop(guard_1);
+
+        let mut boxed = LocklikeKind::StdRwLock.make_lock(0);
+        *boxed.get_mut() = 69;
+        assert_eq!(69, boxed.into_inner());
+    }
+
+    #[test]
+    fn std_mutex_exclusive_read() {
+        let lock = Mutex::new(0);
+        let guard = Locklike::read(&lock);
+        assert!(Locklike::try_read(&lock, Duration::ZERO).is_none());
+
+        // the upgrade never releases the mutex, so it succeeds regardless of the duration
+        let mut guard = guard.try_upgrade(Duration::ZERO).upgraded().unwrap();
+        *guard = 42;
+        let guard = guard.downgrade();
+        assert!(Locklike::try_write(&lock, Duration::ZERO).is_none());
+        drop(guard);
+
+        let mut boxed = LocklikeKind::StdMutex.make_lock(0);
+        *boxed.get_mut() = 69;
+        assert_eq!(69, boxed.into_inner());
+    }
+
+    #[test]
+    fn std_poisoned() {
+        let lock = Arc::new(RwLock::new(0));
+        {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let _guard = lock.write().unwrap();
+                panic!("poisoning the lock");
+            })
+            .join()
+            .unwrap_err();
+        }
+        assert!(lock.is_poisoned());
+        *Locklike::write(&*lock) = 42;
+        assert_eq!(42, *Locklike::try_read(&*lock, Duration::ZERO).unwrap());
+    }
+
+    #[test]
+    fn map_dyn_guards() {
+        for moderator in MODERATOR_KINDS {
+            let lock = moderator.make_lock_for_test((0, String::new()));
+
+            let mut guard = DynLockWriteGuard::map(lock.write(), |(_, s)| s);
+            guard.push_str("foo");
+            drop(guard);
+
+            let guard = DynLockWriteGuard::filter_map(lock.write(), |(n, _)| (*n > 0).then_some(n));
+            let mut guard = guard.err().unwrap();
+            let (n, _) = &mut *guard;
+            *n = 42;
+            drop(guard);
+
+            let guard = DynLockReadGuard::map(lock.read(), |(n, _)| n);
+            assert_eq!(42, *guard);
+            assert!(lock.try_write(Duration::ZERO).is_none());
+            drop(guard);
+
+            let guard = DynLockReadGuard::try_map(lock.read(), |(_, s)| s.strip_prefix('f').ok_or(()));
+            assert_eq!("oo", &*guard.ok().unwrap());
+
+            // all locks have been released
+            assert!(lock.try_write(Duration::ZERO).is_some());
+        }
+    }
+
+    #[test]
+    fn dyn_from_mapped_guards() {
+        let lock = ZLock::<_, ReadBiased>::new((0, 0));
+
+        let mut guard = DynMappedLockWriteGuard::from(LockWriteGuard::map(lock.write(), |(_, right)| right));
+        *guard = 42;
+        drop(guard);
+
+        let guard = DynMappedLockReadGuard::from(LockReadGuard::map(lock.read(), |(_, right)| right));
+        assert_eq!(42, *guard);
+        assert!(lock.try_write(Duration::ZERO).is_none());
+        drop(guard);
+
+        assert!(lock.try_write(Duration::ZERO).is_some());
     }
 
     fn takes_boxed(lock: LockBoxSized<u64>) {
@@ -0,0 +1,265 @@
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use crate::deadlock::LockId;
+use crate::zlock::{LockReadGuard, LockWriteGuard, Moderator};
+
+unsafe impl<U: ?Sized + Sync, M: Moderator> Sync for MappedLockReadGuard<'_, U, M> {}
+unsafe impl<U: ?Sized + Sync, M: Moderator> Sync for MappedLockWriteGuard<'_, U, M> {}
+
+impl<'a, T: ?Sized, M: Moderator> LockReadGuard<'a, T, M> {
+    /// Projects the guard onto a component of the locked data. The read lock is retained
+    /// by the returned guard.
+    ///
+    /// This is an associated function, invoked as `LockReadGuard::map(guard, f)`, so as not
+    /// to shadow a method of the same name on `T`.
+    #[inline]
+    pub fn map<U: ?Sized, F: FnOnce(&T) -> &U>(mut guard: Self, f: F) -> MappedLockReadGuard<'a, U, M> {
+        let data = NonNull::from(f(unsafe { guard.data.as_ref() }));
+        guard.locked = false;
+        MappedLockReadGuard::new(data, &guard.lock.sync, &guard.lock.id)
+    }
+
+    /// Attempts to project the guard onto a component of the locked data, returning the
+    /// original guard if the closure returns `None`.
+    #[inline]
+    pub fn filter_map<U: ?Sized, F: FnOnce(&T) -> Option<&U>>(mut guard: Self, f: F) -> Result<MappedLockReadGuard<'a, U, M>, Self> {
+        match f(unsafe { guard.data.as_ref() }) {
+            None => Err(guard),
+            Some(data) => {
+                let data = NonNull::from(data);
+                guard.locked = false;
+                Ok(MappedLockReadGuard::new(data, &guard.lock.sync, &guard.lock.id))
+            }
+        }
+    }
+
+    /// Attempts to project the guard onto a component of the locked data, returning the
+    /// original guard alongside the error if the closure fails.
+    #[inline]
+    pub fn try_map<U: ?Sized, E, F: FnOnce(&T) -> Result<&U, E>>(mut guard: Self, f: F) -> Result<MappedLockReadGuard<'a, U, M>, (Self, E)> {
+        match f(unsafe { guard.data.as_ref() }) {
+            Err(err) => Err((guard, err)),
+            Ok(data) => {
+                let data = NonNull::from(data);
+                guard.locked = false;
+                Ok(MappedLockReadGuard::new(data, &guard.lock.sync, &guard.lock.id))
+            }
+        }
+    }
+}
+
+impl<'a, T: ?Sized, M: Moderator> LockWriteGuard<'a, T, M> {
+    /// Projects the guard onto a component of the locked data. The write lock is retained
+    /// by the returned guard.
+    ///
+    /// This is an associated function, invoked as `LockWriteGuard::map(guard, f)`, so as not
+    /// to shadow a method of the same name on `T`.
+    #[inline]
+    pub fn map<U: ?Sized, F: FnOnce(&mut T) -> &mut U>(mut guard: Self, f: F) -> MappedLockWriteGuard<'a, U, M> {
+        let data = NonNull::from(f(unsafe { &mut *guard.lock.data.get() }));
+        guard.locked = false;
+        MappedLockWriteGuard::new(data, &guard.lock.sync, &guard.lock.id)
+    }
+
+    /// Attempts to project the guard onto a component of the locked data, returning the
+    /// original guard if the closure returns `None`.
+    #[inline]
+    pub fn filter_map<U: ?Sized, F: FnOnce(&mut T) -> Option<&mut U>>(mut guard: Self, f: F) -> Result<MappedLockWriteGuard<'a, U, M>, Self> {
+        match f(unsafe { &mut *guard.lock.data.get() }) {
+            None => Err(guard),
+            Some(data) => {
+                let data = NonNull::from(data);
+                guard.locked = false;
+                Ok(MappedLockWriteGuard::new(data, &guard.lock.sync, &guard.lock.id))
+            }
+        }
+    }
+
+    /// Attempts to project the guard onto a component of the locked data, returning the
+    /// original guard alongside the error if the closure fails.
+    #[inline]
+    pub fn try_map<U: ?Sized, E, F: FnOnce(&mut T) -> Result<&mut U, E>>(mut guard: Self, f: F) -> Result<MappedLockWriteGuard<'a, U, M>, (Self, E)> {
+        match f(unsafe { &mut *guard.lock.data.get() }) {
+            Err(err) => Err((guard, err)),
+            Ok(data) => {
+                let data = NonNull::from(data);
+                guard.locked = false;
+                Ok(MappedLockWriteGuard::new(data, &guard.lock.sync, &guard.lock.id))
+            }
+        }
+    }
+}
+
+/// A read guard projected onto a component of the locked data.
+///
+/// Unlike [`LockReadGuard`], a mapped guard cannot be upgraded, as the projection may
+/// refer to data that was never meant to be mutated.
+pub struct MappedLockReadGuard<'a, U: ?Sized + 'a, M: Moderator + 'a> {
+    data: NonNull<U>,
+    sync: &'a M::Sync,
+    id: &'a LockId,
+    locked: bool,
+
+    /// Emulates !Send for the struct. (Until issue 68318 -- negative trait bounds -- is resolved.)
+    __no_send: PhantomData<*const ()>,
+}
+
+impl<'a, U: ?Sized, M: Moderator> MappedLockReadGuard<'a, U, M> {
+    #[inline]
+    fn new(data: NonNull<U>, sync: &'a M::Sync, id: &'a LockId) -> Self {
+        Self {
+            data,
+            sync,
+            id,
+            locked: true,
+            __no_send: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn map<V: ?Sized, F: FnOnce(&U) -> &V>(mut guard: Self, f: F) -> MappedLockReadGuard<'a, V, M> {
+        let data = NonNull::from(f(unsafe { guard.data.as_ref() }));
+        guard.locked = false;
+        MappedLockReadGuard::new(data, guard.sync, guard.id)
+    }
+
+    #[inline]
+    pub fn filter_map<V: ?Sized, F: FnOnce(&U) -> Option<&V>>(mut guard: Self, f: F) -> Result<MappedLockReadGuard<'a, V, M>, Self> {
+        match f(unsafe { guard.data.as_ref() }) {
+            None => Err(guard),
+            Some(data) => {
+                let data = NonNull::from(data);
+                guard.locked = false;
+                Ok(MappedLockReadGuard::new(data, guard.sync, guard.id))
+            }
+        }
+    }
+
+    #[inline]
+    pub fn try_map<V: ?Sized, E, F: FnOnce(&U) -> Result<&V, E>>(mut guard: Self, f: F) -> Result<MappedLockReadGuard<'a, V, M>, (Self, E)> {
+        match f(unsafe { guard.data.as_ref() }) {
+            Err(err) => Err((guard, err)),
+            Ok(data) => {
+                let data = NonNull::from(data);
+                guard.locked = false;
+                Ok(MappedLockReadGuard::new(data, guard.sync, guard.id))
+            }
+        }
+    }
+}
+
+impl<U: ?Sized, M: Moderator> Drop for MappedLockReadGuard<'_, U, M> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.locked {
+            self.id.released();
+            M::read_unlock(self.sync);
+        }
+    }
+}
+
+impl<U: ?Sized, M: Moderator> Deref for MappedLockReadGuard<'_, U, M> {
+    type Target = U;
+
+    #[inline]
+    fn deref(&self) -> &U {
+        unsafe { self.data.as_ref() }
+    }
+}
+
+/// A write guard projected onto a component of the locked data.
+pub struct MappedLockWriteGuard<'a, U: ?Sized + 'a, M: Moderator + 'a> {
+    data: NonNull<U>,
+    sync: &'a M::Sync,
+    id: &'a LockId,
+    locked: bool,
+
+    /// Emulates !Send for the struct. (Until issue 68318 -- negative trait bounds -- is resolved.)
+    __no_send: PhantomData<*const ()>,
+
+    /// Keeps the guard invariant over `U`, as it hands out mutable references.
+    __variance: PhantomData<&'a mut U>,
+}
+
+impl<'a, U: ?Sized, M: Moderator> MappedLockWriteGuard<'a, U, M> {
+    #[inline]
+    fn new(data: NonNull<U>, sync: &'a M::Sync, id: &'a LockId) -> Self {
+        Self {
+            data,
+            sync,
+            id,
+            locked: true,
+            __no_send: PhantomData,
+            __variance: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn map<V: ?Sized, F: FnOnce(&mut U) -> &mut V>(mut guard: Self, f: F) -> MappedLockWriteGuard<'a, V, M> {
+        let data = NonNull::from(f(unsafe { guard.data.as_mut() }));
+        guard.locked = false;
+        MappedLockWriteGuard::new(data, guard.sync, guard.id)
+    }
+
+    #[inline]
+    pub fn filter_map<V: ?Sized, F: FnOnce(&mut U) -> Option<&mut V>>(mut guard: Self, f: F) -> Result<MappedLockWriteGuard<'a, V, M>, Self> {
+        match f(unsafe { guard.data.as_mut() }) {
+            None => Err(guard),
+            Some(data) => {
+                let data = NonNull::from(data);
+                guard.locked = false;
+                Ok(MappedLockWriteGuard::new(data, guard.sync, guard.id))
+            }
+        }
+    }
+
+    #[inline]
+    pub fn try_map<V: ?Sized, E, F: FnOnce(&mut U) -> Result<&mut V, E>>(mut guard: Self, f: F) -> Result<MappedLockWriteGuard<'a, V, M>, (Self, E)> {
+        match f(unsafe { guard.data.as_mut() }) {
+            Err(err) => Err((guard, err)),
+            Ok(data) => {
+                let data = NonNull::from(data);
+                guard.locked = false;
+                Ok(MappedLockWriteGuard::new(data, guard.sync, guard.id))
+            }
+        }
+    }
+
+    /// Atomically downgrades the write lock to a read lock, retaining the projection.
+    #[inline]
+    pub fn downgrade(mut guard: Self) -> MappedLockReadGuard<'a, U, M> {
+        guard.locked = false;
+        M::downgrade(guard.sync);
+        MappedLockReadGuard::new(guard.data, guard.sync, guard.id)
+    }
+}
+
+impl<U: ?Sized, M: Moderator> Drop for MappedLockWriteGuard<'_, U, M> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.locked {
+            self.id.released();
+            M::write_unlock(self.sync);
+        }
+    }
+}
+
+impl<U: ?Sized, M: Moderator> Deref for MappedLockWriteGuard<'_, U, M> {
+    type Target = U;
+
+    #[inline]
+    fn deref(&self) -> &U {
+        unsafe { self.data.as_ref() }
+    }
+}
+
+impl<U: ?Sized, M: Moderator> DerefMut for MappedLockWriteGuard<'_, U, M> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { self.data.as_mut() }
+    }
+}
+
+#[cfg(test)]
+mod tests;
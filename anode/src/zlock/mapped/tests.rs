@@ -0,0 +1,161 @@
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+use crate::zlock::{ArrivalOrdered, LockReadGuard, LockWriteGuard, MappedLockReadGuard, MappedLockWriteGuard, Moderator, PhaseFair, Prioritized, ReadBiased, Stochastic, WriteBiased, ZLock};
+
+#[derive(Debug, Default)]
+struct Pair {
+    left: u64,
+    right: Option<String>,
+}
+
+#[test]
+fn map_read() {
+    __map_read::<ReadBiased>();
+    __map_read::<WriteBiased>();
+    __map_read::<ArrivalOrdered>();
+    __map_read::<Stochastic>();
+    __map_read::<PhaseFair>();
+    __map_read::<Prioritized>();
+}
+
+fn __map_read<M: Moderator>() {
+    let lock = ZLock::<_, M>::new(Pair { left: 42, right: Some(String::from("foo")) });
+    let guard = LockReadGuard::map(lock.read(), |pair| &pair.left);
+    assert_eq!(42, *guard);
+
+    // the read lock is retained by the mapped guard
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    assert!(lock.try_read(Duration::ZERO).is_some());
+
+    // and released with it
+    drop(guard);
+    assert!(lock.try_write(Duration::ZERO).is_some());
+
+    // mapped guards can themselves be mapped
+    let guard = LockReadGuard::map(lock.read(), |pair| &pair.right);
+    let guard = MappedLockReadGuard::map(guard, |right| right.as_ref().unwrap().as_str());
+    assert_eq!("foo", &*guard);
+    drop(guard);
+    assert!(lock.try_write(Duration::ZERO).is_some());
+}
+
+#[test]
+fn map_write() {
+    __map_write::<ReadBiased>();
+    __map_write::<WriteBiased>();
+    __map_write::<ArrivalOrdered>();
+    __map_write::<Stochastic>();
+    __map_write::<PhaseFair>();
+    __map_write::<Prioritized>();
+}
+
+fn __map_write<M: Moderator>() {
+    let lock = ZLock::<_, M>::new(Pair::default());
+    let mut guard = LockWriteGuard::map(lock.write(), |pair| &mut pair.left);
+    *guard = 42;
+
+    // the write lock is retained by the mapped guard
+    assert!(lock.try_read(Duration::ZERO).is_none());
+
+    // downgrading retains the projection
+    let guard = MappedLockWriteGuard::downgrade(guard);
+    assert_eq!(42, *guard);
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    assert!(lock.try_read(Duration::ZERO).is_some());
+    drop(guard);
+
+    let guard = LockWriteGuard::map(lock.write(), |pair| &mut pair.right);
+    let mut guard = MappedLockWriteGuard::map(guard, |right| right.get_or_insert_with(String::new));
+    guard.push_str("bar");
+    drop(guard);
+
+    assert!(lock.try_write(Duration::ZERO).is_some());
+    let pair = lock.into_inner();
+    assert_eq!(42, pair.left);
+    assert_eq!(Some(String::from("bar")), pair.right);
+}
+
+#[test]
+fn filter_map() {
+    __filter_map::<ReadBiased>();
+    __filter_map::<WriteBiased>();
+    __filter_map::<ArrivalOrdered>();
+    __filter_map::<Stochastic>();
+    __filter_map::<PhaseFair>();
+    __filter_map::<Prioritized>();
+}
+
+fn __filter_map<M: Moderator>() {
+    let lock = ZLock::<_, M>::new(Pair::default());
+
+    // on failure, the original guard is returned
+    let guard = LockReadGuard::filter_map(lock.read(), |pair| pair.right.as_ref());
+    let guard = guard.err().unwrap();
+    assert_eq!(0, guard.left);
+    drop(guard);
+
+    let guard = LockWriteGuard::filter_map(lock.write(), |pair| pair.right.as_mut());
+    let mut guard = guard.err().unwrap();
+    guard.right = Some(String::from("foo"));
+    drop(guard);
+
+    // on success, the projection is returned
+    let guard = LockWriteGuard::filter_map(lock.write(), |pair| pair.right.as_mut());
+    let mut guard = guard.ok().unwrap();
+    guard.push_str("bar");
+    drop(guard);
+
+    let guard = LockReadGuard::filter_map(lock.read(), |pair| pair.right.as_ref());
+    assert_eq!("foobar", *guard.ok().unwrap());
+    assert!(lock.try_write(Duration::ZERO).is_some());
+}
+
+#[test]
+fn try_map() {
+    __try_map::<ReadBiased>();
+    __try_map::<WriteBiased>();
+    __try_map::<ArrivalOrdered>();
+    __try_map::<Stochastic>();
+    __try_map::<PhaseFair>();
+    __try_map::<Prioritized>();
+}
+
+fn __try_map<M: Moderator>() {
+    let lock = ZLock::<_, M>::new(Pair::default());
+
+    // on failure, the original guard is returned alongside the error
+    let res = LockReadGuard::try_map(lock.read(), |pair| pair.right.as_ref().ok_or("absent"));
+    let (guard, err) = res.err().unwrap();
+    assert_eq!("absent", err);
+    drop(guard);
+
+    let res = LockWriteGuard::try_map(lock.write(), |pair| pair.right.as_mut().ok_or(pair.left));
+    let (mut guard, err) = res.err().unwrap();
+    assert_eq!(0, err);
+    guard.right = Some(String::from("foo"));
+    drop(guard);
+
+    let res = LockReadGuard::try_map(lock.read(), |pair| pair.right.as_ref().ok_or("absent"));
+    assert_eq!("foo", *res.ok().unwrap());
+    assert!(lock.try_write(Duration::ZERO).is_some());
+}
+
+#[test]
+fn panic_in_map_releases_lock() {
+    __panic_in_map_releases_lock::<ReadBiased>();
+    __panic_in_map_releases_lock::<WriteBiased>();
+    __panic_in_map_releases_lock::<ArrivalOrdered>();
+    __panic_in_map_releases_lock::<Stochastic>();
+    __panic_in_map_releases_lock::<PhaseFair>();
+    __panic_in_map_releases_lock::<Prioritized>();
+}
+
+fn __panic_in_map_releases_lock<M: Moderator>() {
+    let lock = ZLock::<_, M>::new(Pair::default());
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        LockWriteGuard::map(lock.write(), |_: &mut Pair| -> &mut u64 { panic!() });
+    }));
+    assert!(res.is_err());
+    assert!(lock.try_write(Duration::ZERO).is_some());
+}
@@ -0,0 +1,392 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::ops::Deref;
+use std::mem;
+use std::time::Duration;
+use crate::backoff::ExpBackoff;
+use crate::deadline::Deadline;
+use crate::inf_iterator::{InfIterator, IntoInfIterator};
+use crate::rand::FIXED_DURATION;
+use crate::zlock::{LockReadGuard, LockWriteGuard, Moderator, ZLock};
+
+/// Acquires every lock in the set, blocking for as long as it takes. See [`try_lock_all`].
+#[inline]
+pub fn lock_all<S: LockSet>(set: S) -> S::Guard {
+    try_lock_all(set, Duration::MAX).unwrap()
+}
+
+/// Acquires every lock in the set, each with its own intent, or none of them. The set is
+/// either a tuple of (possibly heterogeneous) intents, or an array, vector or slice of
+/// intents of the same type. For example,
+/// ```
+/// use std::time::Duration;
+/// use anode::zlock::{try_lock_all, ReadBiased, ReadIntent, WriteIntent, ZLock};
+///
+/// let from = ZLock::<_, ReadBiased>::new(100);
+/// let to = ZLock::<_, ReadBiased>::new(String::from("balance"));
+/// let (mut from, to) = try_lock_all((WriteIntent(&from), ReadIntent(&to)), Duration::from_secs(1)).unwrap();
+/// *from -= 10;
+/// assert_eq!("balance", *to);
+/// ```
+///
+/// Deadlock is avoided by never waiting for one lock while holding another. The
+/// acquisition blocks on a single lock (initially, the first), then tries the remaining
+/// locks without waiting. If any lock is unavailable, all acquired locks are released,
+/// and, after backing off, the acquisition blocks on the unavailable lock. Hence, the order
+/// of locks in the set has no bearing on correctness.
+///
+/// Returns `None` if the locks could not be acquired before the duration elapsed, in
+/// which case none of the locks are held.
+///
+/// # Panics
+/// If the same lock appears more than once in the set.
+pub fn try_lock_all<S: LockSet>(set: S, duration: Duration) -> Option<S::Guard> {
+    let acquired = {
+        let locks = set.locks();
+        for (i, lock) in locks.iter().enumerate() {
+            assert!(
+                locks[..i].iter().all(|other| other.addr() != lock.addr()),
+                "a lock may appear only once in a lock set"
+            );
+        }
+        acquire_all(&locks, duration)
+    };
+
+    // SAFETY: every lock has been acquired with its intent
+    acquired.then(|| unsafe { set.into_guard() })
+}
+
+fn acquire_all(locks: &[&dyn sealed::Acquire], duration: Duration) -> bool {
+    if locks.is_empty() {
+        return true;
+    }
+
+    let mut deadline = Deadline::lazy_after(duration);
+    let mut rng = FIXED_DURATION;
+    let mut backoff = ExpBackoff::sleepy().into_inf_iter();
+    let mut anchor = 0;
+    loop {
+        if !locks[anchor].try_acquire(deadline.remaining()) {
+            return false;
+        }
+
+        let others = (0..locks.len()).filter(move |&i| i != anchor);
+        match others.clone().find(|&i| !locks[i].try_acquire(Duration::ZERO)) {
+            None => return true,
+            Some(unavailable) => {
+                // SAFETY: releases only the locks acquired in this round
+                for i in others.take_while(|&i| i != unavailable) {
+                    unsafe { locks[i].release() };
+                }
+                unsafe { locks[anchor].release() };
+                anchor = unavailable;
+                backoff.next().act(|| &mut rng);
+            }
+        }
+    }
+}
+
+mod sealed {
+    use std::time::Duration;
+
+    /// A type-erased lock, held with a specific intent.
+    pub trait Acquire {
+        /// The address of the lock, identifying it within a set.
+        fn addr(&self) -> *const ();
+
+        fn try_acquire(&self, duration: Duration) -> bool;
+
+        /// # Safety
+        /// The lock must have been acquired with [`try_acquire`](Self::try_acquire).
+        unsafe fn release(&self);
+    }
+
+    pub trait Intent: Acquire {
+        type Guard;
+
+        /// Wraps a lock that has been acquired with [`Acquire::try_acquire`] in a guard.
+        ///
+        /// # Safety
+        /// The lock must be held with this intent, and is released when the guard drops.
+        unsafe fn into_guard(self) -> Self::Guard;
+    }
+
+    pub trait Set {
+        type Guard;
+
+        fn locks(&self) -> Vec<&dyn Acquire>;
+
+        /// # Safety
+        /// Every lock in the set must be held with its intent.
+        unsafe fn into_guard(self) -> Self::Guard;
+    }
+}
+
+/// A lock together with the mode in which it is to be acquired, as part of a [`LockSet`].
+pub trait LockIntent: sealed::Intent {}
+
+impl<I: sealed::Intent> LockIntent for I {}
+
+/// A set of [`LockIntent`]s that may be acquired together with [`lock_all`] and
+/// [`try_lock_all`].
+pub trait LockSet: sealed::Set {}
+
+impl<S: sealed::Set> LockSet for S {}
+
+/// Acquires a lock for reading, yielding a [`LockReadGuard`].
+pub struct ReadIntent<'a, T: ?Sized, M: Moderator>(pub &'a ZLock<T, M>);
+
+/// Acquires a lock for writing, yielding a [`LockWriteGuard`].
+pub struct WriteIntent<'a, T: ?Sized, M: Moderator>(pub &'a ZLock<T, M>);
+
+/// Acquires a lock for either reading or writing, yielding an [`IntentGuard`]. For
+/// mixing intents in a set of locks of the same type.
+pub enum Intent<'a, T: ?Sized, M: Moderator> {
+    Read(&'a ZLock<T, M>),
+    Write(&'a ZLock<T, M>),
+}
+
+pub enum IntentGuard<'a, T: ?Sized, M: Moderator> {
+    Read(LockReadGuard<'a, T, M>),
+    Write(LockWriteGuard<'a, T, M>),
+}
+
+impl<T: ?Sized, M: Moderator> Clone for ReadIntent<'_, T, M> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Copy for ReadIntent<'_, T, M> {}
+
+impl<T: ?Sized, M: Moderator> Clone for WriteIntent<'_, T, M> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Copy for WriteIntent<'_, T, M> {}
+
+impl<T: ?Sized, M: Moderator> Clone for Intent<'_, T, M> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Copy for Intent<'_, T, M> {}
+
+impl<T: ?Sized, M: Moderator> ZLock<T, M> {
+    #[inline]
+    fn addr(&self) -> *const () {
+        self as *const Self as *const ()
+    }
+
+    #[inline]
+    fn try_read_forgotten(&self, duration: Duration) -> bool {
+        // the guard is reconstituted by the set's guard
+        self.try_read(duration).map(mem::forget).is_some()
+    }
+
+    #[inline]
+    fn try_write_forgotten(&self, duration: Duration) -> bool {
+        self.try_write(duration).map(mem::forget).is_some()
+    }
+}
+
+impl<T: ?Sized, M: Moderator> sealed::Acquire for ReadIntent<'_, T, M> {
+    #[inline]
+    fn addr(&self) -> *const () {
+        self.0.addr()
+    }
+
+    #[inline]
+    fn try_acquire(&self, duration: Duration) -> bool {
+        self.0.try_read_forgotten(duration)
+    }
+
+    #[inline]
+    unsafe fn release(&self) {
+        self.0.read_unlock();
+    }
+}
+
+impl<'a, T: ?Sized, M: Moderator> sealed::Intent for ReadIntent<'a, T, M> {
+    type Guard = LockReadGuard<'a, T, M>;
+
+    #[inline]
+    unsafe fn into_guard(self) -> Self::Guard {
+        self.0.read_guard()
+    }
+}
+
+impl<T: ?Sized, M: Moderator> sealed::Acquire for WriteIntent<'_, T, M> {
+    #[inline]
+    fn addr(&self) -> *const () {
+        self.0.addr()
+    }
+
+    #[inline]
+    fn try_acquire(&self, duration: Duration) -> bool {
+        self.0.try_write_forgotten(duration)
+    }
+
+    #[inline]
+    unsafe fn release(&self) {
+        self.0.write_unlock();
+    }
+}
+
+impl<'a, T: ?Sized, M: Moderator> sealed::Intent for WriteIntent<'a, T, M> {
+    type Guard = LockWriteGuard<'a, T, M>;
+
+    #[inline]
+    unsafe fn into_guard(self) -> Self::Guard {
+        self.0.write_guard()
+    }
+}
+
+impl<T: ?Sized, M: Moderator> sealed::Acquire for Intent<'_, T, M> {
+    #[inline]
+    fn addr(&self) -> *const () {
+        match self {
+            Intent::Read(lock) | Intent::Write(lock) => lock.addr(),
+        }
+    }
+
+    #[inline]
+    fn try_acquire(&self, duration: Duration) -> bool {
+        match self {
+            Intent::Read(lock) => lock.try_read_forgotten(duration),
+            Intent::Write(lock) => lock.try_write_forgotten(duration),
+        }
+    }
+
+    #[inline]
+    unsafe fn release(&self) {
+        match self {
+            Intent::Read(lock) => lock.read_unlock(),
+            Intent::Write(lock) => lock.write_unlock(),
+        }
+    }
+}
+
+impl<'a, T: ?Sized, M: Moderator> sealed::Intent for Intent<'a, T, M> {
+    type Guard = IntentGuard<'a, T, M>;
+
+    #[inline]
+    unsafe fn into_guard(self) -> Self::Guard {
+        match self {
+            Intent::Read(lock) => IntentGuard::Read(lock.read_guard()),
+            Intent::Write(lock) => IntentGuard::Write(lock.write_guard()),
+        }
+    }
+}
+
+impl<T: ?Sized, M: Moderator> IntentGuard<'_, T, M> {
+    /// Returns a mutable reference to the data if the lock is held for writing.
+    #[inline]
+    pub fn as_mut(&mut self) -> Option<&mut T> {
+        match self {
+            IntentGuard::Read(_) => None,
+            IntentGuard::Write(guard) => Some(guard),
+        }
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Deref for IntentGuard<'_, T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        match self {
+            IntentGuard::Read(guard) => guard,
+            IntentGuard::Write(guard) => guard,
+        }
+    }
+}
+
+impl<T: ?Sized + Debug, M: Moderator> Debug for IntentGuard<'_, T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntentGuard::Read(_) => f.debug_tuple("Read").field(&&**self).finish(),
+            IntentGuard::Write(_) => f.debug_tuple("Write").field(&&**self).finish(),
+        }
+    }
+}
+
+macro_rules! impl_set_for_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: LockIntent),+> sealed::Set for ($($name,)+) {
+            type Guard = ($($name::Guard,)+);
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn locks(&self) -> Vec<&dyn sealed::Acquire> {
+                let ($($name,)+) = self;
+                vec![$($name as &dyn sealed::Acquire),+]
+            }
+
+            #[inline]
+            #[allow(non_snake_case)]
+            unsafe fn into_guard(self) -> Self::Guard {
+                let ($($name,)+) = self;
+                ($($name.into_guard(),)+)
+            }
+        }
+    };
+}
+
+impl_set_for_tuple!(A);
+impl_set_for_tuple!(A, B);
+impl_set_for_tuple!(A, B, C);
+impl_set_for_tuple!(A, B, C, D);
+impl_set_for_tuple!(A, B, C, D, E);
+impl_set_for_tuple!(A, B, C, D, E, F);
+
+impl<I: LockIntent, const N: usize> sealed::Set for [I; N] {
+    type Guard = [I::Guard; N];
+
+    #[inline]
+    fn locks(&self) -> Vec<&dyn sealed::Acquire> {
+        self.iter().map(|intent| intent as &dyn sealed::Acquire).collect()
+    }
+
+    #[inline]
+    unsafe fn into_guard(self) -> Self::Guard {
+        self.map(|intent| intent.into_guard())
+    }
+}
+
+impl<I: LockIntent> sealed::Set for Vec<I> {
+    type Guard = Vec<I::Guard>;
+
+    #[inline]
+    fn locks(&self) -> Vec<&dyn sealed::Acquire> {
+        self.iter().map(|intent| intent as &dyn sealed::Acquire).collect()
+    }
+
+    #[inline]
+    unsafe fn into_guard(self) -> Self::Guard {
+        self.into_iter().map(|intent| intent.into_guard()).collect()
+    }
+}
+
+impl<I: LockIntent + Copy> sealed::Set for &[I] {
+    type Guard = Vec<I::Guard>;
+
+    #[inline]
+    fn locks(&self) -> Vec<&dyn sealed::Acquire> {
+        self.iter().map(|intent| intent as &dyn sealed::Acquire).collect()
+    }
+
+    #[inline]
+    unsafe fn into_guard(self) -> Self::Guard {
+        self.iter().map(|intent| intent.into_guard()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use crate::test_utils::SHORT_WAIT;
+use crate::zlock::{lock_all, try_lock_all, ArrivalOrdered, Intent, Moderator, PhaseFair, Prioritized, ReadBiased, ReadIntent, Stochastic, WriteBiased, WriteIntent, ZLock};
+
+#[test]
+fn tuple_cycle() {
+    __tuple_cycle::<ReadBiased>();
+    __tuple_cycle::<WriteBiased>();
+    __tuple_cycle::<ArrivalOrdered>();
+    __tuple_cycle::<Stochastic>();
+    __tuple_cycle::<PhaseFair>();
+    __tuple_cycle::<Prioritized>();
+}
+
+fn __tuple_cycle<M: Moderator>() {
+    let a = ZLock::<_, M>::new(0);
+    let b = ZLock::<_, M>::new("b");
+    let c = ZLock::<_, ReadBiased>::new(vec![1, 2]);
+
+    let (mut guard_a, guard_b, mut guard_c) = lock_all((WriteIntent(&a), ReadIntent(&b), WriteIntent(&c)));
+    *guard_a = 42;
+    assert_eq!("b", *guard_b);
+    guard_c.push(3);
+
+    assert!(a.try_read(Duration::ZERO).is_none());
+    assert!(b.try_read(Duration::ZERO).is_some());
+    assert!(b.try_write(Duration::ZERO).is_none());
+    assert!(c.try_read(Duration::ZERO).is_none());
+
+    drop((guard_a, guard_b, guard_c));
+    assert!(a.try_write(Duration::ZERO).is_some());
+    assert!(b.try_write(Duration::ZERO).is_some());
+    assert_eq!(42, a.into_inner());
+    assert_eq!(vec![1, 2, 3], c.into_inner());
+}
+
+#[test]
+fn all_or_nothing() {
+    let a = ZLock::<_, WriteBiased>::new(0);
+    let b = ZLock::<_, WriteBiased>::new(0);
+    let guard_b = b.read();
+
+    // b cannot be write-acquired, so neither lock is acquired
+    assert!(try_lock_all((WriteIntent(&a), WriteIntent(&b)), SHORT_WAIT).is_none());
+    assert!(a.try_write(Duration::ZERO).is_some());
+
+    // reading is compatible with the outstanding read lock
+    let guards = try_lock_all((WriteIntent(&a), ReadIntent(&b)), SHORT_WAIT);
+    assert!(guards.is_some());
+    drop(guards);
+    drop(guard_b);
+}
+
+#[test]
+fn mixed_intents() {
+    let locks = [ZLock::<_, ArrivalOrdered>::new(0), ZLock::new(1), ZLock::new(2)];
+    let intents = [Intent::Read(&locks[0]), Intent::Write(&locks[1]), Intent::Read(&locks[2])];
+
+    let mut guards = try_lock_all(&intents[..], Duration::ZERO).unwrap();
+    assert_eq!(vec![0, 1, 2], guards.iter().map(|guard| **guard).collect::<Vec<_>>());
+    assert!(guards[0].as_mut().is_none());
+    *guards[1].as_mut().unwrap() = 10;
+    assert!(format!("{:?}", guards[1]).contains("Write(10)"), "{:?}", guards[1]);
+    drop(guards);
+
+    let guards = lock_all(intents);
+    assert_eq!(10, *guards[1]);
+    drop(guards);
+
+    let guards = lock_all(locks.iter().map(WriteIntent).collect::<Vec<_>>());
+    assert_eq!(3, guards.len());
+    drop(guards);
+
+    assert!(try_lock_all(Vec::<WriteIntent<(), ReadBiased>>::new(), Duration::ZERO).unwrap().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "a lock may appear only once in a lock set")]
+fn duplicate_lock() {
+    let lock = ZLock::<_, ReadBiased>::new(0);
+    try_lock_all((ReadIntent(&lock), ReadIntent(&lock)), Duration::ZERO);
+}
+
+#[test]
+fn opposing_orders() {
+    __opposing_orders::<ReadBiased>();
+    __opposing_orders::<WriteBiased>();
+    __opposing_orders::<ArrivalOrdered>();
+    __opposing_orders::<Stochastic>();
+    __opposing_orders::<PhaseFair>();
+    __opposing_orders::<Prioritized>();
+}
+
+/// Threads that acquire the same locks in opposing orders do not deadlock.
+fn __opposing_orders<M: Moderator + 'static>() {
+    const ITERATIONS: usize = 100;
+    let locks = Arc::new((ZLock::<_, M>::new(0), ZLock::<_, M>::new(0)));
+
+    let threads = (0..4).map(|i| {
+        let locks = locks.clone();
+        thread::spawn(move || {
+            for _ in 0..ITERATIONS {
+                let (a, b) = &*locks;
+                let (mut guard_1, mut guard_2) = if i % 2 == 0 {
+                    lock_all((WriteIntent(a), WriteIntent(b)))
+                } else {
+                    let (guard_b, guard_a) = lock_all((WriteIntent(b), WriteIntent(a)));
+                    (guard_a, guard_b)
+                };
+                *guard_1 += 1;
+                *guard_2 += 1;
+            }
+        })
+    }).collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(4 * ITERATIONS, *locks.0.read());
+    assert_eq!(4 * ITERATIONS, *locks.1.read());
+}
@@ -0,0 +1,527 @@
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use crate::backoff::ExpBackoff;
+use crate::deadline::Deadline;
+use crate::monitor::{Directive, Monitor, Notification, SpeculativeMonitor};
+use crate::zlock::{AsyncModerator, LockState, Moderator, ModeratorMonitor, ThreadAgnostic, Tickets, UpgradableModerator, Waiter, READ_QUEUE, WRITE_QUEUE};
+use crate::zlock::word::{Enlistment, LockWord, READER, UPGRADABLE, WRITER};
+
+/// Alternates between read and write phases, after Brandenburg and Anderson's phase-fair
+/// reader-writer locks.
+///
+/// A reader that arrives while a writer is waiting is held back until the end of the next
+/// write phase. Conversely, when a write phase ends, all readers that were waiting for it
+/// are admitted before the next writer, even if writers are queued. Writers are admitted
+/// in the order of arrival. Hence, a reader waits for at most one write phase, and a writer
+/// waits for at most one read phase, plus the write phases of the writers ahead of it.
+#[derive(Debug)]
+pub struct PhaseFair;
+
+pub struct PhaseFairSync {
+    word: LockWord,
+    monitor: ModeratorMonitor<PhaseFairState>,
+}
+
+impl PhaseFairSync {
+    const fn new(backoff: &'static ExpBackoff) -> Self {
+        Self {
+            word: LockWord::new(),
+            monitor: SpeculativeMonitor::with_queues(PhaseFairState {
+                phase: 0,
+                waiting_readers: 0,
+                admitting_readers: 0,
+                waiting_writers: 0,
+                tickets: Tickets::INIT,
+            }, backoff),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PhaseFairState {
+    /// The number of write phases that have ended.
+    phase: u64,
+
+    /// The number of readers waiting for the lock.
+    waiting_readers: u32,
+
+    /// The number of readers that were waiting when the last write phase ended, and are
+    /// yet to be admitted (or give up). Writers defer to these readers.
+    admitting_readers: u32,
+
+    /// The number of writers (including upgraders) waiting for the lock. Readers that arrive
+    /// while there are waiting writers are held back until the next write phase ends.
+    waiting_writers: u32,
+
+    /// The tickets of the writers, which are admitted in the order of their arrival.
+    tickets: Tickets,
+}
+
+impl PhaseFairState {
+    /// Ends a write phase, admitting the readers that are presently waiting.
+    #[inline]
+    fn end_write_phase(&mut self) {
+        self.phase += 1;
+        self.admitting_readers = self.waiting_readers;
+    }
+}
+
+impl Moderator for PhaseFair {
+    type Sync = PhaseFairSync;
+    type ModeratorConfig = &'static ExpBackoff;
+
+    const INIT: Self::Sync = PhaseFairSync::new(&ExpBackoff::SLEEPY);
+
+    /// The backoff policy applies to the monitor's tracker.
+    #[inline]
+    fn with_config(config: Self::ModeratorConfig) -> Self::Sync {
+        PhaseFairSync::new(config)
+    }
+
+    #[inline]
+    fn try_read(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_read_until(sync, Deadline::lazy_after(duration))
+    }
+
+    #[inline]
+    fn try_read_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_read() {
+            return true;
+        }
+
+        let mut waiter = ReadWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn read_unlock(sync: &Self::Sync) {
+        if sync.word.try_fast_read_unlock() {
+            return;
+        }
+
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() > 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(!sync.word.load().writer());
+
+            // readers are never held back by other readers
+            match sync.word.shift(READER, 0).readers() {
+                0 | 1 => Directive::NotifyAndReturn(Notification::all(WRITE_QUEUE)),
+                _ => Directive::Return
+            }
+        });
+    }
+
+    #[inline]
+    fn try_write(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_write_until(sync, Deadline::lazy_after(duration))
+    }
+
+    #[inline]
+    fn try_write_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_write() {
+            return true;
+        }
+
+        let mut waiter = WriteWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn write_unlock(sync: &Self::Sync) {
+        if sync.word.try_fast_write_unlock() {
+            return;
+        }
+
+        end_write_phase(sync, 0);
+    }
+
+    fn downgrade(sync: &Self::Sync) {
+        end_write_phase(sync, READER);
+    }
+
+    fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_upgrade_until(sync, Deadline::lazy_after(duration))
+    }
+
+    fn try_upgrade_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        let mut waiter = UpgradeWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn inspect(sync: &Self::Sync) -> LockState {
+        sync.monitor.compute(|state| LockState {
+            writer_pending: state.waiting_writers > 0,
+            tickets: Some(state.tickets),
+            ..sync.word.load().inspect()
+        })
+    }
+}
+
+unsafe impl ThreadAgnostic for PhaseFair {}
+
+impl UpgradableModerator for PhaseFair {
+    #[inline]
+    fn try_read_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = ReadWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn upgradable_unlock(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, 0);
+            Directive::NotifyAllAndReturn
+        });
+    }
+
+    fn try_upgrade_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = UpgradeWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn downgrade_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+
+    fn downgrade_to_upgradable(sync: &Self::Sync) {
+        end_write_phase(sync, UPGRADABLE);
+    }
+}
+
+impl AsyncModerator for PhaseFair {
+    type ReadWaiter = ReadWaiter;
+    type WriteWaiter = WriteWaiter;
+    type UpgradeWaiter = UpgradeWaiter;
+
+    #[inline]
+    fn forget(sync: &Self::Sync, waker: &Waker) {
+        sync.monitor.forget(waker);
+    }
+}
+
+/// Releases the write lock, retaining the given mode (if any), and admits the readers that
+/// waited out the write phase. The writers are notified only if the lock is relinquished.
+#[inline]
+fn end_write_phase(sync: &PhaseFairSync, to: u64) {
+    sync.monitor.enter(|state| {
+        debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+        debug_assert!(sync.word.load().writer());
+
+        sync.word.shift(WRITER, to);
+        state.end_write_phase();
+
+        if to == 0 {
+            Directive::NotifyAllAndReturn
+        } else {
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        }
+    });
+}
+
+/// Withdraws a reader that gave up waiting, notifying the writers that may have deferred
+/// to it.
+#[inline]
+fn withdraw_reader(sync: &PhaseFairSync, arrival_phase: u64) {
+    sync.monitor.enter(|state| {
+        state.waiting_readers -= 1;
+        if arrival_phase < state.phase {
+            state.admitting_readers -= 1;
+        }
+        Directive::NotifyAndReturn(Notification::all(WRITE_QUEUE))
+    });
+}
+
+/// Withdraws a writer (or upgrader) that gave up waiting, forfeiting its ticket (if any)
+/// and releasing the readers that were held back by it. The writers are notified only if
+/// a ticket was forfeited.
+#[inline]
+fn withdraw_writer(sync: &PhaseFairSync, forfeit_ticket: bool) {
+    sync.monitor.enter(|state| {
+        state.waiting_writers -= 1;
+        if forfeit_ticket {
+            state.tickets.service();
+        }
+
+        if forfeit_ticket {
+            Directive::NotifyAllAndReturn
+        } else {
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        }
+    });
+}
+
+pub struct ReadWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    upgradable: bool,
+
+    /// The phase in which the reader arrived, assigned upon the first step.
+    arrival_phase: Option<u64>,
+    waiting: bool,
+    enlistment: Enlistment,
+}
+
+impl ReadWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, upgradable: false, arrival_phase: None, waiting: false, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, upgradable: true, arrival_phase: None, waiting: false, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut PhaseFairState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let arrival_phase = *self.arrival_phase.get_or_insert(state.phase);
+            let word_state = word.load();
+
+            // a reader that arrived during the read phase yields to the waiting writers
+            let admissible = state.waiting_writers == 0 || arrival_phase < state.phase;
+            if !word_state.writer() && admissible && (!self.upgradable || !word_state.upgradable()) {
+                self.acquired = true;
+                if self.waiting {
+                    self.waiting = false;
+                    state.waiting_readers -= 1;
+                    if arrival_phase < state.phase {
+                        state.admitting_readers -= 1;
+                    }
+                }
+                self.enlistment.acquire(word, 0, if self.upgradable { UPGRADABLE } else { READER });
+            } else if !self.waiting {
+                self.waiting = true;
+                state.waiting_readers += 1;
+            }
+        }
+
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(READ_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &PhaseFairSync) {
+        if self.waiting {
+            self.waiting = false;
+            withdraw_reader(sync, self.arrival_phase.unwrap());
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for ReadWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<PhaseFairSync> for ReadWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &PhaseFairSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &PhaseFairSync) {
+        self.conclude(sync);
+    }
+}
+
+pub struct WriteWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    ticket: u64,
+    waiting: bool,
+    enlistment: Enlistment,
+}
+
+impl WriteWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, ticket: 0, waiting: false, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut PhaseFairState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            if self.ticket == 0 {
+                self.ticket = state.tickets.take();
+            }
+
+            let word_state = word.load();
+            if word_state.readers() == 0
+                && !word_state.writer()
+                && !word_state.upgradable()
+                && state.admitting_readers == 0
+                && state.tickets.is_turn(self.ticket)
+            {
+                self.acquired = true;
+                state.tickets.service();
+                if self.waiting {
+                    self.waiting = false;
+                    state.waiting_writers -= 1;
+                }
+                self.enlistment.acquire(word, 0, WRITER);
+            } else if !self.waiting {
+                self.waiting = true;
+                state.waiting_writers += 1;
+            }
+        }
+
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &PhaseFairSync) {
+        if self.waiting {
+            self.waiting = false;
+            withdraw_writer(sync, true);
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for WriteWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<PhaseFairSync> for WriteWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &PhaseFairSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &PhaseFairSync) {
+        self.conclude(sync);
+    }
+}
+
+/// An upgrader already holds a read lock; it neither takes a ticket nor defers to the
+/// admitted readers (which would otherwise wait on it), but it does hold back arriving
+/// readers, as any other waiting writer.
+pub struct UpgradeWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    waiting: bool,
+    enlistment: Enlistment,
+
+    /// Whether the lock being upgraded is an upgradable read lock, as opposed to an
+    /// ordinary read lock.
+    upgradable: bool,
+}
+
+impl UpgradeWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, waiting: false, enlistment: Enlistment::default(), upgradable: false }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, waiting: false, enlistment: Enlistment::default(), upgradable: true }
+    }
+
+    /// An upgradable reader need only wait for the ordinary readers to drain, whereas an
+    /// ordinary reader must be the sole holder of the lock.
+    #[inline]
+    fn can_acquire(&self, readers: u32, upgradable: bool) -> bool {
+        if self.upgradable {
+            debug_assert!(upgradable);
+            readers == 0
+        } else {
+            readers == 1 && !upgradable
+        }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut PhaseFairState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let word_state = word.load();
+            debug_assert!(!word_state.writer());
+
+            if self.can_acquire(word_state.readers(), word_state.upgradable()) {
+                self.acquired = true;
+                if self.waiting {
+                    self.waiting = false;
+                    state.waiting_writers -= 1;
+                }
+                self.enlistment.acquire(word, if self.upgradable { UPGRADABLE } else { READER }, WRITER);
+            } else if !self.waiting {
+                self.waiting = true;
+                state.waiting_writers += 1;
+            }
+        }
+
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &PhaseFairSync) {
+        if self.waiting {
+            self.waiting = false;
+            withdraw_writer(sync, false);
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for UpgradeWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<PhaseFairSync> for UpgradeWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &PhaseFairSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &PhaseFairSync) {
+        self.conclude(sync);
+    }
+}
+
+#[cfg(test)]
+mod tests;
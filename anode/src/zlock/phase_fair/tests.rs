@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::executor::{Executor, Queue, Submitter, ThreadPool};
+use crate::monitor::Monitor;
+use crate::test_utils::{LONG_WAIT, SHORT_WAIT};
+use crate::wait;
+use crate::wait::{Wait, WaitResult};
+use crate::zlock::{PhaseFair, ZLock};
+
+#[test]
+fn timeout_in_write_unblocks_readers() {
+    let lock = ZLock::<_, PhaseFair>::new(0);
+    let guard_1 = lock.read();
+
+    // cannot acquire a write lock with an active reader
+    let guard_2 = lock.try_write(SHORT_WAIT);
+    assert!(guard_2.is_none());
+
+    // the timeout should have withdrawn the writer, forfeiting its ticket
+    assert_eq!(0, lock.waiting_writers());
+    let guard_3 = lock.try_read(Duration::ZERO);
+    assert!(guard_3.is_some());
+    drop(guard_1);
+    drop(guard_3);
+
+    let guard_4 = lock.try_write(Duration::ZERO);
+    assert!(guard_4.is_some());
+}
+
+#[test]
+fn timeout_in_upgrade_unblocks_readers() {
+    let lock = ZLock::<_, PhaseFair>::new(0);
+    let guard_1 = lock.read();
+
+    // cannot upgrade a read lock with another active reader
+    let guard_2 = lock.read().try_upgrade(SHORT_WAIT);
+    assert!(guard_2.is_unchanged());
+    assert_eq!(0, lock.waiting_writers());
+
+    let guard_3 = lock.try_read(Duration::ZERO);
+    assert!(guard_3.is_some());
+    drop(guard_1);
+    drop(guard_2);
+    drop(guard_3);
+}
+
+#[test]
+fn timeout_in_read_withdraws_reader() {
+    let lock = ZLock::<_, PhaseFair>::new(0);
+    let guard_1 = lock.write();
+
+    let guard_2 = lock.try_read(SHORT_WAIT);
+    assert!(guard_2.is_none());
+    assert_eq!(0, lock.waiting_readers());
+
+    // the write phase ends with no readers to admit, so the next writer is not held back
+    drop(guard_1);
+    assert_eq!(0, lock.admitting_readers());
+    let guard_3 = lock.try_write(Duration::ZERO);
+    assert!(guard_3.is_some());
+}
+
+#[test]
+fn pending_writer_holds_back_readers() {
+    let lock = Arc::new(ZLock::<_, PhaseFair>::new(0));
+    let guard_1 = lock.read();
+
+    let t_2 = ThreadPool::new(1, Queue::Unbounded);
+    let t_2_write = {
+        let lock = lock.clone();
+        t_2.submitter().submit(move || {
+            let guard_2 = lock.write();
+            drop(guard_2);
+        })
+    };
+
+    // wait until t_2 is queued behind the read lock
+    lock.wait_for_waiting_writers(1, LONG_WAIT).unwrap();
+
+    // a newly arriving reader is held back until the write phase ends
+    let guard_3 = lock.try_read(SHORT_WAIT);
+    assert!(guard_3.is_none());
+    assert_eq!(0, lock.waiting_readers());
+
+    // release the read lock; this will unblock t_2
+    drop(guard_1);
+    assert!(t_2_write.get().is_success());
+    assert_eq!(0, lock.waiting_writers());
+
+    let guard_4 = lock.try_read(Duration::ZERO);
+    assert!(guard_4.is_some());
+}
+
+#[test]
+fn readers_admitted_before_next_writer() {
+    let lock = Arc::new(ZLock::<_, PhaseFair>::new(0));
+    let order = Arc::new(Mutex::new(vec![]));
+    let guard_1 = lock.write();
+
+    let t_2 = ThreadPool::new(1, Queue::Unbounded);
+    let t_2_read = {
+        let (lock, order) = (lock.clone(), order.clone());
+        t_2.submitter().submit(move || {
+            let guard_2 = lock.read();
+            order.lock().unwrap().push("read");
+            drop(guard_2);
+        })
+    };
+    lock.wait_for_waiting_readers(1, LONG_WAIT).unwrap();
+
+    let t_3 = ThreadPool::new(1, Queue::Unbounded);
+    let t_3_write = {
+        let (lock, order) = (lock.clone(), order.clone());
+        t_3.submitter().submit(move || {
+            let guard_3 = lock.write();
+            order.lock().unwrap().push("write");
+            drop(guard_3);
+        })
+    };
+    lock.wait_for_waiting_writers(1, LONG_WAIT).unwrap();
+
+    // ending the write phase admits t_2 ahead of t_3, despite t_3 being queued
+    drop(guard_1);
+    assert!(t_2_read.get().is_success());
+    assert!(t_3_write.get().is_success());
+    assert_eq!(vec!["read", "write"], *order.lock().unwrap());
+    assert_eq!(0, lock.waiting_readers());
+    assert_eq!(0, lock.admitting_readers());
+    assert_eq!(0, lock.waiting_writers());
+}
+
+impl<T> ZLock<T, PhaseFair> {
+    fn waiting_readers(&self) -> u32 {
+        self.sync.monitor.compute(|state| state.waiting_readers)
+    }
+
+    fn admitting_readers(&self) -> u32 {
+        self.sync.monitor.compute(|state| state.admitting_readers)
+    }
+
+    fn waiting_writers(&self) -> u32 {
+        self.sync.monitor.compute(|state| state.waiting_writers)
+    }
+
+    fn wait_for_waiting_readers(&self, target: u32, duration: Duration) -> WaitResult {
+        wait::Spin::wait_for_inequality(|| self.waiting_readers(), Ordering::is_eq, &target, duration)
+    }
+
+    fn wait_for_waiting_writers(&self, target: u32, duration: Duration) -> WaitResult {
+        wait::Spin::wait_for_inequality(|| self.waiting_writers(), Ordering::is_eq, &target, duration)
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use crate::chalice::{ChaliceResult, Poisoned};
+use crate::zlock::{LockReadGuard, LockWriteGuard, Moderator, UpgradeOutcome, ZLock};
+
+/// A [`ZLock`] that opts into poisoning, following the semantics of [`Chalice`](crate::chalice::Chalice):
+/// a writer that panics while holding the lock poisons it, and every subsequent acquisition
+/// reports the poisoning by returning its guard wrapped in [`Poisoned`].
+///
+/// Only write guards poison the lock. A panic while a read guard (or an upgradable read
+/// guard) is held leaves the lock unpoisoned, as a reader cannot have left the data in an
+/// inconsistent state. A write guard poisons the lock if it is dropped or downgraded while
+/// its thread is unwinding, unless the thread was already panicking when the lock was
+/// acquired — such as from within a `Drop` implementation during unwinding — in which case
+/// the panic is not attributed to the critical section. Once poisoned, the lock remains so
+/// until [`clear_poison`](Self::clear_poison) is called.
+///
+/// As with [`std::sync::RwLock`], poisoning is advisory. The guard is always handed out, and
+/// the result may be unwrapped either explicitly or through [`Remedy`](crate::remedy::Remedy).
+pub struct PoisonZLock<T: ?Sized, M: Moderator> {
+    poisoned: AtomicBool,
+    lock: ZLock<T, M>,
+}
+
+impl<T, M: Moderator> PoisonZLock<T, M> {
+    #[inline]
+    pub const fn new(t: T) -> Self {
+        Self {
+            poisoned: AtomicBool::new(false),
+            lock: ZLock::new(t),
+        }
+    }
+
+    pub fn into_inner(self) -> ChaliceResult<T> {
+        let poisoned = self.poisoned.into_inner();
+        wrap(poisoned, self.lock.into_inner())
+    }
+}
+
+impl<T: ?Sized, M: Moderator> PoisonZLock<T, M> {
+    #[inline]
+    pub fn read(&self) -> ChaliceResult<PoisonReadGuard<'_, T, M>> {
+        self.try_read(Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    pub fn try_read(&self, duration: Duration) -> Option<ChaliceResult<PoisonReadGuard<'_, T, M>>> {
+        self.lock.try_read(duration).map(|guard| {
+            wrap(self.is_poisoned(), PoisonReadGuard { guard, poison: &self.poisoned })
+        })
+    }
+
+    #[inline]
+    pub fn write(&self) -> ChaliceResult<PoisonWriteGuard<'_, T, M>> {
+        self.try_write(Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    pub fn try_write(&self, duration: Duration) -> Option<ChaliceResult<PoisonWriteGuard<'_, T, M>>> {
+        self.lock.try_write(duration).map(|guard| {
+            wrap(self.is_poisoned(), PoisonWriteGuard::new(guard, &self.poisoned))
+        })
+    }
+
+    /// Determines whether the lock has been poisoned by a panicking writer.
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    /// Clears the poisoned state, signifying that the caller has restored the data to a
+    /// consistent state.
+    #[inline]
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns a mutable reference to the underlying data, wrapped in [`Poisoned`] if the
+    /// lock has been poisoned.
+    #[inline]
+    pub fn get_mut(&mut self) -> ChaliceResult<&mut T> {
+        let poisoned = self.is_poisoned();
+        wrap(poisoned, self.lock.get_mut())
+    }
+}
+
+#[inline]
+fn wrap<T>(poisoned: bool, t: T) -> ChaliceResult<T> {
+    if poisoned {
+        Err(Poisoned(t))
+    } else {
+        Ok(t)
+    }
+}
+
+pub struct PoisonReadGuard<'a, T: ?Sized + 'a, M: Moderator + 'a> {
+    guard: LockReadGuard<'a, T, M>,
+    poison: &'a AtomicBool,
+}
+
+impl<'a, T: ?Sized, M: Moderator> PoisonReadGuard<'a, T, M> {
+    /// Upgrades the read lock to a write lock. Since readers cannot poison the lock, the
+    /// upgrade never observes a change in the poisoned state.
+    #[inline]
+    pub fn upgrade(self) -> PoisonWriteGuard<'a, T, M> {
+        PoisonWriteGuard::new(self.guard.upgrade(), self.poison)
+    }
+
+    #[inline]
+    pub fn try_upgrade(self, duration: Duration) -> PoisonUpgradeOutcome<'a, T, M> {
+        let poison = self.poison;
+        self.guard.try_upgrade(duration).map(
+            |guard| PoisonWriteGuard::new(guard, poison),
+            |guard| PoisonReadGuard { guard, poison },
+        )
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Deref for PoisonReadGuard<'_, T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+pub struct PoisonWriteGuard<'a, T: ?Sized + 'a, M: Moderator + 'a> {
+    guard: LockWriteGuard<'a, T, M>,
+    poison: &'a AtomicBool,
+
+    /// Whether the thread was already panicking when the lock was acquired, in which case
+    /// the ongoing panic does not poison the lock.
+    panicking: bool,
+}
+
+impl<'a, T: ?Sized, M: Moderator> PoisonWriteGuard<'a, T, M> {
+    #[inline]
+    fn new(guard: LockWriteGuard<'a, T, M>, poison: &'a AtomicBool) -> Self {
+        Self {
+            guard,
+            poison,
+            panicking: thread::panicking(),
+        }
+    }
+
+    #[inline]
+    fn poison_if_panicking(&self) {
+        if !self.panicking && thread::panicking() {
+            self.poison.store(true, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    pub fn downgrade(self) -> PoisonReadGuard<'a, T, M> {
+        let poison = self.poison;
+        PoisonReadGuard { guard: self.into_guard().downgrade(), poison }
+    }
+
+    /// Relinquishes the poisoning responsibility, returning the underlying guard.
+    #[inline]
+    fn into_guard(self) -> LockWriteGuard<'a, T, M> {
+        self.poison_if_panicking();
+        let this = ManuallyDrop::new(self);
+        unsafe { ptr::read(&this.guard) }
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Drop for PoisonWriteGuard<'_, T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        self.poison_if_panicking();
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Deref for PoisonWriteGuard<'_, T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized, M: Moderator> DerefMut for PoisonWriteGuard<'_, T, M> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized + Debug, M: Moderator> Debug for PoisonReadGuard<'_, T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Debug, M: Moderator> Debug for PoisonWriteGuard<'_, T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+pub type PoisonUpgradeOutcome<'a, T, M> = UpgradeOutcome<PoisonWriteGuard<'a, T, M>, PoisonReadGuard<'a, T, M>>;
+
+impl<T: ?Sized + Debug, M: Moderator> Debug for PoisonZLock<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoisonZLock")
+            .field("lock", &&self.lock)
+            .field("poisoned", &self.is_poisoned())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests;
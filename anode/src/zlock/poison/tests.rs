@@ -0,0 +1,112 @@
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+use crate::remedy::Remedy;
+use crate::zlock::{ArrivalOrdered, Moderator, PoisonZLock, PhaseFair, Prioritized, ReadBiased, Stochastic, WriteBiased};
+
+#[test]
+fn unpoisoned_cycle() {
+    __unpoisoned_cycle::<ReadBiased>();
+    __unpoisoned_cycle::<WriteBiased>();
+    __unpoisoned_cycle::<ArrivalOrdered>();
+    __unpoisoned_cycle::<Stochastic>();
+    __unpoisoned_cycle::<PhaseFair>();
+    __unpoisoned_cycle::<Prioritized>();
+}
+
+fn __unpoisoned_cycle<M: Moderator>() {
+    let lock = PoisonZLock::<_, M>::new(0);
+    assert!(!lock.is_poisoned());
+
+    let guard = lock.read().unwrap();
+    assert_eq!(0, *guard);
+    let mut guard = guard.upgrade();
+    *guard = 42;
+    let guard = guard.downgrade();
+    assert_eq!(42, *guard);
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    drop(guard);
+
+    let mut guard = lock.write().unwrap();
+    *guard += 1;
+    drop(guard);
+
+    assert!(!lock.is_poisoned());
+    assert_eq!(43, lock.into_inner().unwrap());
+}
+
+#[test]
+fn panicking_writer_poisons() {
+    __panicking_writer_poisons::<ReadBiased>();
+    __panicking_writer_poisons::<WriteBiased>();
+    __panicking_writer_poisons::<ArrivalOrdered>();
+    __panicking_writer_poisons::<Stochastic>();
+    __panicking_writer_poisons::<PhaseFair>();
+    __panicking_writer_poisons::<Prioritized>();
+}
+
+fn __panicking_writer_poisons<M: Moderator>() {
+    let lock = PoisonZLock::<_, M>::new(42);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut guard = lock.write().unwrap();
+        *guard = 69;
+        panic!();
+    }));
+    assert!(res.is_err());
+    assert!(lock.is_poisoned());
+
+    // the lock is released, but every acquisition reports the poisoning
+    let guard = lock.read().unwrap_err().into_inner();
+    assert_eq!(69, *guard);
+    drop(guard);
+    assert!(lock.try_write(Duration::ZERO).unwrap().is_err());
+    assert!(lock.try_read(Duration::ZERO).unwrap().is_err());
+
+    lock.clear_poison();
+    assert!(!lock.is_poisoned());
+    assert_eq!(69, *lock.read().unwrap());
+}
+
+#[test]
+fn panicking_reader_does_not_poison() {
+    __panicking_reader_does_not_poison::<ReadBiased>();
+    __panicking_reader_does_not_poison::<WriteBiased>();
+    __panicking_reader_does_not_poison::<ArrivalOrdered>();
+    __panicking_reader_does_not_poison::<Stochastic>();
+    __panicking_reader_does_not_poison::<PhaseFair>();
+    __panicking_reader_does_not_poison::<Prioritized>();
+}
+
+fn __panicking_reader_does_not_poison<M: Moderator>() {
+    let lock = PoisonZLock::<_, M>::new(42);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _guard = lock.read().unwrap();
+        panic!();
+    }));
+    assert!(res.is_err());
+    assert!(!lock.is_poisoned());
+    assert!(lock.try_write(Duration::ZERO).unwrap().is_ok());
+}
+
+#[test]
+fn remedy_poisoned() {
+    let mut lock = PoisonZLock::<_, ReadBiased>::new(42);
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _guard = lock.write().unwrap();
+        panic!();
+    }));
+    assert!(lock.is_poisoned());
+
+    assert_eq!(42, *lock.read().remedy());
+    *lock.write().remedy() = 69;
+    assert_eq!(69, *lock.try_read(Duration::ZERO).remedy().unwrap());
+    assert!(lock.get_mut().is_err());
+    assert_eq!(69, *lock.get_mut().remedy());
+    assert_eq!(69, lock.into_inner().remedy());
+}
+
+#[test]
+fn debug() {
+    let lock = PoisonZLock::<_, ReadBiased>::new(42);
+    assert_eq!("PoisonZLock { lock: ZLock { data: 42, state: LockState { readers: 0, writer: false, upgradable: false, writer_pending: false, waiting: 0, tickets: None }, .. }, poisoned: false }", format!("{:?}", lock));
+}
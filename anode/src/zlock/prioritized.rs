@@ -0,0 +1,572 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use crate::backoff::ExpBackoff;
+use crate::deadline::Deadline;
+use crate::monitor::{Directive, Monitor, Notification, SpeculativeMonitor};
+use crate::zlock::{AsyncModerator, LockReadGuard, LockState, LockWriteGuard, Moderator, ModeratorMonitor, ThreadAgnostic, Tickets, UpgradableModerator, Waiter, ZLock, READ_QUEUE, WRITE_QUEUE};
+use crate::zlock::word::{Enlistment, LockWord, READER, UPGRADABLE, WRITER};
+
+/// Serves waiters highest-priority-first, and in the order of arrival among waiters of
+/// equal priority. Priorities are assigned per acquisition with
+/// [`ZLock::read_with_priority`] and [`ZLock::write_with_priority`] (and their timed
+/// variants); ordinary acquisitions take the [`DEFAULT_PRIORITY`](Prioritized::DEFAULT_PRIORITY).
+///
+/// To prevent starvation, a waiter gains one level of priority for every
+/// [`AGEING_INTERVAL`](Prioritized::AGEING_INTERVAL) acquisitions that are serviced while
+/// it waits. Thus, a waiter is overtaken by at most `AGEING_INTERVAL` acquisitions for each
+/// level of priority that separates it from the later arrivals.
+#[derive(Debug)]
+pub struct Prioritized;
+
+impl Prioritized {
+    /// The priority of acquisitions made through the ordinary [`ZLock`] methods.
+    pub const DEFAULT_PRIORITY: i8 = 0;
+
+    /// The number of acquisitions serviced ahead of a waiter for it to gain one level of
+    /// priority.
+    pub const AGEING_INTERVAL: u64 = 4;
+
+    #[inline]
+    fn try_read_with_priority(sync: &PrioritizedSync, deadline: Deadline, priority: i8) -> bool {
+        if sync.word.try_fast_read() {
+            return true;
+        }
+
+        let mut waiter = ReadWaiter::new(deadline, priority);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn try_write_with_priority(sync: &PrioritizedSync, deadline: Deadline, priority: i8) -> bool {
+        if sync.word.try_fast_write() {
+            return true;
+        }
+
+        let mut waiter = WriteWaiter::new(deadline, priority);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+}
+
+pub struct PrioritizedSync {
+    word: LockWord,
+    monitor: ModeratorMonitor<PrioritizedState>,
+}
+
+impl PrioritizedSync {
+    const fn new(backoff: &'static ExpBackoff) -> Self {
+        Self {
+            word: LockWord::new(),
+            monitor: SpeculativeMonitor::with_queues(PrioritizedState {
+                upgrade_pending: false,
+                tickets: Tickets::INIT,
+                queue: BTreeSet::new(),
+            }, backoff),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PrioritizedState {
+    /// Set while the upgradable reader awaits its upgrade, holding back readers that arrive
+    /// in the meantime.
+    upgrade_pending: bool,
+    tickets: Tickets,
+
+    /// The waiters, ordered by their effective priority. The head of the queue is next to
+    /// acquire the lock.
+    queue: BTreeSet<Position>,
+}
+
+/// The place of a waiter in the queue. As all waiters age at the same rate, the relative
+/// order of any two waiters is settled upon arrival: the rank folds the priority and the
+/// number of acquisitions serviced before the waiter arrived into a single number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Position {
+    rank: Reverse<i128>,
+    ticket: u64,
+}
+
+impl PrioritizedState {
+    #[inline]
+    fn enqueue(&mut self, priority: i8) -> Position {
+        let rank = i128::from(priority) * i128::from(Prioritized::AGEING_INTERVAL) - i128::from(self.tickets.serviced);
+        let position = Position { rank: Reverse(rank), ticket: self.tickets.take() };
+        self.queue.insert(position);
+        position
+    }
+
+    #[inline]
+    fn is_next(&self, position: &Position) -> bool {
+        self.queue.first() == Some(position)
+    }
+
+    #[inline]
+    fn service(&mut self, position: &Position) {
+        self.queue.remove(position);
+        self.tickets.service();
+    }
+}
+
+impl Moderator for Prioritized {
+    type Sync = PrioritizedSync;
+    type ModeratorConfig = &'static ExpBackoff;
+
+    const INIT: Self::Sync = PrioritizedSync::new(&ExpBackoff::SLEEPY);
+
+    /// The backoff policy applies to the monitor's tracker.
+    #[inline]
+    fn with_config(config: Self::ModeratorConfig) -> Self::Sync {
+        PrioritizedSync::new(config)
+    }
+
+    /// Without contenders, the queue is empty; hence, an uncontended reader may bypass
+    /// the queue without overtaking anyone.
+    #[inline]
+    fn try_read(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_read_until(sync, Deadline::lazy_after(duration))
+    }
+
+    #[inline]
+    fn try_read_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        Self::try_read_with_priority(sync, deadline, Self::DEFAULT_PRIORITY)
+    }
+
+    #[inline]
+    fn read_unlock(sync: &Self::Sync) {
+        if sync.word.try_fast_read_unlock() {
+            return;
+        }
+
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() > 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(!sync.word.load().writer());
+
+            // readers are never held back by other readers
+            match sync.word.shift(READER, 0).readers() {
+                0 | 1 => Directive::NotifyAndReturn(Notification::all(WRITE_QUEUE)),
+                _ => Directive::Return
+            }
+        });
+    }
+
+    #[inline]
+    fn try_write(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_write_until(sync, Deadline::lazy_after(duration))
+    }
+
+    #[inline]
+    fn try_write_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        Self::try_write_with_priority(sync, deadline, Self::DEFAULT_PRIORITY)
+    }
+
+    #[inline]
+    fn write_unlock(sync: &Self::Sync) {
+        if sync.word.try_fast_write_unlock() {
+            return;
+        }
+
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, 0);
+            Directive::NotifyAllAndReturn
+        });
+    }
+
+    fn downgrade(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+
+    fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_upgrade_until(sync, Deadline::lazy_after(duration))
+    }
+
+    fn try_upgrade_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        let mut waiter = UpgradeWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn inspect(sync: &Self::Sync) -> LockState {
+        sync.monitor.compute(|state| LockState {
+            writer_pending: state.upgrade_pending,
+            tickets: Some(state.tickets),
+            ..sync.word.load().inspect()
+        })
+    }
+}
+
+unsafe impl ThreadAgnostic for Prioritized {}
+
+impl UpgradableModerator for Prioritized {
+    #[inline]
+    fn try_read_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = ReadWaiter::upgradable(Deadline::lazy_after(duration), Self::DEFAULT_PRIORITY);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn upgradable_unlock(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, 0);
+            Directive::NotifyAllAndReturn
+        });
+    }
+
+    fn try_upgrade_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = UpgradeWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn downgrade_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+
+    fn downgrade_to_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, UPGRADABLE);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+}
+
+impl AsyncModerator for Prioritized {
+    type ReadWaiter = ReadWaiter;
+    type WriteWaiter = WriteWaiter;
+    type UpgradeWaiter = UpgradeWaiter;
+
+    #[inline]
+    fn forget(sync: &Self::Sync, waker: &Waker) {
+        sync.monitor.forget(waker);
+    }
+}
+
+impl<T: ?Sized> ZLock<T, Prioritized> {
+    #[inline]
+    pub fn read_with_priority(&self, priority: i8) -> LockReadGuard<'_, T, Prioritized> {
+        self.try_read_with_priority(priority, Duration::MAX).unwrap()
+    }
+
+    /// Acquires a read lock, overtaking the waiters of lower (effective) priority.
+    #[inline]
+    pub fn try_read_with_priority(&self, priority: i8, duration: Duration) -> Option<LockReadGuard<'_, T, Prioritized>> {
+        self.id.attempt(self, duration);
+        if Prioritized::try_read_with_priority(&self.sync, Deadline::lazy_after(duration), priority) {
+            self.id.acquired(self);
+            Some(self.read_guard())
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn write_with_priority(&self, priority: i8) -> LockWriteGuard<'_, T, Prioritized> {
+        self.try_write_with_priority(priority, Duration::MAX).unwrap()
+    }
+
+    /// Acquires a write lock, overtaking the waiters of lower (effective) priority.
+    #[inline]
+    pub fn try_write_with_priority(&self, priority: i8, duration: Duration) -> Option<LockWriteGuard<'_, T, Prioritized>> {
+        self.id.attempt(self, duration);
+        if Prioritized::try_write_with_priority(&self.sync, Deadline::lazy_after(duration), priority) {
+            self.id.acquired(self);
+            Some(self.write_guard())
+        } else {
+            None
+        }
+    }
+}
+
+/// Removes a reader or writer that gave up waiting from the queue, so that the waiters
+/// behind it are not held up.
+#[inline]
+fn forfeit_position(sync: &PrioritizedSync, position: &Position) {
+    sync.monitor.enter(|state| {
+        state.queue.remove(position);
+        Directive::NotifyAllAndReturn
+    });
+}
+
+/// Clears the `upgrade_pending` flag raised by an upgradable reader that gave up on its
+/// upgrade, releasing the readers that were held back by it.
+#[inline]
+fn withdraw_upgrade(sync: &PrioritizedSync) {
+    sync.monitor.enter(|state| {
+        state.upgrade_pending = false;
+        Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+    });
+}
+
+pub struct ReadWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    upgradable: bool,
+    priority: i8,
+    position: Option<Position>,
+    enlistment: Enlistment,
+}
+
+impl ReadWaiter {
+    #[inline]
+    fn new(deadline: Deadline, priority: i8) -> Self {
+        Self { deadline, acquired: false, upgradable: false, priority, position: None, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline, priority: i8) -> Self {
+        Self { deadline, acquired: false, upgradable: true, priority, position: None, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut PrioritizedState) -> Directive {
+        let position = *self.position.get_or_insert_with(|| {
+            self.enlistment.enlist(word);
+            state.enqueue(self.priority)
+        });
+        if !self.acquired {
+            let word_state = word.load();
+            if !word_state.writer()
+                && !state.upgrade_pending
+                && (!self.upgradable || !word_state.upgradable())
+                && state.is_next(&position)
+            {
+                self.acquired = true;
+                self.enlistment.acquire(word, 0, if self.upgradable { UPGRADABLE } else { READER });
+                state.service(&position);
+            }
+        }
+
+        // only a reader may follow a reader
+        if self.acquired {
+            Directive::Notify(Notification::all(READ_QUEUE))
+        } else {
+            Directive::WaitOn(READ_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &PrioritizedSync) {
+        if let Some(position) = self.position.take() {
+            if !self.acquired {
+                forfeit_position(sync, &position);
+            }
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for ReadWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever, Prioritized::DEFAULT_PRIORITY)
+    }
+}
+
+impl Waiter<PrioritizedSync> for ReadWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &PrioritizedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &PrioritizedSync) {
+        self.conclude(sync);
+    }
+}
+
+pub struct WriteWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    priority: i8,
+    position: Option<Position>,
+    enlistment: Enlistment,
+}
+
+impl WriteWaiter {
+    #[inline]
+    fn new(deadline: Deadline, priority: i8) -> Self {
+        Self { deadline, acquired: false, priority, position: None, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut PrioritizedState) -> Directive {
+        let position = *self.position.get_or_insert_with(|| {
+            self.enlistment.enlist(word);
+            state.enqueue(self.priority)
+        });
+        if !self.acquired {
+            let word_state = word.load();
+            if word_state.readers() == 0
+                && !word_state.writer()
+                && !word_state.upgradable()
+                && state.is_next(&position)
+            {
+                self.acquired = true;
+                self.enlistment.acquire(word, 0, WRITER);
+                state.service(&position);
+            }
+        }
+
+        // the successor is held back by the writer until it releases the lock
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &PrioritizedSync) {
+        if let Some(position) = self.position.take() {
+            if !self.acquired {
+                forfeit_position(sync, &position);
+            }
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for WriteWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever, Prioritized::DEFAULT_PRIORITY)
+    }
+}
+
+impl Waiter<PrioritizedSync> for WriteWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &PrioritizedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &PrioritizedSync) {
+        self.conclude(sync);
+    }
+}
+
+/// An upgrader already holds a read lock, and so does not queue. Instead, it holds back
+/// the readers at the head of the queue until its upgrade completes.
+pub struct UpgradeWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    self_upgrade_pending: bool,
+    enlistment: Enlistment,
+
+    /// Whether the lock being upgraded is an upgradable read lock, as opposed to an
+    /// ordinary read lock.
+    upgradable: bool,
+}
+
+impl UpgradeWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_upgrade_pending: false, enlistment: Enlistment::default(), upgradable: false }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_upgrade_pending: false, enlistment: Enlistment::default(), upgradable: true }
+    }
+
+    /// An upgradable reader need only wait for the ordinary readers to drain, whereas an
+    /// ordinary reader must be the sole holder of the lock.
+    #[inline]
+    fn can_acquire(&self, readers: u32, upgradable: bool) -> bool {
+        if self.upgradable {
+            debug_assert!(upgradable);
+            readers == 0
+        } else {
+            readers == 1 && !upgradable
+        }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut PrioritizedState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let word_state = word.load();
+            debug_assert!(!word_state.writer());
+
+            if self.can_acquire(word_state.readers(), word_state.upgradable()) {
+                self.acquired = true;
+                if self.upgradable {
+                    self.self_upgrade_pending = false;
+                    state.upgrade_pending = false;
+                }
+                self.enlistment.acquire(word, if self.upgradable { UPGRADABLE } else { READER }, WRITER);
+            } else if self.upgradable && !self.self_upgrade_pending {
+                // readers that arrive after the upgrade request must wait their turn
+                self.self_upgrade_pending = true;
+                state.upgrade_pending = true;
+            }
+        }
+
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &PrioritizedSync) {
+        if self.self_upgrade_pending {
+            self.self_upgrade_pending = false;
+            withdraw_upgrade(sync);
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for UpgradeWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<PrioritizedSync> for UpgradeWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &PrioritizedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &PrioritizedSync) {
+        self.conclude(sync);
+    }
+}
+
+#[cfg(test)]
+mod tests;
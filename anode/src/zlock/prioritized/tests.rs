@@ -0,0 +1,136 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::executor::{Executor, Queue, Submitter, ThreadPool};
+use crate::monitor::Monitor;
+use crate::test_utils::{LONG_WAIT, SHORT_WAIT};
+use crate::wait;
+use crate::wait::{Wait, WaitResult};
+use crate::zlock::{Prioritized, Tickets, ZLock};
+use crate::zlock::prioritized::PrioritizedState;
+
+#[test]
+fn higher_priority_served_first() {
+    let lock = Arc::new(ZLock::<_, Prioritized>::new(0));
+    let order = Arc::new(Mutex::new(vec![]));
+    let guard_1 = lock.write();
+
+    let mut pools = vec![];
+    let mut completions = vec![];
+    for (priority, name) in [(-1, "low"), (0, "default"), (1, "high")] {
+        let pool = ThreadPool::new(1, Queue::Unbounded);
+        completions.push({
+            let (lock, order) = (lock.clone(), order.clone());
+            pool.submitter().submit(move || {
+                let guard = lock.write_with_priority(priority);
+                order.lock().unwrap().push(name);
+                drop(guard);
+            })
+        });
+        pools.push(pool);
+
+        // wait until the writer is queued, so that the order of arrival is certain
+        lock.wait_for_queued(pools.len(), LONG_WAIT).unwrap();
+    }
+
+    drop(guard_1);
+    for completion in completions {
+        assert!(completion.get().is_success());
+    }
+    assert_eq!(vec!["high", "default", "low"], *order.lock().unwrap());
+    assert_eq!(0, lock.queued());
+}
+
+#[test]
+fn readers_queue_with_writers() {
+    let lock = Arc::new(ZLock::<_, Prioritized>::new(0));
+    let order = Arc::new(Mutex::new(vec![]));
+    let guard_1 = lock.write();
+
+    let t_2 = ThreadPool::new(1, Queue::Unbounded);
+    let t_2_write = {
+        let (lock, order) = (lock.clone(), order.clone());
+        t_2.submitter().submit(move || {
+            let guard_2 = lock.write();
+            order.lock().unwrap().push("write");
+            drop(guard_2);
+        })
+    };
+    lock.wait_for_queued(1, LONG_WAIT).unwrap();
+
+    let t_3 = ThreadPool::new(1, Queue::Unbounded);
+    let t_3_read = {
+        let (lock, order) = (lock.clone(), order.clone());
+        t_3.submitter().submit(move || {
+            let guard_3 = lock.read_with_priority(1);
+            order.lock().unwrap().push("read");
+            drop(guard_3);
+        })
+    };
+    lock.wait_for_queued(2, LONG_WAIT).unwrap();
+
+    drop(guard_1);
+    assert!(t_2_write.get().is_success());
+    assert!(t_3_read.get().is_success());
+    assert_eq!(vec!["read", "write"], *order.lock().unwrap());
+}
+
+#[test]
+fn timeout_forfeits_position() {
+    let lock = ZLock::<_, Prioritized>::new(0);
+    let guard_1 = lock.read();
+
+    let guard_2 = lock.try_write_with_priority(1, SHORT_WAIT);
+    assert!(guard_2.is_none());
+    assert_eq!(0, lock.queued());
+
+    // the forfeited position does not hold up subsequent readers
+    let guard_3 = lock.try_read_with_priority(0, Duration::ZERO);
+    assert!(guard_3.is_some());
+    drop(guard_1);
+    drop(guard_3);
+
+    let guard_4 = lock.try_write_with_priority(-1, Duration::ZERO);
+    assert!(guard_4.is_some());
+}
+
+#[test]
+fn ageing() {
+    let mut state = PrioritizedState {
+        upgrade_pending: false,
+        tickets: Tickets::INIT,
+        queue: BTreeSet::new(),
+    };
+    let low = state.enqueue(-1);
+
+    // a later arrival of higher priority overtakes the waiter...
+    let high = state.enqueue(1);
+    assert!(state.is_next(&high));
+    state.service(&high);
+    assert!(state.is_next(&low));
+
+    // ...until the waiter has aged sufficiently
+    state.tickets.serviced += 2 * Prioritized::AGEING_INTERVAL - 1;
+    let high = state.enqueue(1);
+    assert!(state.is_next(&low));
+    state.service(&low);
+    assert!(state.is_next(&high));
+    state.service(&high);
+
+    // among equal priorities, the earlier arrival is next
+    let first = state.enqueue(0);
+    let second = state.enqueue(0);
+    assert_eq!(Ordering::Less, first.cmp(&second));
+    assert!(state.is_next(&first));
+}
+
+impl<T> ZLock<T, Prioritized> {
+    fn queued(&self) -> usize {
+        self.sync.monitor.compute(|state| state.queue.len())
+    }
+
+    fn wait_for_queued(&self, target: usize, duration: Duration) -> WaitResult {
+        wait::Spin::wait_for_inequality(|| self.queued(), Ordering::is_eq, &target, duration)
+    }
+}
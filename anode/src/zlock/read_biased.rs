@@ -1,65 +1,73 @@
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
+use crate::backoff::ExpBackoff;
 use crate::deadline::Deadline;
-use crate::monitor::{Directive, Monitor, SpeculativeMonitor};
-use crate::zlock::Moderator;
+use crate::monitor::{Directive, Monitor, Notification, SpeculativeMonitor};
+use crate::zlock::{AsyncModerator, LockState, Moderator, ModeratorMonitor, ThreadAgnostic, UpgradableModerator, Waiter, READ_QUEUE, WRITE_QUEUE};
+use crate::zlock::word::{Enlistment, LockWord, READER, UPGRADABLE, WRITER};
 
 #[derive(Debug)]
 pub struct ReadBiased;
 
 pub struct ReadBiasedSync {
-    monitor: SpeculativeMonitor<ReadBiasedState>,
+    word: LockWord,
+    monitor: ModeratorMonitor<()>,
 }
 
-#[derive(Debug)]
-struct ReadBiasedState {
-    readers: u32,
-    writer: bool,
+impl ReadBiasedSync {
+    const fn new(backoff: &'static ExpBackoff) -> Self {
+        Self {
+            word: LockWord::new(),
+            monitor: SpeculativeMonitor::with_queues((), backoff),
+        }
+    }
 }
 
 impl Moderator for ReadBiased {
     type Sync = ReadBiasedSync;
+    type ModeratorConfig = &'static ExpBackoff;
 
+    const INIT: Self::Sync = ReadBiasedSync::new(&ExpBackoff::SLEEPY);
+
+    /// The backoff policy applies to the monitor's tracker.
     #[inline]
-    fn new() -> Self::Sync {
-        Self::Sync {
-            monitor: SpeculativeMonitor::new(ReadBiasedState { readers: 0, writer: false }),
-        }
+    fn with_config(config: Self::ModeratorConfig) -> Self::Sync {
+        ReadBiasedSync::new(config)
     }
 
     #[inline]
     fn try_read(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        sync.monitor.enter(|state| {
-            if !acquired && !state.writer {
-                acquired = true;
-                state.readers += 1;
-            }
+        Self::try_read_until(sync, Deadline::lazy_after(duration))
+    }
 
-            if acquired {
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
-            }
-        });
-        acquired
+    #[inline]
+    fn try_read_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_read() {
+            return true;
+        }
+
+        let mut waiter = ReadWaiter::new(deadline);
+        sync.monitor.enter(|_| waiter.step(&sync.word));
+        waiter.conclude(sync);
+        waiter.acquired
     }
 
     #[inline]
     fn read_unlock(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers > 0, "readers: {}", state.readers);
-                debug_assert!(!state.writer);
-
-                released = true;
-                state.readers -= 1;
-            }
+        if sync.word.try_fast_read_unlock() {
+            return;
+        }
+
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() > 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(!sync.word.load().writer());
 
-            match state.readers {
-                1 => Directive::NotifyAll,
-                0 => Directive::NotifyOne,
+            // readers are never held back by other readers
+            let word = sync.word.shift(READER, 0);
+            match word.readers() {
+                1 => Directive::NotifyAndReturn(Notification::all(WRITE_QUEUE)),
+                0 if word.upgradable() => Directive::NotifyAndReturn(Notification::all(WRITE_QUEUE)),
+                0 => Directive::NotifyAndReturn(Notification::one(WRITE_QUEUE)),
                 _ => Directive::Return
             }
         });
@@ -67,73 +75,313 @@ impl Moderator for ReadBiased {
 
     #[inline]
     fn try_write(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        sync.monitor.enter(|state| {
-            if !acquired && state.readers == 0 && !state.writer {
-                acquired = true;
-                state.writer = true;
-            }
+        Self::try_write_until(sync, Deadline::lazy_after(duration))
+    }
 
-            if acquired {
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
-            }
-        });
-        acquired
+    #[inline]
+    fn try_write_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_write() {
+            return true;
+        }
+
+        let mut waiter = WriteWaiter::new(deadline);
+        sync.monitor.enter(|_| waiter.step(&sync.word));
+        waiter.conclude(sync);
+        waiter.acquired
     }
 
     #[inline]
     fn write_unlock(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers == 0, "readers: {}", state.readers);
-                debug_assert!(state.writer);
-
-                released = true;
-                state.writer = false;
-            }
+        if sync.word.try_fast_write_unlock() {
+            return;
+        }
 
-            Directive::NotifyOne
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, 0);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE).and_one(WRITE_QUEUE))
         });
     }
 
     fn downgrade(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers == 0, "readers: {}", state.readers);
-                debug_assert!(state.writer);
-
-                released = true;
-                state.writer = false;
-                state.readers = 1;
-            }
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
 
-            Directive::NotifyAll
+            sync.word.shift(WRITER, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
         });
     }
 
     fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        sync.monitor.enter(|state| {
-            if !acquired && state.readers == 1 {
-                debug_assert!(!state.writer);
-
-                acquired = true;
-                state.readers = 0;
-                state.writer = true;
+        Self::try_upgrade_until(sync, Deadline::lazy_after(duration))
+    }
+
+    fn try_upgrade_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        let mut waiter = UpgradeWaiter::new(deadline);
+        sync.monitor.enter(|_| waiter.step(&sync.word));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn inspect(sync: &Self::Sync) -> LockState {
+        sync.monitor.compute(|_| sync.word.load().inspect())
+    }
+}
+
+unsafe impl ThreadAgnostic for ReadBiased {}
+
+impl UpgradableModerator for ReadBiased {
+    #[inline]
+    fn try_read_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = ReadWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|_| waiter.step(&sync.word));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn upgradable_unlock(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, 0);
+            Directive::NotifyAllAndReturn
+        });
+    }
+
+    fn try_upgrade_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = UpgradeWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|_| waiter.step(&sync.word));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn downgrade_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+
+    fn downgrade_to_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, UPGRADABLE);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+}
+
+impl AsyncModerator for ReadBiased {
+    type ReadWaiter = ReadWaiter;
+    type WriteWaiter = WriteWaiter;
+    type UpgradeWaiter = UpgradeWaiter;
+
+    #[inline]
+    fn forget(sync: &Self::Sync, waker: &Waker) {
+        sync.monitor.forget(waker);
+    }
+}
+
+pub struct ReadWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    upgradable: bool,
+    enlistment: Enlistment,
+}
+
+impl ReadWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, upgradable: false, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, upgradable: true, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let state = word.load();
+            if !state.writer() && (!self.upgradable || !state.upgradable()) {
+                self.acquired = true;
+                self.enlistment.acquire(word, 0, if self.upgradable { UPGRADABLE } else { READER });
             }
+        }
 
-            if acquired {
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(READ_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &ReadBiasedSync) {
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for ReadWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<ReadBiasedSync> for ReadWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &ReadBiasedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|_| self.step(&sync.word), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &ReadBiasedSync) {
+        self.conclude(sync);
+    }
+}
+
+pub struct WriteWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    enlistment: Enlistment,
+}
+
+impl WriteWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let state = word.load();
+            if state.readers() == 0 && !state.writer() && !state.upgradable() {
+                self.acquired = true;
+                self.enlistment.acquire(word, 0, WRITER);
             }
-        });
-        acquired
+        }
+
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &ReadBiasedSync) {
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for WriteWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<ReadBiasedSync> for WriteWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &ReadBiasedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|_| self.step(&sync.word), cx)
     }
-}
\ No newline at end of file
+
+    #[inline]
+    fn cancel(&mut self, sync: &ReadBiasedSync) {
+        self.conclude(sync);
+    }
+}
+
+pub struct UpgradeWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    enlistment: Enlistment,
+
+    /// Whether the lock being upgraded is an upgradable read lock, as opposed to an
+    /// ordinary read lock.
+    upgradable: bool,
+}
+
+impl UpgradeWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, enlistment: Enlistment::default(), upgradable: false }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, enlistment: Enlistment::default(), upgradable: true }
+    }
+
+    /// An upgradable reader need only wait for the ordinary readers to drain, whereas an
+    /// ordinary reader must be the sole holder of the lock.
+    #[inline]
+    fn can_acquire(&self, readers: u32, upgradable: bool) -> bool {
+        if self.upgradable {
+            debug_assert!(upgradable);
+            readers == 0
+        } else {
+            readers == 1 && !upgradable
+        }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let state = word.load();
+            debug_assert!(!state.writer());
+
+            if self.can_acquire(state.readers(), state.upgradable()) {
+                self.acquired = true;
+                self.enlistment.acquire(word, if self.upgradable { UPGRADABLE } else { READER }, WRITER);
+            }
+        }
+
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &ReadBiasedSync) {
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for UpgradeWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<ReadBiasedSync> for UpgradeWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &ReadBiasedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|_| self.step(&sync.word), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &ReadBiasedSync) {
+        self.conclude(sync);
+    }
+}
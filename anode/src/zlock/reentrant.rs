@@ -0,0 +1,237 @@
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use crate::zlock::{Moderator, ZLock};
+
+unsafe impl<T: ?Sized + Send, M: Moderator> Send for ReentrantZLock<T, M> {}
+unsafe impl<T: ?Sized + Send + Sync, M: Moderator> Sync for ReentrantZLock<T, M> {}
+unsafe impl<T: ?Sized + Sync, M: Moderator> Sync for ReentrantReadGuard<'_, T, M> {}
+unsafe impl<T: ?Sized + Sync, M: Moderator> Sync for ReentrantWriteGuard<'_, T, M> {}
+
+/// A [`ZLock`] whose write lock may be re-acquired by the owning thread. While holding the
+/// write lock, the owner may also acquire read locks, which are subsumed by the write lock.
+///
+/// As a write lock may be held several times over by the same thread, the guards only
+/// dereference to a shared reference. Mutation requires interior mutability. Since read
+/// locks may be held by several threads at once, the lock is only [`Sync`] if the data
+/// is; e.g., atomics or a nested lock. A [`Cell`](std::cell::Cell) or
+/// [`RefCell`](std::cell::RefCell) confines the lock to a single thread.
+///
+/// Only the write lock is reentrant. A thread that holds a read lock (but not the write
+/// lock) will deadlock if it attempts to acquire the write lock. Similarly, a thread that
+/// holds a read lock may block when attempting another read lock if the moderator gives
+/// precedence to a pending writer.
+pub struct ReentrantZLock<T: ?Sized, M: Moderator> {
+    /// The thread that holds the write lock, or 0 if the write lock is not held.
+    owner: AtomicU64,
+
+    /// The number of guards held by the owner. Only accessed by the owner.
+    recursion: UnsafeCell<usize>,
+
+    lock: ZLock<T, M>,
+}
+
+/// A nonzero identifier of the current thread, drawn from a process-wide counter. Unlike the
+/// address of a thread-local, an identifier is never reused once its thread exits.
+#[inline]
+fn current_thread() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static ID: u64 = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    }
+    ID.with(|id| *id)
+}
+
+impl<T, M: Moderator> ReentrantZLock<T, M> {
+    #[inline]
+    pub const fn new(t: T) -> Self {
+        Self {
+            owner: AtomicU64::new(0),
+            recursion: UnsafeCell::new(0),
+            lock: ZLock::new(t),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+}
+
+impl<T: ?Sized, M: Moderator> ReentrantZLock<T, M> {
+    #[inline]
+    pub fn read(&self) -> ReentrantReadGuard<'_, T, M> {
+        self.try_read(Duration::MAX).unwrap()
+    }
+
+    /// Acquires a read lock. If the current thread holds the write lock, the read lock is
+    /// granted immediately.
+    #[inline]
+    pub fn try_read(&self, duration: Duration) -> Option<ReentrantReadGuard<'_, T, M>> {
+        if self.is_owned() {
+            self.reenter();
+            Some(ReentrantReadGuard::new(self, false))
+        } else {
+            // the guard is reconstituted when the read lock is released
+            mem::forget(self.lock.try_read(duration)?);
+            Some(ReentrantReadGuard::new(self, true))
+        }
+    }
+
+    #[inline]
+    pub fn write(&self) -> ReentrantWriteGuard<'_, T, M> {
+        self.try_write(Duration::MAX).unwrap()
+    }
+
+    /// Acquires a write lock. If the current thread holds the write lock, the recursion
+    /// count is incremented and the lock is granted immediately.
+    #[inline]
+    pub fn try_write(&self, duration: Duration) -> Option<ReentrantWriteGuard<'_, T, M>> {
+        if self.is_owned() {
+            self.reenter();
+        } else {
+            mem::forget(self.lock.try_write(duration)?);
+            self.owner.store(current_thread(), Ordering::Relaxed);
+            unsafe {
+                *self.recursion.get() = 1;
+            }
+        }
+        Some(ReentrantWriteGuard::new(self))
+    }
+
+    /// Returns `true` if the current thread holds the write lock.
+    #[inline]
+    pub fn is_owned(&self) -> bool {
+        self.owner.load(Ordering::Relaxed) == current_thread()
+    }
+
+    /// Increments the recursion count. May only be called by the owner.
+    #[inline]
+    fn reenter(&self) {
+        let recursion = unsafe { &mut *self.recursion.get() };
+        *recursion = recursion.checked_add(1).expect("recursion limit exceeded");
+    }
+
+    /// Decrements the recursion count, releasing the write lock when the count reaches
+    /// zero. May only be called by the owner.
+    #[inline]
+    fn exit(&self) {
+        let recursion = unsafe { &mut *self.recursion.get() };
+        *recursion -= 1;
+        if *recursion == 0 {
+            self.owner.store(0, Ordering::Relaxed);
+            self.lock.write_unlock();
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the [`ReentrantZLock`] mutably, no actual locking needs to
+    /// take place---the mutable borrow statically guarantees no locks exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.lock.get_mut()
+    }
+}
+
+pub struct ReentrantReadGuard<'a, T: ?Sized + 'a, M: Moderator + 'a> {
+    lock: &'a ReentrantZLock<T, M>,
+
+    /// Whether the guard holds a read lock, as opposed to a reentry of the write lock.
+    shared: bool,
+
+    /// Emulates !Send for the struct. (Until issue 68318 -- negative trait bounds -- is resolved.)
+    __no_send: PhantomData<*const ()>,
+}
+
+impl<'a, T: ?Sized, M: Moderator> ReentrantReadGuard<'a, T, M> {
+    #[inline]
+    fn new(lock: &'a ReentrantZLock<T, M>, shared: bool) -> Self {
+        Self {
+            lock,
+            shared,
+            __no_send: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Drop for ReentrantReadGuard<'_, T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.shared {
+            self.lock.lock.read_unlock();
+        } else {
+            self.lock.exit();
+        }
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Deref for ReentrantReadGuard<'_, T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.lock.data.get() }
+    }
+}
+
+pub struct ReentrantWriteGuard<'a, T: ?Sized + 'a, M: Moderator + 'a> {
+    lock: &'a ReentrantZLock<T, M>,
+
+    /// Emulates !Send for the struct. (Until issue 68318 -- negative trait bounds -- is resolved.)
+    __no_send: PhantomData<*const ()>,
+}
+
+impl<'a, T: ?Sized, M: Moderator> ReentrantWriteGuard<'a, T, M> {
+    #[inline]
+    fn new(lock: &'a ReentrantZLock<T, M>) -> Self {
+        Self {
+            lock,
+            __no_send: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Drop for ReentrantWriteGuard<'_, T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.exit();
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Deref for ReentrantWriteGuard<'_, T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized + Debug, M: Moderator> Debug for ReentrantZLock<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ReentrantZLock");
+        match self.try_read(Duration::ZERO) {
+            None => {
+                struct LockedPlaceholder;
+                impl Debug for LockedPlaceholder {
+                    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.write_str("<locked>")
+                    }
+                }
+                d.field("data", &LockedPlaceholder);
+            }
+            Some(guard) => {
+                d.field("data", &&*guard);
+            }
+        }
+        d.finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests;
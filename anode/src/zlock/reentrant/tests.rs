@@ -0,0 +1,134 @@
+use std::cell::Cell;
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+use crate::test_utils::SHORT_WAIT;
+use crate::zlock::{ArrivalOrdered, Moderator, PhaseFair, Prioritized, ReadBiased, ReentrantZLock, Stochastic, WriteBiased};
+
+#[test]
+fn reentrant_cycle() {
+    __reentrant_cycle::<ReadBiased>();
+    __reentrant_cycle::<WriteBiased>();
+    __reentrant_cycle::<ArrivalOrdered>();
+    __reentrant_cycle::<Stochastic>();
+    __reentrant_cycle::<PhaseFair>();
+    __reentrant_cycle::<Prioritized>();
+}
+
+fn __reentrant_cycle<M: Moderator>() {
+    let lock = ReentrantZLock::<_, M>::new(Cell::new(0));
+    assert!(!lock.is_owned());
+
+    let outer = lock.write();
+    assert!(lock.is_owned());
+    outer.set(1);
+    {
+        let inner = lock.write();
+        assert_eq!(1, inner.get());
+        inner.set(2);
+
+        let read = lock.try_read(Duration::ZERO).unwrap();
+        assert_eq!(2, read.get());
+    }
+    assert!(lock.is_owned());
+    assert_eq!(2, outer.get());
+
+    // the lock outlives the outer guard, if a nested guard is still held
+    let read = lock.read();
+    drop(outer);
+    assert!(lock.is_owned());
+    assert_eq!(2, read.get());
+    drop(read);
+    assert!(!lock.is_owned());
+
+    // ordinary read locks are shared
+    let read_1 = lock.read();
+    let read_2 = lock.read();
+    assert!(!lock.is_owned());
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    drop((read_1, read_2));
+
+    assert!(lock.try_write(Duration::ZERO).is_some());
+    assert_eq!(2, lock.into_inner().get());
+}
+
+#[test]
+fn excludes_other_threads() {
+    __excludes_other_threads::<ReadBiased>();
+    __excludes_other_threads::<WriteBiased>();
+    __excludes_other_threads::<ArrivalOrdered>();
+    __excludes_other_threads::<Stochastic>();
+    __excludes_other_threads::<PhaseFair>();
+    __excludes_other_threads::<Prioritized>();
+}
+
+fn __excludes_other_threads<M: Moderator + 'static>() {
+    let lock = Arc::new(ReentrantZLock::<_, M>::new(0));
+    let outer = lock.write();
+    let inner = lock.write();
+
+    let try_from_other = || {
+        let lock = lock.clone();
+        thread::spawn(move || {
+            assert!(!lock.is_owned());
+            let read = lock.try_read(SHORT_WAIT).is_some();
+            let write = lock.try_write(SHORT_WAIT).is_some();
+            (read, write)
+        }).join().unwrap()
+    };
+
+    assert_eq!((false, false), try_from_other());
+    drop(outer);
+    assert_eq!((false, false), try_from_other());
+    drop(inner);
+    assert_eq!((true, true), try_from_other());
+}
+
+#[test]
+fn owner_outlives_thread() {
+    let lock = Arc::new(ReentrantZLock::<_, ReadBiased>::new(AtomicU64::new(0)));
+
+    // the owner exits without releasing the write lock
+    {
+        let lock = lock.clone();
+        thread::spawn(move || mem::forget(lock.write())).join().unwrap();
+    }
+
+    // threads spawned later must not be mistaken for the departed owner
+    for _ in 0..10 {
+        let lock = lock.clone();
+        thread::spawn(move || {
+            assert!(!lock.is_owned());
+            assert!(lock.try_write(Duration::ZERO).is_none());
+        }).join().unwrap();
+    }
+}
+
+#[test]
+fn static_lock() {
+    static LOCK: ReentrantZLock<AtomicU64, WriteBiased> = ReentrantZLock::new(AtomicU64::new(0));
+
+    fn recurse(depth: u64) {
+        let guard = LOCK.write();
+        guard.fetch_add(1, Ordering::Relaxed);
+        if depth > 0 {
+            recurse(depth - 1);
+        }
+    }
+
+    recurse(3);
+    assert_eq!(4, LOCK.read().load(Ordering::Relaxed));
+}
+
+#[test]
+fn debug() {
+    let lock = ReentrantZLock::<_, ReadBiased>::new("foobar");
+    assert!(format!("{:?}", lock).contains("foobar"), "{:?}", lock);
+
+    // the owner may read the data while holding the write lock
+    let guard = lock.write();
+    assert!(format!("{:?}", lock).contains("foobar"), "{:?}", lock);
+    drop(guard);
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::time::Duration;
+use crate::zlock::{LockReadGuard, LockWriteGuard, Moderator, UpgradeOutcome, ZLock};
+
+/// A [`ZLock`] that additionally supports seqlock-style optimistic reads, which copy the
+/// data out without touching the moderator.
+///
+/// The lock maintains a version that is odd for as long as a writer holds the lock and
+/// is advanced when the writer releases or downgrades it. An optimistic reader samples
+/// the version, copies the data and samples the version again; the copy is valid if
+/// neither sample was odd and the two agree. Otherwise, the reader falls back to acquiring
+/// a read lock in the usual manner.
+///
+/// Optimistic reads are limited to [`Copy`] types, as the copy may be torn by a concurrent
+/// writer and is only materialised once it has been validated. They suit small, read-mostly
+/// data, such as configuration, where a reader is rarely overlapped by a writer.
+///
+/// # Examples
+/// ```
+/// use anode::zlock::{ReadBiased, SeqZLock};
+///
+/// let lock = SeqZLock::<_, ReadBiased>::new((1, 2));
+/// let stamp = lock.stamp().unwrap();
+/// assert_eq!((1, 2), *lock.read_optimistic());
+///
+/// *lock.write() = (3, 4);
+/// assert!(!lock.validate(stamp));
+/// assert_eq!(Some((3, 4)), lock.try_read_optimistic().map(|(data, _)| data));
+/// ```
+pub struct SeqZLock<T: ?Sized, M: Moderator> {
+    version: AtomicU64,
+    lock: ZLock<T, M>,
+}
+
+/// A sample of the version of a [`SeqZLock`], taken while no writer held the lock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stamp(u64);
+
+impl<T, M: Moderator> SeqZLock<T, M> {
+    #[inline]
+    pub const fn new(t: T) -> Self {
+        Self {
+            version: AtomicU64::new(0),
+            lock: ZLock::new(t),
+        }
+    }
+
+    /// Creates a lock whose moderator is tuned with the given configuration.
+    #[inline]
+    pub fn with_config(t: T, config: M::ModeratorConfig) -> Self {
+        Self {
+            version: AtomicU64::new(0),
+            lock: ZLock::with_config(t, config),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+}
+
+impl<T: ?Sized, M: Moderator> SeqZLock<T, M> {
+    #[inline]
+    pub fn read(&self) -> SeqReadGuard<'_, T, M> {
+        self.try_read(Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    pub fn try_read(&self, duration: Duration) -> Option<SeqReadGuard<'_, T, M>> {
+        self.lock.try_read(duration).map(|guard| SeqReadGuard { guard, version: &self.version })
+    }
+
+    #[inline]
+    pub fn write(&self) -> SeqWriteGuard<'_, T, M> {
+        self.try_write(Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    pub fn try_write(&self, duration: Duration) -> Option<SeqWriteGuard<'_, T, M>> {
+        self.lock.try_write(duration).map(|guard| SeqWriteGuard::new(guard, &self.version))
+    }
+
+    /// Samples the version of the data, returning `None` if a writer presently holds the
+    /// lock.
+    #[inline]
+    pub fn stamp(&self) -> Option<Stamp> {
+        let version = self.version.load(Ordering::Acquire);
+        if version & 1 == 0 {
+            Some(Stamp(version))
+        } else {
+            None
+        }
+    }
+
+    /// Determines whether the data is unchanged since the given stamp was taken; that is,
+    /// no writer has acquired the lock in the interim.
+    #[inline]
+    pub fn validate(&self, stamp: Stamp) -> bool {
+        fence(Ordering::Acquire);
+        self.version.load(Ordering::Relaxed) == stamp.0
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Any outstanding stamp is invalidated, as the data may be changed through the
+    /// returned reference.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        *self.version.get_mut() += 2;
+        self.lock.get_mut()
+    }
+}
+
+impl<T: Copy, M: Moderator> SeqZLock<T, M> {
+    /// Makes a single attempt at copying the data without acquiring the lock, returning
+    /// the copy along with the stamp it was validated against. Returns `None` if a writer
+    /// held the lock before or during the attempt.
+    #[inline]
+    pub fn try_read_optimistic(&self) -> Option<(T, Stamp)> {
+        let stamp = self.stamp()?;
+
+        // the copy may race with a writer, and is only assumed initialised once validated
+        let copy = unsafe { ptr::read_volatile(self.lock.data.get() as *const MaybeUninit<T>) };
+        if self.validate(stamp) {
+            Some((unsafe { copy.assume_init() }, stamp))
+        } else {
+            None
+        }
+    }
+
+    /// Reads the data optimistically, falling back to acquiring a read lock if the
+    /// optimistic attempt conflicts with a writer.
+    #[inline]
+    pub fn read_optimistic(&self) -> OptimisticRead<'_, T, M> {
+        match self.try_read_optimistic() {
+            Some((data, stamp)) => OptimisticRead::Copied(data, stamp),
+            None => OptimisticRead::Locked(self.read()),
+        }
+    }
+}
+
+/// The outcome of [`SeqZLock::read_optimistic`]: either a validated copy of the data or,
+/// if the optimistic attempt failed, a read guard.
+pub enum OptimisticRead<'a, T: 'a, M: Moderator + 'a> {
+    Copied(T, Stamp),
+    Locked(SeqReadGuard<'a, T, M>),
+}
+
+impl<T, M: Moderator> OptimisticRead<'_, T, M> {
+    #[inline]
+    pub fn is_copied(&self) -> bool {
+        matches!(self, OptimisticRead::Copied(..))
+    }
+
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        matches!(self, OptimisticRead::Locked(_))
+    }
+}
+
+impl<T, M: Moderator> Deref for OptimisticRead<'_, T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        match self {
+            OptimisticRead::Copied(data, _) => data,
+            OptimisticRead::Locked(guard) => guard,
+        }
+    }
+}
+
+pub struct SeqReadGuard<'a, T: ?Sized + 'a, M: Moderator + 'a> {
+    guard: LockReadGuard<'a, T, M>,
+    version: &'a AtomicU64,
+}
+
+impl<'a, T: ?Sized, M: Moderator> SeqReadGuard<'a, T, M> {
+    #[inline]
+    pub fn upgrade(self) -> SeqWriteGuard<'a, T, M> {
+        SeqWriteGuard::new(self.guard.upgrade(), self.version)
+    }
+
+    #[inline]
+    pub fn try_upgrade(self, duration: Duration) -> SeqUpgradeOutcome<'a, T, M> {
+        let version = self.version;
+        self.guard.try_upgrade(duration).map(
+            |guard| SeqWriteGuard::new(guard, version),
+            |guard| SeqReadGuard { guard, version },
+        )
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Deref for SeqReadGuard<'_, T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+pub struct SeqWriteGuard<'a, T: ?Sized + 'a, M: Moderator + 'a> {
+    guard: LockWriteGuard<'a, T, M>,
+    version: &'a AtomicU64,
+}
+
+impl<'a, T: ?Sized, M: Moderator> SeqWriteGuard<'a, T, M> {
+    /// Marks the start of a write by making the version odd. The fence prevents the
+    /// writer's subsequent stores from being observed ahead of the version change.
+    #[inline]
+    fn new(guard: LockWriteGuard<'a, T, M>, version: &'a AtomicU64) -> Self {
+        version.fetch_add(1, Ordering::Acquire);
+        fence(Ordering::Release);
+        Self { guard, version }
+    }
+
+    /// Marks the end of the write, making the version even again.
+    #[inline]
+    fn end_write(&self) {
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn downgrade(self) -> SeqReadGuard<'a, T, M> {
+        self.end_write();
+        let this = ManuallyDrop::new(self);
+        let (guard, version) = unsafe { (ptr::read(&this.guard), this.version) };
+        SeqReadGuard { guard: guard.downgrade(), version }
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Drop for SeqWriteGuard<'_, T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        self.end_write();
+    }
+}
+
+impl<T: ?Sized, M: Moderator> Deref for SeqWriteGuard<'_, T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized, M: Moderator> DerefMut for SeqWriteGuard<'_, T, M> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+pub type SeqUpgradeOutcome<'a, T, M> = UpgradeOutcome<SeqWriteGuard<'a, T, M>, SeqReadGuard<'a, T, M>>;
+
+impl<T: ?Sized + Debug, M: Moderator> Debug for SeqReadGuard<'_, T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Debug, M: Moderator> Debug for SeqWriteGuard<'_, T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: Debug, M: Moderator> Debug for OptimisticRead<'_, T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptimisticRead::Copied(data, stamp) => f.debug_tuple("Copied").field(&data).field(stamp).finish(),
+            OptimisticRead::Locked(guard) => f.debug_tuple("Locked").field(guard).finish(),
+        }
+    }
+}
+
+impl<T: ?Sized + Debug, M: Moderator> Debug for SeqZLock<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeqZLock")
+            .field("lock", &&self.lock)
+            .field("version", &self.version.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests;
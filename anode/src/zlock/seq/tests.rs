@@ -0,0 +1,125 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use crate::test_utils::LONG_WAIT;
+use crate::wait;
+use crate::wait::Wait;
+use crate::zlock::{ArrivalOrdered, Moderator, OptimisticRead, PhaseFair, Prioritized, ReadBiased, SeqZLock, Stochastic, WriteBiased};
+
+#[test]
+fn optimistic_cycle() {
+    __optimistic_cycle::<ReadBiased>();
+    __optimistic_cycle::<WriteBiased>();
+    __optimistic_cycle::<ArrivalOrdered>();
+    __optimistic_cycle::<Stochastic>();
+    __optimistic_cycle::<PhaseFair>();
+    __optimistic_cycle::<Prioritized>();
+}
+
+fn __optimistic_cycle<M: Moderator>() {
+    let mut lock = SeqZLock::<_, M>::new(0);
+    let (data, stamp) = lock.try_read_optimistic().unwrap();
+    assert_eq!(0, data);
+    assert_eq!(Some(stamp), lock.stamp());
+
+    // a read lock does not disturb the version
+    let guard = lock.read();
+    assert!(lock.validate(stamp));
+    assert!(lock.read_optimistic().is_copied());
+
+    // an upgrade begins a write
+    let mut guard = guard.upgrade();
+    assert!(lock.stamp().is_none());
+    assert!(lock.try_read_optimistic().is_none());
+    assert!(!lock.validate(stamp));
+    *guard = 42;
+
+    // a downgrade ends it
+    let guard = guard.downgrade();
+    let (data, stamp) = lock.try_read_optimistic().unwrap();
+    assert_eq!(42, data);
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    drop(guard);
+
+    let mut guard = lock.write();
+    *guard += 1;
+    drop(guard);
+    assert!(!lock.validate(stamp));
+    let stamp = lock.stamp().unwrap();
+    assert_eq!(43, *lock.read_optimistic());
+
+    // a failed upgrade leaves the version intact
+    let guard = lock.read();
+    let guard_2 = lock.read();
+    let guard = guard.try_upgrade(Duration::ZERO).unchanged().unwrap();
+    assert!(lock.validate(stamp));
+    drop((guard, guard_2));
+
+    *lock.get_mut() = 69;
+    assert!(!lock.validate(stamp));
+    assert_eq!(69, lock.into_inner());
+}
+
+#[test]
+fn read_optimistic_falls_back_to_lock() {
+    let lock = Arc::new(SeqZLock::<_, ReadBiased>::new(0));
+    let mut guard = lock.write();
+
+    let reader = {
+        let lock = lock.clone();
+        thread::spawn(move || {
+            let read = lock.read_optimistic();
+            (read.is_locked(), *read)
+        })
+    };
+
+    // the reader blocks until the writer is done
+    wait::Spin::wait_for(|| lock.lock.state().waiting == 1, LONG_WAIT).unwrap();
+    *guard = 42;
+    drop(guard);
+    assert_eq!((true, 42), reader.join().unwrap());
+}
+
+#[test]
+fn debug() {
+    let lock = SeqZLock::<_, ReadBiased>::new(42);
+    assert_eq!("SeqZLock { lock: ZLock { data: 42, state: LockState { readers: 0, writer: false, upgradable: false, writer_pending: false, waiting: 0, tickets: None }, .. }, version: 0, .. }", format!("{lock:?}"));
+    let guard = lock.write();
+    assert_eq!("42", format!("{guard:?}"));
+    drop(guard);
+    assert_eq!("Copied(42, Stamp(2))", format!("{:?}", lock.read_optimistic()));
+    assert!(matches!(lock.read_optimistic(), OptimisticRead::Copied(42, _)));
+}
+
+/// Concurrent writers keep the halves of a pair equal; an optimistic reader must never
+/// observe a torn pair.
+#[test]
+fn no_torn_reads() {
+    const WRITES: u64 = 10_000;
+    let lock = Arc::new(SeqZLock::<_, WriteBiased>::new((0u64, 0u64)));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let writer = {
+        let (lock, done) = (lock.clone(), done.clone());
+        thread::spawn(move || {
+            for i in 1..=WRITES {
+                let mut guard = lock.write();
+                guard.0 = i;
+                guard.1 = i;
+            }
+            done.store(true, Ordering::Release);
+        })
+    };
+
+    let mut last = 0;
+    while !done.load(Ordering::Acquire) {
+        let read = lock.read_optimistic();
+        let (a, b) = *read;
+        assert_eq!(a, b);
+        assert!(a >= last, "{a} < {last}");
+        last = a;
+    }
+    writer.join().unwrap();
+    assert_eq!((WRITES, WRITES), *lock.read_optimistic());
+}
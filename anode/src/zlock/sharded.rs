@@ -0,0 +1,335 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::time::Duration;
+use crate::backoff::ExpBackoff;
+use crate::deadline::Deadline;
+use crate::monitor::{Directive, Monitor, Notification, SpeculativeMonitor};
+use crate::zlock::{LockState, Moderator, ModeratorMonitor, ZLock, READ_QUEUE, WRITE_QUEUE};
+
+/// The number of reader slots per lock.
+const SLOTS: usize = 16;
+
+/// A big-reader moderator, which spreads the count of readers across cache-padded slots,
+/// one per group of threads. A reader only ever touches its own thread's slot (and, to
+/// check for a writer, a flag that is rarely written), so that readers on different
+/// cores do not contend for the same cache line. A writer, conversely, must raise the
+/// flag and then scan every slot until the readers have drained.
+///
+/// The moderator prefers writers: once a writer raises its flag, arriving readers stand
+/// aside until it is done. An upgrading reader, in turn, takes precedence over a writer
+/// that is yet to acquire the lock; since the upgrader holds a read lock, the writer could
+/// otherwise never acquire it.
+///
+/// A read lock must be released by the thread that acquired it, which rules out owned
+/// guards and asynchronous acquisition. The slots occupy 2 KiB per lock.
+#[derive(Debug)]
+pub struct Sharded;
+
+/// A [`ZLock`] with the [`Sharded`] moderator.
+pub type ShardedZLock<T> = ZLock<T, Sharded>;
+
+/// A reader count, aligned to a (generous estimate of a) cache line.
+#[repr(align(128))]
+struct Slot(AtomicU32);
+
+pub struct ShardedSync {
+    slots: [Slot; SLOTS],
+
+    /// Raised by a writer (or an upgrading reader) before it waits for the readers to
+    /// drain, and lowered once it releases the lock or gives up.
+    writer: AtomicBool,
+    monitor: ModeratorMonitor<ShardedState>,
+}
+
+struct ShardedState {
+    /// The number of readers waiting to raise the writer flag in order to upgrade.
+    pending_upgrades: u32,
+}
+
+impl ShardedSync {
+    const fn new(backoff: &'static ExpBackoff) -> Self {
+        Self {
+            slots: [const { Slot(AtomicU32::new(0)) }; SLOTS],
+            writer: AtomicBool::new(false),
+            monitor: SpeculativeMonitor::with_queues(ShardedState { pending_upgrades: 0 }, backoff),
+        }
+    }
+
+    /// The slot of the current thread. Threads are assigned slots round-robin, upon first use.
+    #[inline]
+    fn slot(&self) -> &AtomicU32 {
+        static NEXT_SLOT: AtomicUsize = AtomicUsize::new(0);
+        thread_local! {
+            static SLOT: usize = NEXT_SLOT.fetch_add(1, Ordering::Relaxed) % SLOTS;
+        }
+        &self.slots[SLOT.with(|slot| *slot)].0
+    }
+
+    #[inline]
+    fn readers(&self) -> u32 {
+        self.slots.iter().map(|slot| slot.0.load(Ordering::SeqCst)).sum()
+    }
+
+    #[inline]
+    fn try_raise_writer(&self) -> bool {
+        self.writer.compare_exchange(false, true, Ordering::SeqCst, Ordering::Relaxed).is_ok()
+    }
+
+    /// Optimistically counts the current thread as a reader, standing aside if the writer
+    /// flag is raised. The count and the flag are each written before the other is read,
+    /// so that a reader and a writer cannot both proceed.
+    #[inline]
+    fn try_enter_slot(&self) -> bool {
+        let slot = self.slot();
+        slot.fetch_add(1, Ordering::SeqCst);
+        if !self.writer.load(Ordering::SeqCst) {
+            true
+        } else {
+            slot.fetch_sub(1, Ordering::SeqCst);
+            false
+        }
+    }
+
+    /// Issues the given notification. Called from outside the monitor.
+    #[inline]
+    fn notify(&self, notification: Notification) {
+        self.monitor.enter(|_| Directive::NotifyAndReturn(notification));
+    }
+}
+
+impl Moderator for Sharded {
+    type Sync = ShardedSync;
+    type ModeratorConfig = &'static ExpBackoff;
+
+    const INIT: Self::Sync = ShardedSync::new(&ExpBackoff::SLEEPY);
+
+    /// The backoff policy applies to the monitor's tracker.
+    #[inline]
+    fn with_config(config: Self::ModeratorConfig) -> Self::Sync {
+        ShardedSync::new(config)
+    }
+
+    #[inline]
+    fn try_read(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_read_until(sync, Deadline::lazy_after(duration))
+    }
+
+    #[inline]
+    fn try_read_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.try_enter_slot() {
+            return true;
+        }
+
+        // the writer may be waiting for the momentarily raised count to drain
+        sync.notify(Notification::all(WRITE_QUEUE));
+
+        let mut waiter = ReadWaiter::new(deadline);
+        sync.monitor.enter(|_| waiter.step(sync));
+        waiter.acquired
+    }
+
+    #[inline]
+    fn read_unlock(sync: &Self::Sync) {
+        sync.slot().fetch_sub(1, Ordering::SeqCst);
+        if sync.writer.load(Ordering::SeqCst) {
+            sync.notify(Notification::all(WRITE_QUEUE));
+        }
+    }
+
+    #[inline]
+    fn try_write(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_write_until(sync, Deadline::lazy_after(duration))
+    }
+
+    #[inline]
+    fn try_write_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        let raised = sync.try_raise_writer();
+        if raised && sync.readers() == 0 {
+            return true;
+        }
+
+        let mut waiter = WriteWaiter::new(deadline, raised);
+        sync.monitor.enter(|state| waiter.step(sync, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn write_unlock(sync: &Self::Sync) {
+        sync.writer.store(false, Ordering::SeqCst);
+        sync.notify(Notification::ALL);
+    }
+
+    fn downgrade(sync: &Self::Sync) {
+        sync.slot().fetch_add(1, Ordering::SeqCst);
+        sync.writer.store(false, Ordering::SeqCst);
+        sync.notify(Notification::ALL);
+    }
+
+    fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_upgrade_until(sync, Deadline::lazy_after(duration))
+    }
+
+    fn try_upgrade_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        let raised = sync.try_raise_writer();
+        if raised && sync.readers() == 1 {
+            sync.slot().fetch_sub(1, Ordering::SeqCst);
+            return true;
+        }
+
+        if !raised {
+            // a pending writer must be alerted to the upgrade, so that it yields the flag
+            sync.monitor.alter(|state| state.pending_upgrades += 1);
+            sync.notify(Notification::all(WRITE_QUEUE));
+        }
+
+        let mut waiter = UpgradeWaiter::new(deadline, raised);
+        sync.monitor.enter(|state| waiter.step(sync, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    /// A raised writer flag is reported as a held write lock once the readers have
+    /// drained, and as a pending writer until then.
+    fn inspect(sync: &Self::Sync) -> LockState {
+        let pending_upgrades = sync.monitor.compute(|state| state.pending_upgrades);
+        let readers = sync.readers();
+        let raised = sync.writer.load(Ordering::SeqCst);
+        LockState {
+            readers,
+            writer: raised && readers == 0,
+            writer_pending: raised && readers > 0 || pending_upgrades > 0,
+            waiting: sync.monitor.num_waiting(),
+            ..LockState::default()
+        }
+    }
+}
+
+struct ReadWaiter {
+    deadline: Deadline,
+    acquired: bool,
+}
+
+impl ReadWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false }
+    }
+
+    /// Since the monitor serialises the steps of all waiters, a count that is raised and
+    /// lowered within a single step is never observed by a waiting writer.
+    #[inline]
+    fn step(&mut self, sync: &ShardedSync) -> Directive {
+        if !sync.writer.load(Ordering::SeqCst) && sync.try_enter_slot() {
+            self.acquired = true;
+            Directive::Return
+        } else {
+            Directive::WaitOn(READ_QUEUE, self.deadline.remaining())
+        }
+    }
+}
+
+struct WriteWaiter {
+    deadline: Deadline,
+    acquired: bool,
+
+    /// Whether this writer has raised the writer flag.
+    raised: bool,
+}
+
+impl WriteWaiter {
+    #[inline]
+    fn new(deadline: Deadline, raised: bool) -> Self {
+        Self { deadline, acquired: false, raised }
+    }
+
+    #[inline]
+    fn step(&mut self, sync: &ShardedSync, state: &mut ShardedState) -> Directive {
+        if !self.raised {
+            if state.pending_upgrades == 0 && sync.try_raise_writer() {
+                self.raised = true;
+            } else {
+                return Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining());
+            }
+        }
+
+        // the writer lowers its flag in favour of an upgrading reader, notifying the
+        // upgrader as it waits its turn
+        if state.pending_upgrades > 0 {
+            sync.writer.store(false, Ordering::SeqCst);
+            self.raised = false;
+            return Directive::NotifyAndWaitOn(Notification::ALL, WRITE_QUEUE, self.deadline.remaining());
+        }
+
+        if sync.readers() == 0 {
+            self.acquired = true;
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    /// Lowers the flag of a writer that timed out, admitting the readers that stood aside.
+    #[inline]
+    fn conclude(&mut self, sync: &ShardedSync) {
+        if self.raised && !self.acquired {
+            self.raised = false;
+            sync.writer.store(false, Ordering::SeqCst);
+            sync.notify(Notification::ALL);
+        }
+    }
+}
+
+struct UpgradeWaiter {
+    deadline: Deadline,
+    acquired: bool,
+
+    /// Whether this upgrader has raised the writer flag. Until it does, it is counted
+    /// among the pending upgrades.
+    raised: bool,
+}
+
+impl UpgradeWaiter {
+    #[inline]
+    fn new(deadline: Deadline, raised: bool) -> Self {
+        Self { deadline, acquired: false, raised }
+    }
+
+    #[inline]
+    fn step(&mut self, sync: &ShardedSync, state: &mut ShardedState) -> Directive {
+        if !self.raised {
+            if sync.try_raise_writer() {
+                self.raised = true;
+                state.pending_upgrades -= 1;
+            } else {
+                return Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining());
+            }
+        }
+
+        // the upgrader's own read lock remains counted until the upgrade completes
+        if sync.readers() == 1 {
+            sync.slot().fetch_sub(1, Ordering::SeqCst);
+            self.acquired = true;
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    /// Withdraws an upgrader that timed out, notifying the writers that may have deferred
+    /// to it.
+    #[inline]
+    fn conclude(&mut self, sync: &ShardedSync) {
+        if self.acquired {
+            return;
+        }
+        if self.raised {
+            sync.writer.store(false, Ordering::SeqCst);
+        } else {
+            sync.monitor.alter(|state| state.pending_upgrades -= 1);
+        }
+        sync.notify(Notification::ALL);
+    }
+}
+
+#[cfg(test)]
+mod tests;
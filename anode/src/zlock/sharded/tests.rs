@@ -0,0 +1,131 @@
+use std::sync::{Arc, Barrier};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+use crate::executor::{Executor, Queue, Submitter, ThreadPool};
+use crate::monitor::Monitor;
+use crate::test_utils::{LONG_WAIT, SHORT_WAIT};
+use crate::wait;
+use crate::wait::{Wait, WaitResult};
+use crate::zlock::{Sharded, ShardedZLock, ZLock};
+
+#[test]
+fn readers_across_threads() {
+    const READERS: usize = 4;
+    let lock = Arc::new(ShardedZLock::new(42));
+    let acquired = Arc::new(Barrier::new(READERS + 1));
+    let release = Arc::new(Barrier::new(READERS + 1));
+
+    let threads = (0..READERS).map(|_| {
+        let (lock, acquired, release) = (lock.clone(), acquired.clone(), release.clone());
+        thread::spawn(move || {
+            let guard = lock.read();
+            assert_eq!(42, *guard);
+            acquired.wait();
+            release.wait();
+        })
+    }).collect::<Vec<_>>();
+
+    acquired.wait();
+    assert_eq!(READERS as u32, lock.readers());
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    assert!(!lock.is_writer_raised());
+
+    release.wait();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(0, lock.readers());
+    assert!(lock.try_write(Duration::ZERO).is_some());
+}
+
+#[test]
+fn pending_writer_holds_off_readers() {
+    let lock = Arc::new(ShardedZLock::new(0));
+    let guard_1 = lock.read();
+
+    let t_2 = ThreadPool::new(1, Queue::Unbounded);
+    let t_2_write = {
+        let lock = lock.clone();
+        t_2.submitter().submit(move || {
+            *lock.write() = 42;
+        })
+    };
+    lock.wait_for_writer_raised(LONG_WAIT).unwrap();
+
+    // arriving readers stand aside for the pending writer
+    assert!(lock.try_read(SHORT_WAIT).is_none());
+    assert_eq!(1, lock.readers());
+
+    drop(guard_1);
+    assert!(t_2_write.get().is_success());
+    assert_eq!(42, *lock.read());
+}
+
+#[test]
+fn upgrade_takes_precedence_over_pending_writer() {
+    let lock = Arc::new(ShardedZLock::new(vec![]));
+    let guard_1 = lock.read();
+
+    let t_2 = ThreadPool::new(1, Queue::Unbounded);
+    let t_2_write = {
+        let lock = lock.clone();
+        t_2.submitter().submit(move || {
+            lock.write().push("write");
+        })
+    };
+    lock.wait_for_writer_raised(LONG_WAIT).unwrap();
+
+    let mut guard_1 = guard_1.upgrade();
+    guard_1.push("upgrade");
+    drop(guard_1);
+
+    assert!(t_2_write.get().is_success());
+    assert_eq!(vec!["upgrade", "write"], *lock.read());
+    assert_eq!(0, lock.pending_upgrades());
+}
+
+#[test]
+fn timeouts_lower_writer_flag() {
+    let lock = ShardedZLock::new(0);
+    let guard_1 = lock.read();
+    let guard_2 = lock.read();
+    assert_eq!(2, lock.readers());
+
+    assert!(lock.try_write(SHORT_WAIT).is_none());
+    assert!(!lock.is_writer_raised());
+
+    let guard_1 = guard_1.try_upgrade(SHORT_WAIT).unchanged().unwrap();
+    assert!(!lock.is_writer_raised());
+    assert_eq!(0, lock.pending_upgrades());
+    assert!(lock.try_read(Duration::ZERO).is_some());
+
+    drop(guard_2);
+    let guard_1 = guard_1.try_upgrade(Duration::ZERO).upgraded().unwrap();
+    assert_eq!(0, lock.readers());
+    assert!(lock.is_writer_raised());
+
+    let guard_1 = guard_1.downgrade();
+    assert_eq!(1, lock.readers());
+    assert!(!lock.is_writer_raised());
+    drop(guard_1);
+    assert_eq!(0, lock.readers());
+}
+
+impl<T> ZLock<T, Sharded> {
+    fn readers(&self) -> u32 {
+        self.sync.readers()
+    }
+
+    fn is_writer_raised(&self) -> bool {
+        self.sync.writer.load(Ordering::SeqCst)
+    }
+
+    fn pending_upgrades(&self) -> u32 {
+        self.sync.monitor.compute(|state| state.pending_upgrades)
+    }
+
+    fn wait_for_writer_raised(&self, duration: Duration) -> WaitResult {
+        wait::Spin::wait_for(|| self.is_writer_raised(), duration)
+    }
+}
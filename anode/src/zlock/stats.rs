@@ -0,0 +1,336 @@
+use std::marker::PhantomData;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use crate::monitor;
+use crate::zlock::{LockState, Moderator, ThreadAgnostic, UpgradableModerator, ZLock};
+
+/// A [`Moderator`] that delegates to `M`, while collecting contention statistics for the
+/// lock. The statistics are read with [`ZLock::stats`] and cleared with [`ZLock::reset_stats`].
+///
+/// Each acquisition is passed to `M` as a single attempt, so that instrumentation does not
+/// alter the fairness of the lock. A successful acquisition is deemed _contended_ if the
+/// thread blocked in the moderator's [`SpeculativeMonitor`](crate::monitor::SpeculativeMonitor)
+/// before acquiring the lock; moderators that do not wait on such a monitor never report
+/// contention. The time taken by contended acquisitions and by failed attempts that were
+/// allowed to wait is added to the total wait.
+///
+/// As readers share the lock, read hold times are measured from the moment the lock is
+/// first read-locked until the last overlapping reader releases it. Under concurrent
+/// read acquisition and release, the measured spans are approximate.
+#[derive(Debug)]
+pub struct Instrumented<M: Moderator>(PhantomData<M>);
+
+pub struct InstrumentedSync<M: Moderator> {
+    inner: M::Sync,
+    stats: Stats,
+}
+
+/// A point-in-time view of the statistics collected by an [`Instrumented`] moderator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// The number of read locks (including upgradable read locks) acquired.
+    pub reads: u64,
+
+    /// The number of write locks acquired, excluding upgrades.
+    pub writes: u64,
+
+    /// The number of read locks upgraded to write locks.
+    pub upgrades: u64,
+
+    /// The number of acquisitions and upgrades that had to wait for the lock.
+    pub contended: u64,
+
+    /// The number of read and write acquisitions that timed out.
+    pub timeouts: u64,
+
+    /// The number of upgrades that timed out.
+    pub failed_upgrades: u64,
+
+    /// The total time spent waiting for the lock, including failed attempts.
+    pub total_wait: Duration,
+
+    /// The longest time the lock was held by readers.
+    pub max_read_hold: Duration,
+
+    /// The longest time the lock was held by a writer.
+    pub max_write_hold: Duration,
+}
+
+struct Stats {
+    reads: AtomicU64,
+    writes: AtomicU64,
+    upgrades: AtomicU64,
+    contended: AtomicU64,
+    timeouts: AtomicU64,
+    failed_upgrades: AtomicU64,
+    total_wait: AtomicU64,
+    max_read_hold: AtomicU64,
+    max_write_hold: AtomicU64,
+
+    /// The number of readers presently holding the lock.
+    readers: AtomicU32,
+
+    /// When the lock was last read-locked by the first of a group of overlapping readers.
+    read_since: AtomicU64,
+
+    /// When the lock was last write-locked.
+    write_since: AtomicU64,
+}
+
+/// The instant from which all instants are measured, as the lock cannot capture the
+/// time of its (possibly `const`) construction.
+static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// Durations and instants are stored as nanoseconds since the [`EPOCH`].
+#[inline]
+fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+impl Stats {
+    const fn new() -> Self {
+        Self {
+            reads: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+            upgrades: AtomicU64::new(0),
+            contended: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+            failed_upgrades: AtomicU64::new(0),
+            total_wait: AtomicU64::new(0),
+            max_read_hold: AtomicU64::new(0),
+            max_write_hold: AtomicU64::new(0),
+            readers: AtomicU32::new(0),
+            read_since: AtomicU64::new(0),
+            write_since: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    fn now(&self) -> u64 {
+        nanos(EPOCH.elapsed())
+    }
+
+    /// Attempts an acquisition for the given duration, classifying it by whether the
+    /// thread blocked in the moderator's monitor.
+    #[inline]
+    fn acquire(&self, duration: Duration, f: impl FnOnce(Duration) -> bool) -> bool {
+        let start = Instant::now();
+        let waits = monitor::waits();
+        let acquired = f(duration);
+        let waited = if acquired {
+            monitor::waits() != waits
+        } else {
+            !duration.is_zero()
+        };
+        if waited {
+            self.total_wait.fetch_add(nanos(start.elapsed()), Ordering::Relaxed);
+            if acquired {
+                self.contended.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        acquired
+    }
+
+    #[inline]
+    fn read_locked(&self) {
+        if self.readers.fetch_add(1, Ordering::Relaxed) == 0 {
+            self.read_since.store(self.now(), Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    fn read_unlocked(&self) {
+        if self.readers.fetch_sub(1, Ordering::Relaxed) == 1 {
+            let held = self.now().saturating_sub(self.read_since.load(Ordering::Relaxed));
+            self.max_read_hold.fetch_max(held, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    fn write_locked(&self) {
+        self.write_since.store(self.now(), Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn write_unlocked(&self) {
+        let held = self.now().saturating_sub(self.write_since.load(Ordering::Relaxed));
+        self.max_write_hold.fetch_max(held, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn count(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            reads: self.reads.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+            upgrades: self.upgrades.load(Ordering::Relaxed),
+            contended: self.contended.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            failed_upgrades: self.failed_upgrades.load(Ordering::Relaxed),
+            total_wait: Duration::from_nanos(self.total_wait.load(Ordering::Relaxed)),
+            max_read_hold: Duration::from_nanos(self.max_read_hold.load(Ordering::Relaxed)),
+            max_write_hold: Duration::from_nanos(self.max_write_hold.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn reset(&self) {
+        for counter in [
+            &self.reads,
+            &self.writes,
+            &self.upgrades,
+            &self.contended,
+            &self.timeouts,
+            &self.failed_upgrades,
+            &self.total_wait,
+            &self.max_read_hold,
+            &self.max_write_hold,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<M: Moderator> Moderator for Instrumented<M> {
+    type Sync = InstrumentedSync<M>;
+    type ModeratorConfig = M::ModeratorConfig;
+
+    const INIT: Self::Sync = InstrumentedSync {
+        inner: M::INIT,
+        stats: Stats::new(),
+    };
+
+    #[inline]
+    fn with_config(config: Self::ModeratorConfig) -> Self::Sync {
+        InstrumentedSync {
+            inner: M::with_config(config),
+            stats: Stats::new(),
+        }
+    }
+
+    #[inline]
+    fn try_read(sync: &Self::Sync, duration: Duration) -> bool {
+        if sync.stats.acquire(duration, |duration| M::try_read(&sync.inner, duration)) {
+            Stats::count(&sync.stats.reads);
+            sync.stats.read_locked();
+            true
+        } else {
+            Stats::count(&sync.stats.timeouts);
+            false
+        }
+    }
+
+    #[inline]
+    fn read_unlock(sync: &Self::Sync) {
+        sync.stats.read_unlocked();
+        M::read_unlock(&sync.inner);
+    }
+
+    #[inline]
+    fn try_write(sync: &Self::Sync, duration: Duration) -> bool {
+        if sync.stats.acquire(duration, |duration| M::try_write(&sync.inner, duration)) {
+            Stats::count(&sync.stats.writes);
+            sync.stats.write_locked();
+            true
+        } else {
+            Stats::count(&sync.stats.timeouts);
+            false
+        }
+    }
+
+    #[inline]
+    fn write_unlock(sync: &Self::Sync) {
+        sync.stats.write_unlocked();
+        M::write_unlock(&sync.inner);
+    }
+
+    #[inline]
+    fn downgrade(sync: &Self::Sync) {
+        sync.stats.write_unlocked();
+        sync.stats.read_locked();
+        M::downgrade(&sync.inner);
+    }
+
+    #[inline]
+    fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool {
+        upgrade(sync, duration, |duration| M::try_upgrade(&sync.inner, duration))
+    }
+
+    #[inline]
+    fn inspect(sync: &Self::Sync) -> LockState {
+        M::inspect(&sync.inner)
+    }
+}
+
+#[inline]
+fn upgrade<M: Moderator>(sync: &InstrumentedSync<M>, duration: Duration, f: impl FnOnce(Duration) -> bool) -> bool {
+    if sync.stats.acquire(duration, f) {
+        Stats::count(&sync.stats.upgrades);
+        sync.stats.read_unlocked();
+        sync.stats.write_locked();
+        true
+    } else {
+        Stats::count(&sync.stats.failed_upgrades);
+        false
+    }
+}
+
+unsafe impl<M: ThreadAgnostic> ThreadAgnostic for Instrumented<M> {}
+
+impl<M: UpgradableModerator> UpgradableModerator for Instrumented<M> {
+    #[inline]
+    fn try_read_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        if sync.stats.acquire(duration, |duration| M::try_read_upgradable(&sync.inner, duration)) {
+            Stats::count(&sync.stats.reads);
+            sync.stats.read_locked();
+            true
+        } else {
+            Stats::count(&sync.stats.timeouts);
+            false
+        }
+    }
+
+    #[inline]
+    fn upgradable_unlock(sync: &Self::Sync) {
+        sync.stats.read_unlocked();
+        M::upgradable_unlock(&sync.inner);
+    }
+
+    #[inline]
+    fn try_upgrade_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        upgrade(sync, duration, |duration| M::try_upgrade_upgradable(&sync.inner, duration))
+    }
+
+    #[inline]
+    fn downgrade_upgradable(sync: &Self::Sync) {
+        M::downgrade_upgradable(&sync.inner);
+    }
+
+    #[inline]
+    fn downgrade_to_upgradable(sync: &Self::Sync) {
+        sync.stats.write_unlocked();
+        sync.stats.read_locked();
+        M::downgrade_to_upgradable(&sync.inner);
+    }
+}
+
+impl<T: ?Sized, M: Moderator> ZLock<T, Instrumented<M>> {
+    /// Returns the contention statistics collected since the lock was created, or since
+    /// the last call to [`reset_stats`](Self::reset_stats).
+    #[inline]
+    pub fn stats(&self) -> StatsSnapshot {
+        self.sync.stats.snapshot()
+    }
+
+    /// Clears the collected statistics. Locks that are presently held continue to be timed.
+    #[inline]
+    pub fn reset_stats(&self) {
+        self.sync.stats.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,112 @@
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+use crate::test_utils::{CHECK_WAIT, SHORT_WAIT};
+use crate::zlock::{ArrivalOrdered, Instrumented, PhaseFair, Prioritized, ReadBiased, StatsSnapshot, Stochastic, UpgradableModerator, WriteBiased, ZLock};
+
+#[test]
+fn uncontended() {
+    __uncontended::<ReadBiased>();
+    __uncontended::<WriteBiased>();
+    __uncontended::<ArrivalOrdered>();
+    __uncontended::<Stochastic>();
+    __uncontended::<PhaseFair>();
+    __uncontended::<Prioritized>();
+}
+
+fn __uncontended<M: UpgradableModerator>() {
+    let lock = ZLock::<_, Instrumented<M>>::new(0);
+    assert_eq!(StatsSnapshot::default(), lock.stats());
+
+    drop(lock.read());
+    drop(lock.read());
+    drop(lock.write().downgrade());
+    drop(lock.read_upgradable().upgrade());
+
+    let stats = lock.stats();
+    assert_eq!(3, stats.reads);
+    assert_eq!(1, stats.writes);
+    assert_eq!(1, stats.upgrades);
+    assert_eq!(0, stats.contended);
+    assert_eq!(0, stats.timeouts);
+    assert_eq!(0, stats.failed_upgrades);
+    assert_eq!(Duration::ZERO, stats.total_wait);
+}
+
+#[test]
+fn timeouts_and_failed_upgrades() {
+    __timeouts_and_failed_upgrades::<ReadBiased>();
+    __timeouts_and_failed_upgrades::<WriteBiased>();
+    __timeouts_and_failed_upgrades::<ArrivalOrdered>();
+    __timeouts_and_failed_upgrades::<Stochastic>();
+    __timeouts_and_failed_upgrades::<PhaseFair>();
+    __timeouts_and_failed_upgrades::<Prioritized>();
+}
+
+fn __timeouts_and_failed_upgrades<M: UpgradableModerator>() {
+    let lock = ZLock::<_, Instrumented<M>>::new(0);
+    let guard = lock.read();
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    assert!(lock.try_write(SHORT_WAIT).is_none());
+    assert_eq!(2, lock.stats().timeouts);
+
+    let other = lock.read();
+    let guard = guard.try_upgrade(SHORT_WAIT).unchanged().unwrap();
+    drop(other);
+    drop(guard);
+
+    let stats = lock.stats();
+    assert_eq!(2, stats.reads);
+    assert_eq!(0, stats.writes);
+    assert_eq!(0, stats.upgrades);
+    assert_eq!(0, stats.contended);
+    assert_eq!(2, stats.timeouts);
+    assert_eq!(1, stats.failed_upgrades);
+    assert!(stats.total_wait > Duration::ZERO);
+}
+
+#[test]
+fn contended_and_hold_times() {
+    __contended_and_hold_times::<ReadBiased>();
+    __contended_and_hold_times::<WriteBiased>();
+    __contended_and_hold_times::<ArrivalOrdered>();
+    __contended_and_hold_times::<Stochastic>();
+    __contended_and_hold_times::<PhaseFair>();
+    __contended_and_hold_times::<Prioritized>();
+}
+
+fn __contended_and_hold_times<M: UpgradableModerator + 'static>() {
+    let lock = Arc::new(ZLock::<_, Instrumented<M>>::new(0));
+    let barrier = Arc::new(Barrier::new(2));
+
+    let guard = lock.write();
+    let thread = thread::spawn({
+        let (lock, barrier) = (lock.clone(), barrier.clone());
+        move || {
+            barrier.wait();
+            *lock.write() += 1;
+            drop(lock.read());
+        }
+    });
+    barrier.wait();
+    thread::sleep(CHECK_WAIT);
+    drop(guard);
+    thread.join().unwrap();
+
+    let stats = lock.stats();
+    assert_eq!(1, stats.reads);
+    assert_eq!(2, stats.writes);
+    assert_eq!(1, stats.contended);
+    assert!(stats.total_wait > Duration::ZERO);
+    assert!(stats.max_write_hold >= CHECK_WAIT, "{stats:?}");
+    assert!(stats.max_read_hold < CHECK_WAIT, "{stats:?}");
+
+    let guard = lock.read();
+    thread::sleep(CHECK_WAIT);
+    drop(lock.read());
+    drop(guard);
+    assert!(lock.stats().max_read_hold >= CHECK_WAIT);
+
+    lock.reset_stats();
+    assert_eq!(StatsSnapshot::default(), lock.stats());
+}
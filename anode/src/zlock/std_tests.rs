@@ -12,7 +12,7 @@ use crate::zlock::locklike::LockBoxSized;
 use crate::zlock::locklike::LockReadGuardlike;
 use crate::zlock::locklike::LockWriteGuardlike;
 use crate::zlock::locklike::MODERATOR_KINDS;
-use crate::zlock::{ArrivalOrdered, Moderator, ReadBiased, Stochastic, WriteBiased, ZLock};
+use crate::zlock::{ArrivalOrdered, Moderator, PhaseFair, Prioritized, ReadBiased, Sharded, Stochastic, WriteBiased, ZLock};
 use crate::zlock::UpgradeOutcome::Upgraded;
 
 #[derive(Eq, PartialEq, Debug)]
@@ -49,6 +49,21 @@ fn frob_stochastic() {
     __frob(ZLock::<_, Stochastic>::new(()), 10, 1000);
 }
 
+#[test]
+fn frob_phase_fair() {
+    __frob(ZLock::<_, PhaseFair>::new(()), 10, 1000);
+}
+
+#[test]
+fn frob_prioritized() {
+    __frob(ZLock::<_, Prioritized>::new(()), 10, 100);
+}
+
+#[test]
+fn frob_sharded() {
+    __frob(ZLock::<_, Sharded>::new(()), 10, 1000);
+}
+
 /// Enhanced over the original test to exercise both the read/write and the try_read/try_write paths,
 /// as well as downgrade and try_upgrade.
 fn __frob<M: Moderator + 'static>(lock: ZLock<(), M>, threads: usize, runs: usize) {
@@ -1,24 +1,79 @@
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
+use crate::backoff::ExpBackoff;
 use crate::deadline::Deadline;
 use crate::inf_iterator::{InfIterator};
-use crate::monitor::{Directive, Monitor, SpeculativeMonitor};
+use crate::monitor::{Directive, Monitor, Notification, SpeculativeMonitor};
 use crate::rand::{Rand, Seeded, Xorshift, CyclicSeed, Probability};
-use crate::zlock::{Moderator};
+use crate::zlock::{AsyncModerator, LockState, Moderator, ModeratorMonitor, ThreadAgnostic, UpgradableModerator, Waiter, READ_QUEUE, WRITE_QUEUE};
+use crate::zlock::word::{Enlistment, LockWord, READER, UPGRADABLE, WRITER};
 
 #[derive(Debug)]
 pub struct Stochastic;
 
+/// Tunes a [`Stochastic`] moderator.
+///
+/// A reader that arrives while a writer is pending is assigned a position among the
+/// queued readers, and is privileged to acquire the lock ahead of the writer with a
+/// probability that is a function of its position.
+#[derive(Debug, Clone, Copy)]
+pub struct StochasticConfig {
+    /// The position at and beyond which readers are never privileged.
+    pub cutoff: u32,
+
+    /// The probability that a reader at the given position is privileged, in the range
+    /// \[0, 1\]. The function is evaluated within the monitor, so a value outside the range
+    /// only fails a debug assertion; release builds clamp it, taking NaN as 0.
+    pub privilege: fn(u32) -> f64,
+
+    /// The backoff policy of the monitor's tracker.
+    pub backoff: &'static ExpBackoff,
+}
+
+impl StochasticConfig {
+    /// Readers at the first 64 positions are privileged with a probability of
+    /// `1 / (position + 2)`.
+    pub const DEFAULT: Self = Self {
+        cutoff: 64,
+        privilege: |position| 1.0 / (position as f64 + 2.0),
+        backoff: &ExpBackoff::SLEEPY,
+    };
+}
+
+impl Default for StochasticConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 pub struct StochasticSync {
-    monitor: SpeculativeMonitor<StochasticState>,
+    word: LockWord,
+    monitor: ModeratorMonitor<StochasticState>,
+}
+
+impl StochasticSync {
+    const fn new(config: StochasticConfig) -> Self {
+        Self {
+            word: LockWord::new(),
+            monitor: SpeculativeMonitor::with_queues(StochasticState {
+                writer_pending: false,
+                queued: 0,
+                seed: CyclicSeed::new(0),
+                cutoff: config.cutoff,
+                privilege: config.privilege,
+            }, config.backoff),
+        }
+    }
 }
 
 #[derive(Debug)]
 struct StochasticState {
-    readers: u32,
-    writer: bool,
     writer_pending: bool,
     queued: u32,
     seed: CyclicSeed,
+    cutoff: u32,
+    privilege: fn(u32) -> f64,
 }
 
 impl StochasticState {
@@ -32,209 +87,456 @@ impl StochasticState {
 
 impl Moderator for Stochastic {
     type Sync = StochasticSync;
+    type ModeratorConfig = StochasticConfig;
+
+    const INIT: Self::Sync = StochasticSync::new(StochasticConfig::DEFAULT);
 
     #[inline]
-    fn new() -> Self::Sync {
-        Self::Sync {
-            monitor: SpeculativeMonitor::new(StochasticState {
-                readers: 0,
-                writer: false,
-                writer_pending: false,
-                queued: 0,
-                seed: CyclicSeed::default()
-            }),
-        }
+    fn with_config(config: Self::ModeratorConfig) -> Self::Sync {
+        StochasticSync::new(config)
     }
 
     #[inline]
     fn try_read(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        let mut saw_no_pending_writer = false;
-        let mut privilege_determined = false;
-        let mut position = None;
-        sync.monitor.enter(|state| {
-            if !acquired {
-                if !saw_no_pending_writer {
-                    if position.is_none() {
-                        position = Some(state.enqueue());
-                    }
+        Self::try_read_until(sync, Deadline::lazy_after(duration))
+    }
 
-                    if !state.writer_pending {
-                        saw_no_pending_writer = true;
-                    } else if !privilege_determined {
-                        privilege_determined = true;
-                        let position = position.unwrap();
-                        if position < 64 {
-                            let divisor = position as f64 + 2.0;
-                            let p_privileged = 1.0 / divisor;
-                            let mut rng = Xorshift::seed(state.seed.next());
-                            let probability = unsafe { Probability::new_unchecked(p_privileged) };
-                            if rng.next_bool(probability) {
-                                saw_no_pending_writer = true
-                            }
-                        }
-                    }
+    #[inline]
+    fn try_read_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_read() {
+            return true;
+        }
+
+        let mut waiter = ReadWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn read_unlock(sync: &Self::Sync) {
+        if sync.word.try_fast_read_unlock() {
+            return;
+        }
+
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() > 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(!sync.word.load().writer());
+
+            // readers are never held back by other readers
+            match sync.word.shift(READER, 0).readers() {
+                0 | 1 => Directive::NotifyAndReturn(Notification::all(WRITE_QUEUE)),
+                _ => Directive::Return
+            }
+        });
+    }
+
+    #[inline]
+    fn try_write(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_write_until(sync, Deadline::lazy_after(duration))
+    }
+
+    #[inline]
+    fn try_write_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_write() {
+            return true;
+        }
+
+        let mut waiter = WriteWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn write_unlock(sync: &Self::Sync) {
+        if sync.word.try_fast_write_unlock() {
+            return;
+        }
+
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, 0);
+            Directive::NotifyAllAndReturn
+        });
+    }
+
+    fn downgrade(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+
+    fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool {
+        Self::try_upgrade_until(sync, Deadline::lazy_after(duration))
+    }
+
+    fn try_upgrade_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        let mut waiter = UpgradeWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn inspect(sync: &Self::Sync) -> LockState {
+        sync.monitor.compute(|state| LockState {
+            writer_pending: state.writer_pending,
+            ..sync.word.load().inspect()
+        })
+    }
+}
+
+unsafe impl ThreadAgnostic for Stochastic {}
+
+impl UpgradableModerator for Stochastic {
+    #[inline]
+    fn try_read_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = ReadWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn upgradable_unlock(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, 0);
+            Directive::NotifyAllAndReturn
+        });
+    }
+
+    fn try_upgrade_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = UpgradeWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn downgrade_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+
+    fn downgrade_to_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, UPGRADABLE);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+}
+
+impl AsyncModerator for Stochastic {
+    type ReadWaiter = ReadWaiter;
+    type WriteWaiter = WriteWaiter;
+    type UpgradeWaiter = UpgradeWaiter;
+
+    #[inline]
+    fn forget(sync: &Self::Sync, waker: &Waker) {
+        sync.monitor.forget(waker);
+    }
+}
+
+/// Clears the `writer_pending` flag raised by a writer (or upgrader) that has given up on
+/// the lock, notifying the readers that were held back by the flag. A writer that acquires
+/// the lock clears the flag as it does so.
+#[inline]
+fn clear_writer_pending(sync: &StochasticSync) {
+    sync.monitor.enter(|state| {
+        state.writer_pending = false;
+        Directive::NotifyAllAndReturn
+    });
+}
+
+/// Clears the `writer_pending` flag as a writer (or upgrader) that raised it acquires the
+/// lock, within the same critical section. No notification is needed, as the readers held
+/// back by the flag remain excluded by the lock itself.
+#[inline]
+fn withdraw_writer_pending(self_writer_pending: &mut bool, state: &mut StochasticState) {
+    if *self_writer_pending {
+        *self_writer_pending = false;
+        state.writer_pending = false;
+    }
+}
+
+pub struct ReadWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    upgradable: bool,
+    saw_no_pending_writer: bool,
+    privilege_determined: bool,
+    position: Option<u32>,
+    enlistment: Enlistment,
+}
+
+impl ReadWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self {
+            deadline,
+            acquired: false,
+            upgradable: false,
+            saw_no_pending_writer: false,
+            privilege_determined: false,
+            position: None,
+            enlistment: Enlistment::default(),
+        }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { upgradable: true, ..Self::new(deadline) }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut StochasticState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            if !self.saw_no_pending_writer {
+                if self.position.is_none() {
+                    self.position = Some(state.enqueue());
                 }
 
-                if !state.writer && saw_no_pending_writer {
-                    acquired = true;
-                    state.readers += 1;
+                if !state.writer_pending {
+                    self.saw_no_pending_writer = true;
+                } else if !self.privilege_determined {
+                    self.privilege_determined = true;
+                    let position = self.position.unwrap();
+                    if position < state.cutoff {
+                        let mut rng = Xorshift::seed(state.seed.next());
+                        let privilege = (state.privilege)(position);
+                        debug_assert!(
+                            (0.0..=1.0).contains(&privilege),
+                            "privilege ({privilege}) of position {position} is not in [0, 1]"
+                        );
+                        let privilege = if privilege.is_nan() { 0.0 } else { privilege.clamp(0.0, 1.0) };
+                        let probability = Probability::new(privilege);
+                        if rng.next_bool(probability) {
+                            self.saw_no_pending_writer = true
+                        }
+                    }
                 }
             }
 
-            if acquired {
-                state.queued -= 1;
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
+            let word_state = word.load();
+            if !word_state.writer() && self.saw_no_pending_writer && (!self.upgradable || !word_state.upgradable()) {
+                self.acquired = true;
+                self.enlistment.acquire(word, 0, if self.upgradable { UPGRADABLE } else { READER });
             }
-        });
+        }
 
+        if self.acquired {
+            state.queued -= 1;
+            Directive::Return
+        } else {
+            Directive::WaitOn(READ_QUEUE, self.deadline.remaining())
+        }
+    }
 
-        if !acquired {
+    #[inline]
+    fn conclude(&mut self, sync: &StochasticSync) {
+        if !self.acquired && self.position.take().is_some() {
             sync.monitor.alter(|state| {
                 state.queued -= 1;
             })
         }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
 
-        acquired
+impl Default for ReadWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
     }
+}
 
+impl Waiter<StochasticSync> for ReadWaiter {
     #[inline]
-    fn read_unlock(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers > 0, "readers: {}", state.readers);
-                debug_assert!(!state.writer);
-
-                released = true;
-                state.readers -= 1;
-            }
+    fn poll_acquire(&mut self, sync: &StochasticSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
 
-            match state.readers {
-                0 | 1 => Directive::NotifyAll,
-                _ => Directive::Return
-            }
-        });
+    #[inline]
+    fn cancel(&mut self, sync: &StochasticSync) {
+        self.conclude(sync);
     }
+}
 
+pub struct WriteWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    self_writer_pending: bool,
+    enlistment: Enlistment,
+}
+
+impl WriteWaiter {
     #[inline]
-    fn try_write(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        let mut self_writer_pending = false;
-        sync.monitor.enter(|state| {
-            if !acquired {
-                if state.readers == 0 && !state.writer {
-                    state.writer = true;
-                    acquired = true;
-                } else if !state.writer_pending {
-                    self_writer_pending = true;
-                    state.writer_pending = true;
-                }
-            }
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_writer_pending: false, enlistment: Enlistment::default() }
+    }
 
-            if acquired {
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut StochasticState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let word_state = word.load();
+            if word_state.readers() == 0 && !word_state.writer() && !word_state.upgradable() {
+                self.acquired = true;
+                self.enlistment.acquire(word, 0, WRITER);
+                withdraw_writer_pending(&mut self.self_writer_pending, state);
+            } else if !state.writer_pending {
+                self.self_writer_pending = true;
+                state.writer_pending = true;
             }
-        });
+        }
 
-        if self_writer_pending {
-            let mut cleared_writer_pending = false;
-            sync.monitor.enter(|state| {
-                if !cleared_writer_pending {
-                    cleared_writer_pending = true;
-                    state.writer_pending = false;
-                }
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
 
-                if acquired {
-                    Directive::Return
-                } else {
-                    Directive::NotifyAll
-                }
-            });
+    #[inline]
+    fn conclude(&mut self, sync: &StochasticSync) {
+        if self.self_writer_pending {
+            self.self_writer_pending = false;
+            clear_writer_pending(sync);
         }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
 
-        acquired
+impl Default for WriteWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
     }
+}
 
+impl Waiter<StochasticSync> for WriteWaiter {
     #[inline]
-    fn write_unlock(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers == 0, "readers: {}", state.readers);
-                debug_assert!(state.writer);
-
-                released = true;
-                state.writer = false;
-            }
+    fn poll_acquire(&mut self, sync: &StochasticSync, cx: &mut Context<'_>) -> Poll<()> {
+        let poll = sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx);
+        if poll.is_ready() {
+            self.conclude(sync);
+        }
+        poll
+    }
 
-            Directive::NotifyAll
-        });
+    #[inline]
+    fn cancel(&mut self, sync: &StochasticSync) {
+        self.conclude(sync);
     }
+}
 
-    fn downgrade(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers == 0, "readers: {}", state.readers);
-                debug_assert!(state.writer);
-
-                released = true;
-                state.writer = false;
-                state.readers = 1;
-            }
+pub struct UpgradeWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    self_writer_pending: bool,
+    enlistment: Enlistment,
 
-            Directive::NotifyAll
-        });
+    /// Whether the lock being upgraded is an upgradable read lock, as opposed to an
+    /// ordinary read lock.
+    upgradable: bool,
+}
+
+impl UpgradeWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_writer_pending: false, enlistment: Enlistment::default(), upgradable: false }
     }
 
-    fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        let mut self_writer_pending = false;
-        sync.monitor.enter(|state| {
-            if !acquired {
-                debug_assert!(!state.writer);
-
-                if state.readers == 1 {
-                    acquired = true;
-                    state.readers = 0;
-                    state.writer = true;
-                } else if !state.writer_pending {
-                    self_writer_pending = true;
-                    state.writer_pending = true;
-                }
-            }
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_writer_pending: false, enlistment: Enlistment::default(), upgradable: true }
+    }
+
+    /// An upgradable reader need only wait for the ordinary readers to drain, whereas an
+    /// ordinary reader must be the sole holder of the lock.
+    #[inline]
+    fn can_acquire(&self, readers: u32, upgradable: bool) -> bool {
+        if self.upgradable {
+            debug_assert!(upgradable);
+            readers == 0
+        } else {
+            readers == 1 && !upgradable
+        }
+    }
 
-            if acquired {
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut StochasticState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let word_state = word.load();
+            debug_assert!(!word_state.writer());
+
+            if self.can_acquire(word_state.readers(), word_state.upgradable()) {
+                self.acquired = true;
+                self.enlistment.acquire(word, if self.upgradable { UPGRADABLE } else { READER }, WRITER);
+                withdraw_writer_pending(&mut self.self_writer_pending, state);
+            } else if !state.writer_pending {
+                self.self_writer_pending = true;
+                state.writer_pending = true;
             }
-        });
+        }
 
-        if self_writer_pending {
-            let mut cleared_writer_pending = false;
-            sync.monitor.enter(|state| {
-                if !cleared_writer_pending {
-                    cleared_writer_pending = true;
-                    state.writer_pending = false;
-                }
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
 
-                if acquired {
-                    Directive::Return
-                } else {
-                    Directive::NotifyAll
-                }
-            });
+    #[inline]
+    fn conclude(&mut self, sync: &StochasticSync) {
+        if self.self_writer_pending {
+            self.self_writer_pending = false;
+            clear_writer_pending(sync);
         }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
 
-        acquired
+impl Default for UpgradeWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
     }
 }
+
+impl Waiter<StochasticSync> for UpgradeWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &StochasticSync, cx: &mut Context<'_>) -> Poll<()> {
+        let poll = sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx);
+        if poll.is_ready() {
+            self.conclude(sync);
+        }
+        poll
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &StochasticSync) {
+        self.conclude(sync);
+    }
+}
+
+#[cfg(test)]
+mod tests;
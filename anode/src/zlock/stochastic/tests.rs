@@ -0,0 +1,54 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::executor::{Executor, Queue, Submitter, ThreadPool};
+use crate::monitor::Monitor;
+use crate::test_utils::{LONG_WAIT, SHORT_WAIT};
+use crate::wait;
+use crate::wait::{Wait, WaitResult};
+use crate::zlock::{Stochastic, StochasticConfig, ZLock};
+
+#[test]
+fn beyond_cutoff_never_privileged() {
+    let config = StochasticConfig { cutoff: 0, privilege: |_| 1.0, ..StochasticConfig::default() };
+    let privileged = __privileged(config);
+    assert!(!privileged);
+}
+
+#[test]
+fn within_cutoff_privileged() {
+    let config = StochasticConfig { cutoff: u32::MAX, privilege: |_| 1.0, ..StochasticConfig::default() };
+    let privileged = __privileged(config);
+    assert!(privileged);
+}
+
+/// Attempts to read-acquire the lock while a writer is pending, returning `true` if the
+/// reader was privileged to overtake the writer.
+fn __privileged(config: StochasticConfig) -> bool {
+    let lock = Arc::new(ZLock::<_, Stochastic>::with_config(0, config));
+    let guard_1 = lock.read();
+
+    let t_2 = ThreadPool::new(1, Queue::Unbounded);
+    let t_2_write = {
+        let lock = lock.clone();
+        t_2.submitter().submit(move || {
+            drop(lock.write());
+        })
+    };
+    lock.wait_for_writer_pending_flag(true, LONG_WAIT).unwrap();
+
+    let privileged = lock.try_read(SHORT_WAIT).is_some();
+    drop(guard_1);
+    assert!(t_2_write.get().is_success());
+    privileged
+}
+
+impl<T> ZLock<T, Stochastic> {
+    fn is_writer_pending(&self) -> bool {
+        self.sync.monitor.compute(|state| state.writer_pending)
+    }
+
+    fn wait_for_writer_pending_flag(&self, target: bool, duration: Duration) -> WaitResult {
+        wait::Spin::wait_for_inequality(|| self.is_writer_pending(), Ordering::is_eq, &target, duration)
+    }
+}
@@ -1,11 +1,21 @@
-use std::time::{Duration};
-use crate::zlock::locklike::{LockBoxSized, LockReadGuardlike, LockWriteGuardlike, MODERATOR_KINDS};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::deadline::Deadline;
+use crate::executor::{Executor, Queue, Submitter, ThreadPool};
+use crate::test_utils::{LONG_WAIT, SHORT_WAIT};
+use crate::wait;
+use crate::wait::Wait;
+use crate::backoff::ExpBackoff;
+use crate::zlock::{ArrivalOrdered, Instrumented, LegacyArrivalOrdered, LegacyReadBiased, LegacyWriteBiased, LockState, Moderator, PhaseFair, PoisonZLock, Prioritized, ReadBiased, Sharded, Stochastic, StochasticConfig, Tickets, WriteBiased, WriteBiasedConfig, ZLock};
+use crate::zlock::locklike::{LockBoxSized, LockReadGuardlike, LockWriteGuardlike, LocklikeKind, MODERATOR_KINDS};
 
 #[test]
 fn box_cycle() {
     for moderator in MODERATOR_KINDS {
         __box_cycle(moderator.make_lock_for_test(42));
     }
+    __box_cycle(LocklikeKind::StdRwLock.make_lock(42));
+    __box_cycle(LocklikeKind::StdMutex.make_lock(42));
 }
 
 fn __box_cycle(boxed: LockBoxSized<i32>) {
@@ -77,3 +87,167 @@ fn __box_cycle(boxed: LockBoxSized<i32>) {
         assert_eq!(1983, *guard);
     }
 }
+
+#[test]
+fn static_lock() {
+    static READ_BIASED: ZLock<u64, ReadBiased> = ZLock::new(0);
+    static WRITE_BIASED: ZLock<u64, WriteBiased> = ZLock::new(0);
+    static ARRIVAL_ORDERED: ZLock<u64, ArrivalOrdered> = ZLock::new(0);
+    static STOCHASTIC: ZLock<u64, Stochastic> = ZLock::new(0);
+    static PHASE_FAIR: ZLock<u64, PhaseFair> = ZLock::new(0);
+    static PRIORITIZED: ZLock<u64, Prioritized> = ZLock::new(0);
+    static SHARDED: ZLock<u64, Sharded> = ZLock::new(0);
+    static LEGACY_READ_BIASED: ZLock<u64, LegacyReadBiased> = ZLock::new(0);
+    static LEGACY_WRITE_BIASED: ZLock<u64, LegacyWriteBiased> = ZLock::new(0);
+    static LEGACY_ARRIVAL_ORDERED: ZLock<u64, LegacyArrivalOrdered> = ZLock::new(0);
+    static INSTRUMENTED: ZLock<u64, Instrumented<WriteBiased>> = ZLock::new(0);
+    static POISON: PoisonZLock<u64, WriteBiased> = PoisonZLock::new(0);
+
+    *READ_BIASED.write() += 1;
+    *WRITE_BIASED.write() += 1;
+    *ARRIVAL_ORDERED.write() += 1;
+    *STOCHASTIC.write() += 1;
+    *PHASE_FAIR.write() += 1;
+    *PRIORITIZED.write() += 1;
+    *SHARDED.write() += 1;
+    *LEGACY_READ_BIASED.write() += 1;
+    *LEGACY_WRITE_BIASED.write() += 1;
+    *LEGACY_ARRIVAL_ORDERED.write() += 1;
+    *INSTRUMENTED.write() += 1;
+    *POISON.write().unwrap() += 1;
+
+    assert_eq!(1, *READ_BIASED.read());
+    assert_eq!(1, *WRITE_BIASED.read());
+    assert_eq!(1, *ARRIVAL_ORDERED.read());
+    assert_eq!(1, *STOCHASTIC.read());
+    assert_eq!(1, *PHASE_FAIR.read());
+    assert_eq!(1, *PRIORITIZED.read());
+    assert_eq!(1, *SHARDED.read());
+    assert_eq!(1, *LEGACY_READ_BIASED.read());
+    assert_eq!(1, *LEGACY_WRITE_BIASED.read());
+    assert_eq!(1, *LEGACY_ARRIVAL_ORDERED.read());
+    assert_eq!(1, *INSTRUMENTED.read());
+    assert_eq!(2, INSTRUMENTED.stats().reads + INSTRUMENTED.stats().writes);
+    assert_eq!(1, *POISON.read().unwrap());
+}
+
+#[test]
+fn with_config() {
+    __with_config::<ReadBiased>(&ExpBackoff::SPINNY);
+    __with_config::<WriteBiased>(WriteBiasedConfig { max_consecutive_writes: Some(1), backoff: &ExpBackoff::YIELDY, ..WriteBiasedConfig::default() });
+    __with_config::<ArrivalOrdered>(&ExpBackoff::SPINNY);
+    __with_config::<Stochastic>(StochasticConfig { cutoff: 8, ..StochasticConfig::default() });
+    __with_config::<PhaseFair>(&ExpBackoff::SPINNY);
+    __with_config::<Prioritized>(&ExpBackoff::SPINNY);
+    __with_config::<Sharded>(&ExpBackoff::SPINNY);
+    __with_config::<LegacyReadBiased>(());
+    __with_config::<Instrumented<ReadBiased>>(&ExpBackoff::YIELDY);
+}
+
+fn __with_config<M: Moderator>(config: M::ModeratorConfig) {
+    let lock = ZLock::<_, M>::with_config(0, config);
+    *lock.write() += 1;
+    let guard = lock.read();
+    assert_eq!(1, *guard);
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    drop(guard);
+    assert_eq!(1, lock.into_inner());
+}
+
+#[test]
+fn shared_deadline() {
+    __shared_deadline::<ReadBiased>();
+    __shared_deadline::<WriteBiased>();
+    __shared_deadline::<ArrivalOrdered>();
+    __shared_deadline::<Stochastic>();
+    __shared_deadline::<PhaseFair>();
+    __shared_deadline::<Prioritized>();
+    __shared_deadline::<Sharded>();
+    __shared_deadline::<LegacyReadBiased>();
+    __shared_deadline::<LegacyWriteBiased>();
+    __shared_deadline::<LegacyArrivalOrdered>();
+    __shared_deadline::<Instrumented<ReadBiased>>();
+}
+
+fn __shared_deadline<M: Moderator>() {
+    let lock = ZLock::<_, M>::new(0);
+    let mut deadline = Deadline::after(SHORT_WAIT);
+    let guard_1 = lock.try_read_until(deadline.resolved()).unwrap();
+    let guard_2 = lock.try_read_until(deadline.resolved()).unwrap();
+
+    // the deadline elapses while the first writer waits
+    assert!(lock.try_write_until(deadline.resolved()).is_none());
+    assert!(deadline.remaining().is_zero());
+    let guard_1 = guard_1.try_upgrade_until(deadline.resolved()).unchanged().unwrap();
+    drop(guard_2);
+
+    let mut guard_1 = guard_1.try_upgrade_until(Deadline::Forever).upgraded().unwrap();
+    *guard_1 = 42;
+    drop(guard_1);
+    assert_eq!(42, *lock.try_write_until(Instant::now()).unwrap());
+}
+
+#[test]
+fn state() {
+    __state::<ReadBiased>();
+    __state::<WriteBiased>();
+    __state::<ArrivalOrdered>();
+    __state::<Stochastic>();
+    __state::<PhaseFair>();
+    __state::<Prioritized>();
+    __state::<Sharded>();
+    __state::<LegacyReadBiased>();
+    __state::<LegacyWriteBiased>();
+    __state::<LegacyArrivalOrdered>();
+    __state::<Instrumented<ReadBiased>>();
+}
+
+fn __state<M: Moderator>() {
+    let lock = ZLock::<_, M>::new(0);
+    let state = lock.state();
+    assert_eq!(LockState { tickets: state.tickets, ..LockState::default() }, state);
+
+    let guard_1 = lock.read();
+    let guard_2 = lock.read();
+    assert_eq!(2, lock.state().readers);
+    assert!(!lock.state().writer);
+    drop((guard_1, guard_2));
+
+    let guard = lock.write();
+    let state = lock.state();
+    assert_eq!((0, true), (state.readers, state.writer));
+    assert!(format!("{lock:?}").contains("writer: true"), "{lock:?}");
+    drop(guard);
+    assert_eq!(LockState { tickets: lock.state().tickets, ..LockState::default() }, lock.state());
+}
+
+#[test]
+fn state_of_contended_lock() {
+    let lock = Arc::new(ZLock::<_, WriteBiased>::new(0));
+    let guard_1 = lock.read();
+
+    let t_2 = ThreadPool::new(1, Queue::Unbounded);
+    let t_2_write = {
+        let lock = lock.clone();
+        t_2.submitter().submit(move || {
+            drop(lock.write());
+        })
+    };
+    wait::Spin::wait_for(|| lock.state().writer_pending, LONG_WAIT).unwrap();
+    let state = lock.state();
+    assert_eq!((1, false, 1), (state.readers, state.writer, state.waiting));
+
+    drop(guard_1);
+    assert!(t_2_write.get().is_success());
+    assert_eq!(LockState::default(), lock.state());
+}
+
+#[test]
+fn state_tickets() {
+    let initial = Some(Tickets { next: 1, serviced: 0 });
+    assert_eq!(initial, ZLock::<(), ArrivalOrdered>::new(()).state().tickets);
+    assert_eq!(initial, ZLock::<(), PhaseFair>::new(()).state().tickets);
+    assert_eq!(initial, ZLock::<(), Prioritized>::new(()).state().tickets);
+    assert_eq!(initial, ZLock::<(), LegacyArrivalOrdered>::new(()).state().tickets);
+    assert_eq!(None, ZLock::<(), ReadBiased>::new(()).state().tickets);
+}
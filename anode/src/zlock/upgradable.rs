@@ -0,0 +1,116 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::time::Duration;
+use crate::zlock::{LockReadGuard, LockWriteGuard, UpgradableModerator, UpgradeOutcome, ZLock};
+
+unsafe impl<T: ?Sized + Sync, M: UpgradableModerator> Sync for UpgradableLockReadGuard<'_, T, M> {}
+
+impl<T: ?Sized, M: UpgradableModerator> ZLock<T, M> {
+    /// Acquires an upgradable read lock. The lock admits ordinary readers alongside it, but
+    /// excludes writers and other upgradable readers.
+    ///
+    /// Unlike [`LockReadGuard::upgrade`], which requires the upgrading reader to be the sole
+    /// holder of the lock, [`UpgradableLockReadGuard::upgrade`] only waits for the ordinary
+    /// readers to drain; hence, it cannot deadlock against another upgradable reader, of
+    /// which there is at most one. It does deadlock against an ordinary reader that is
+    /// itself upgrading, as each awaits the other's release.
+    #[inline]
+    pub fn read_upgradable(&self) -> UpgradableLockReadGuard<'_, T, M> {
+        self.try_read_upgradable(Duration::MAX).unwrap()
+    }
+
+    #[inline]
+    pub fn try_read_upgradable(&self, duration: Duration) -> Option<UpgradableLockReadGuard<'_, T, M>> {
+        self.id.attempt(self, duration);
+        if M::try_read_upgradable(&self.sync, duration) {
+            self.id.acquired(self);
+            Some(self.upgradable_guard())
+        } else {
+            None
+        }
+    }
+
+    /// Wraps an upgradable read lock that has already been acquired through the moderator.
+    #[inline]
+    fn upgradable_guard(&self) -> UpgradableLockReadGuard<'_, T, M> {
+        UpgradableLockReadGuard {
+            lock: self,
+            locked: true,
+            __no_send: PhantomData,
+        }
+    }
+}
+
+pub struct UpgradableLockReadGuard<'a, T: ?Sized + 'a, M: UpgradableModerator + 'a> {
+    lock: &'a ZLock<T, M>,
+    locked: bool,
+
+    /// Emulates !Send for the struct. (Until issue 68318 -- negative trait bounds -- is resolved.)
+    __no_send: PhantomData<*const ()>,
+}
+
+impl<T: ?Sized, M: UpgradableModerator> Drop for UpgradableLockReadGuard<'_, T, M> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.locked {
+            self.lock.id.released();
+            M::upgradable_unlock(&self.lock.sync);
+        }
+    }
+}
+
+impl<'a, T: ?Sized, M: UpgradableModerator> UpgradableLockReadGuard<'a, T, M> {
+    /// Upgrades to a write lock once the ordinary readers have drained.
+    ///
+    /// Blocks forever if one of the ordinary readers is upgrading by way of
+    /// [`LockReadGuard::upgrade`], as that upgrade in turn awaits the release of this
+    /// guard. Ordinary readers that may upgrade while an upgradable reader exists should use
+    /// [`LockReadGuard::try_upgrade`] and release their read lock on failure.
+    #[inline]
+    pub fn upgrade(self) -> LockWriteGuard<'a, T, M> {
+        self.try_upgrade(Duration::MAX).upgraded().unwrap()
+    }
+
+    #[inline]
+    pub fn try_upgrade(mut self, duration: Duration) -> UpgradableUpgradeOutcome<'a, T, M> {
+        if M::try_upgrade_upgradable(&self.lock.sync, duration) {
+            self.locked = false;
+            UpgradeOutcome::Upgraded(self.lock.write_guard())
+        } else {
+            UpgradeOutcome::Unchanged(self)
+        }
+    }
+
+    /// Converts the upgradable read lock into an ordinary read lock, admitting another
+    /// upgradable reader or writer.
+    #[inline]
+    pub fn downgrade(mut self) -> LockReadGuard<'a, T, M> {
+        self.locked = false;
+        M::downgrade_upgradable(&self.lock.sync);
+        self.lock.read_guard()
+    }
+}
+
+impl<T: ?Sized, M: UpgradableModerator> Deref for UpgradableLockReadGuard<'_, T, M> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized, M: UpgradableModerator> LockWriteGuard<'a, T, M> {
+    /// Atomically downgrades the write lock to an upgradable read lock.
+    #[inline]
+    pub fn downgrade_to_upgradable(mut self) -> UpgradableLockReadGuard<'a, T, M> {
+        self.locked = false;
+        M::downgrade_to_upgradable(&self.lock.sync);
+        self.lock.upgradable_guard()
+    }
+}
+
+pub type UpgradableUpgradeOutcome<'a, T, M> = UpgradeOutcome<LockWriteGuard<'a, T, M>, UpgradableLockReadGuard<'a, T, M>>;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,189 @@
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+use crate::test_utils;
+use crate::test_utils::{CHECK_WAIT, SHORT_WAIT};
+use crate::zlock::{ArrivalOrdered, PhaseFair, Prioritized, ReadBiased, Stochastic, UpgradableModerator, WriteBiased, ZLock};
+
+#[test]
+fn upgradable_cycle() {
+    __upgradable_cycle::<ReadBiased>();
+    __upgradable_cycle::<WriteBiased>();
+    __upgradable_cycle::<ArrivalOrdered>();
+    __upgradable_cycle::<Stochastic>();
+    __upgradable_cycle::<PhaseFair>();
+    __upgradable_cycle::<Prioritized>();
+}
+
+fn __upgradable_cycle<M: UpgradableModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+    let guard = lock.read_upgradable();
+    assert_eq!(0, *guard);
+
+    // coexists with ordinary readers, but excludes writers and other upgradable readers
+    assert!(lock.try_read(Duration::ZERO).is_some());
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    assert!(lock.try_read_upgradable(Duration::ZERO).is_none());
+
+    let mut guard = guard.upgrade();
+    *guard = 42;
+    assert!(lock.try_read(Duration::ZERO).is_none());
+
+    let guard = guard.downgrade_to_upgradable();
+    assert_eq!(42, *guard);
+    assert!(lock.try_read(Duration::ZERO).is_some());
+    assert!(lock.try_read_upgradable(Duration::ZERO).is_none());
+
+    // an ordinary read lock no longer excludes other upgradable readers
+    let guard = guard.downgrade();
+    assert_eq!(42, *guard);
+    assert!(lock.try_write(Duration::ZERO).is_none());
+    assert!(lock.try_read_upgradable(Duration::ZERO).is_some());
+    drop(guard);
+
+    assert!(lock.try_write(Duration::ZERO).is_some());
+    assert_eq!(42, lock.into_inner());
+}
+
+#[test]
+fn ordinary_upgrade_excluded_by_upgradable() {
+    __ordinary_upgrade_excluded_by_upgradable::<ReadBiased>();
+    __ordinary_upgrade_excluded_by_upgradable::<WriteBiased>();
+    __ordinary_upgrade_excluded_by_upgradable::<ArrivalOrdered>();
+    __ordinary_upgrade_excluded_by_upgradable::<Stochastic>();
+    __ordinary_upgrade_excluded_by_upgradable::<PhaseFair>();
+    __ordinary_upgrade_excluded_by_upgradable::<Prioritized>();
+}
+
+fn __ordinary_upgrade_excluded_by_upgradable<M: UpgradableModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+    let upgradable = lock.read_upgradable();
+    let outcome = lock.read().try_upgrade(SHORT_WAIT);
+    assert!(outcome.is_unchanged());
+    drop(outcome);
+
+    // the upgradable reader is unaffected by the failed upgrade
+    assert!(upgradable.try_upgrade(Duration::ZERO).is_upgraded());
+}
+
+#[test]
+fn upgrade_awaits_readers() {
+    __upgrade_awaits_readers::<ReadBiased>();
+    __upgrade_awaits_readers::<WriteBiased>();
+    __upgrade_awaits_readers::<ArrivalOrdered>();
+    __upgrade_awaits_readers::<Stochastic>();
+    __upgrade_awaits_readers::<PhaseFair>();
+    __upgrade_awaits_readers::<Prioritized>();
+}
+
+fn __upgrade_awaits_readers<M: UpgradableModerator + 'static>() {
+    let lock = Arc::new(ZLock::<_, M>::new(0));
+    let guard = lock.read();
+
+    // an upgrade cannot complete with an active reader
+    let outcome = lock.read_upgradable().try_upgrade(SHORT_WAIT);
+    assert!(outcome.is_unchanged());
+    drop(outcome);
+
+    let upgrader = {
+        let lock = lock.clone();
+        test_utils::spawn_blocked(move || {
+            let mut guard = lock.read_upgradable().upgrade();
+            *guard = 42;
+        })
+    };
+    thread::sleep(CHECK_WAIT);
+    assert!(!upgrader.is_finished());
+
+    drop(guard);
+    upgrader.join().unwrap();
+    assert_eq!(42, *lock.read());
+}
+
+#[test]
+fn concurrent_upgrades() {
+    __concurrent_upgrades::<ReadBiased>();
+    __concurrent_upgrades::<WriteBiased>();
+    __concurrent_upgrades::<ArrivalOrdered>();
+    __concurrent_upgrades::<Stochastic>();
+    __concurrent_upgrades::<PhaseFair>();
+    __concurrent_upgrades::<Prioritized>();
+}
+
+fn __concurrent_upgrades<M: UpgradableModerator + 'static>() {
+    const THREADS: usize = 4;
+    const ITERATIONS: usize = 100;
+
+    let lock = Arc::new(ZLock::<_, M>::new(0));
+    let barrier = Arc::new(Barrier::new(THREADS));
+    let threads = (0..THREADS)
+        .map(|_| {
+            let lock = lock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                for _ in 0..ITERATIONS {
+                    let guard = lock.read_upgradable();
+                    let value = *guard;
+                    let mut guard = guard.upgrade();
+                    *guard = value + 1;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(THREADS * ITERATIONS, *lock.read());
+}
+
+#[test]
+fn pending_upgrade_holds_back_readers() {
+    __pending_upgrade_holds_back_readers::<WriteBiased>();
+    __pending_upgrade_holds_back_readers::<ArrivalOrdered>();
+}
+
+fn __pending_upgrade_holds_back_readers<M: UpgradableModerator + 'static>() {
+    let lock = Arc::new(ZLock::<_, M>::new(0));
+    let guard = lock.read();
+
+    let upgrader = {
+        let lock = lock.clone();
+        test_utils::spawn_blocked(move || {
+            let _guard = lock.read_upgradable().upgrade();
+        })
+    };
+    thread::sleep(CHECK_WAIT);
+    assert!(!upgrader.is_finished());
+
+    // readers arriving after the upgrade request are held back
+    assert!(lock.try_read(SHORT_WAIT).is_none());
+
+    drop(guard);
+    upgrader.join().unwrap();
+    assert!(lock.try_read(Duration::ZERO).is_some());
+}
+
+#[test]
+fn timeout_in_upgrade_unblocks_readers() {
+    __timeout_in_upgrade_unblocks_readers::<ReadBiased>();
+    __timeout_in_upgrade_unblocks_readers::<WriteBiased>();
+    __timeout_in_upgrade_unblocks_readers::<ArrivalOrdered>();
+    __timeout_in_upgrade_unblocks_readers::<Stochastic>();
+    __timeout_in_upgrade_unblocks_readers::<PhaseFair>();
+    __timeout_in_upgrade_unblocks_readers::<Prioritized>();
+}
+
+fn __timeout_in_upgrade_unblocks_readers<M: UpgradableModerator>() {
+    let lock = ZLock::<_, M>::new(0);
+    let guard = lock.read();
+    let outcome = lock.read_upgradable().try_upgrade(SHORT_WAIT);
+    assert!(outcome.is_unchanged());
+
+    // the upgradable lock is still held, but no longer holds back readers
+    assert!(lock.try_read(Duration::ZERO).is_some());
+    assert!(lock.try_read_upgradable(Duration::ZERO).is_none());
+    drop(outcome);
+    drop(guard);
+}
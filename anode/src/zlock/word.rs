@@ -0,0 +1,178 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::zlock::LockState;
+
+/// A single reader's contribution to the lock word.
+pub const READER: u64 = 1;
+
+/// Set while a writer holds the lock.
+pub const WRITER: u64 = 1 << 32;
+
+/// Set while an upgradable reader holds the lock.
+pub const UPGRADABLE: u64 = 1 << 33;
+
+/// A single contender's contribution to the lock word.
+pub const CONTENDER: u64 = 1 << 34;
+
+const READERS_MASK: u64 = WRITER - 1;
+
+const CONTENDERS_MASK: u64 = !(CONTENDER - 1);
+
+/// The packed state of a lock: the number of readers, the writer and upgradable bits, and
+/// the number of contenders -- threads (or tasks) acquiring the lock through the moderator's
+/// monitor.
+///
+/// While there are no contenders, readers and writers may acquire and release the lock with a
+/// single CAS, without entering the monitor. A contender disables this fast path for as long
+/// as it is enlisted, so that (a) no acquisition can bypass the moderator's fairness policy,
+/// and (b) every release is routed through the monitor, notifying the contenders. Conversely,
+/// while there are contenders, the word is only ever altered from within the monitor.
+pub struct LockWord(AtomicU64);
+
+impl LockWord {
+    #[inline]
+    pub const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    #[inline]
+    pub fn load(&self) -> Snapshot {
+        Snapshot(self.0.load(Ordering::Acquire))
+    }
+
+    /// Atomically transitions the word by subtracting `from` and adding `to`, returning the
+    /// resulting state. E.g., `shift(WRITER, READER)` downgrades a write lock.
+    #[inline]
+    pub fn shift(&self, from: u64, to: u64) -> Snapshot {
+        let delta = to.wrapping_sub(from);
+        Snapshot(self.0.fetch_add(delta, Ordering::AcqRel).wrapping_add(delta))
+    }
+
+    #[inline]
+    pub fn try_fast_read(&self) -> bool {
+        let mut current = self.0.load(Ordering::Relaxed);
+        while current & (WRITER | CONTENDERS_MASK) == 0 {
+            debug_assert!(current & READERS_MASK != READERS_MASK, "too many readers");
+            match self.0.compare_exchange_weak(current, current + READER, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => return true,
+                Err(actual) => current = actual,
+            }
+        }
+        false
+    }
+
+    #[inline]
+    pub fn try_fast_read_unlock(&self) -> bool {
+        let mut current = self.0.load(Ordering::Relaxed);
+        while current & CONTENDERS_MASK == 0 {
+            debug_assert!(current & READERS_MASK != 0);
+            match self.0.compare_exchange_weak(current, current - READER, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => return true,
+                Err(actual) => current = actual,
+            }
+        }
+        false
+    }
+
+    #[inline]
+    pub fn try_fast_write(&self) -> bool {
+        self.0.compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed).is_ok()
+    }
+
+    #[inline]
+    pub fn try_fast_write_unlock(&self) -> bool {
+        self.0.compare_exchange(WRITER, 0, Ordering::Release, Ordering::Relaxed).is_ok()
+    }
+}
+
+impl Debug for LockWord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.load(), f)
+    }
+}
+
+/// Tracks whether a waiter is enlisted as a contender in the [`LockWord`]. A waiter enlists
+/// upon first entering the monitor, and remains enlisted until it either acquires the lock
+/// or withdraws.
+#[derive(Default)]
+pub struct Enlistment(bool);
+
+impl Enlistment {
+    #[inline]
+    pub fn enlist(&mut self, word: &LockWord) {
+        if !self.0 {
+            self.0 = true;
+            word.shift(0, CONTENDER);
+        }
+    }
+
+    /// Atomically exchanges the contender's claim, along with `from` (the mode held by the
+    /// waiter, if any), for `to`.
+    #[inline]
+    pub fn acquire(&mut self, word: &LockWord, from: u64, to: u64) -> Snapshot {
+        debug_assert!(self.0);
+        self.0 = false;
+        word.shift(CONTENDER + from, to)
+    }
+
+    #[inline]
+    pub fn withdraw(&mut self, word: &LockWord) {
+        if self.0 {
+            self.0 = false;
+            word.shift(CONTENDER, 0);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot(u64);
+
+impl Snapshot {
+    #[inline]
+    pub fn readers(self) -> u32 {
+        (self.0 & READERS_MASK) as u32
+    }
+
+    #[inline]
+    pub fn writer(self) -> bool {
+        self.0 & WRITER != 0
+    }
+
+    #[inline]
+    pub fn upgradable(self) -> bool {
+        self.0 & UPGRADABLE != 0
+    }
+
+    #[inline]
+    pub fn contenders(self) -> u32 {
+        (self.0 >> CONTENDER.trailing_zeros()) as u32
+    }
+
+    /// The part of a [`LockState`] that is captured by the word, with the contenders
+    /// counted as the waiters.
+    #[inline]
+    pub fn inspect(self) -> LockState {
+        LockState {
+            readers: self.readers(),
+            writer: self.writer(),
+            upgradable: self.upgradable(),
+            waiting: self.contenders(),
+            ..LockState::default()
+        }
+    }
+}
+
+impl Debug for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("readers", &self.readers())
+            .field("writer", &self.writer())
+            .field("upgradable", &self.upgradable())
+            .field("contenders", &self.contenders())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests;
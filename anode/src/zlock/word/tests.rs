@@ -0,0 +1,85 @@
+use crate::zlock::word::{Enlistment, LockWord, READER, UPGRADABLE, WRITER};
+
+#[test]
+fn fast_read_cycle() {
+    let word = LockWord::new();
+    assert!(word.try_fast_read());
+    assert!(word.try_fast_read());
+    assert_eq!(2, word.load().readers());
+
+    // readers exclude a fast writer
+    assert!(!word.try_fast_write());
+
+    assert!(word.try_fast_read_unlock());
+    assert!(word.try_fast_read_unlock());
+    assert_eq!(0, word.load().readers());
+}
+
+#[test]
+fn fast_write_cycle() {
+    let word = LockWord::new();
+    assert!(word.try_fast_write());
+    assert!(word.load().writer());
+
+    // a writer excludes fast readers and writers
+    assert!(!word.try_fast_read());
+    assert!(!word.try_fast_write());
+
+    assert!(word.try_fast_write_unlock());
+    assert!(!word.load().writer());
+}
+
+#[test]
+fn contender_disables_fast_path() {
+    let word = LockWord::new();
+    assert!(word.try_fast_read());
+
+    let mut enlistment = Enlistment::default();
+    enlistment.enlist(&word);
+    enlistment.enlist(&word);
+    assert_eq!(1, word.load().contenders());
+
+    // neither acquisitions nor releases may bypass the contender
+    assert!(!word.try_fast_read());
+    assert!(!word.try_fast_write());
+    assert!(!word.try_fast_read_unlock());
+
+    let state = enlistment.acquire(&word, 0, READER);
+    assert_eq!(2, state.readers());
+    assert_eq!(0, state.contenders());
+    assert!(word.try_fast_read_unlock());
+    assert!(word.try_fast_read_unlock());
+
+    enlistment.enlist(&word);
+    enlistment.withdraw(&word);
+    enlistment.withdraw(&word);
+    assert_eq!(0, word.load().contenders());
+    assert!(word.try_fast_write());
+    assert!(word.try_fast_write_unlock());
+}
+
+#[test]
+fn shift() {
+    let word = LockWord::new();
+    assert!(word.try_fast_write());
+
+    let state = word.shift(WRITER, UPGRADABLE);
+    assert!(!state.writer());
+    assert!(state.upgradable());
+
+    // an upgradable reader coexists with fast readers, but not with fast writers
+    assert!(word.try_fast_read());
+    assert!(!word.try_fast_write());
+
+    let state = word.shift(UPGRADABLE, READER);
+    assert_eq!(2, state.readers());
+    assert!(!state.upgradable());
+    assert_eq!(state, word.load());
+}
+
+#[test]
+fn debug() {
+    let word = LockWord::new();
+    assert!(word.try_fast_read());
+    assert_eq!("Snapshot { readers: 1, writer: false, upgradable: false, contenders: 0 }", format!("{:?}", word));
+}
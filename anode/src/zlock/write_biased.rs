@@ -1,70 +1,187 @@
-use std::time::Duration;
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+use crate::backoff::ExpBackoff;
 use crate::deadline::Deadline;
-use crate::monitor::{Directive, Monitor, SpeculativeMonitor};
-use crate::zlock::{Moderator};
+use crate::monitor::{Directive, Monitor, Notification, SpeculativeMonitor};
+use crate::zlock::{AsyncModerator, LockState, Moderator, ModeratorMonitor, ThreadAgnostic, UpgradableModerator, Waiter, READ_QUEUE, WRITE_QUEUE};
+use crate::zlock::word::{Enlistment, LockWord, READER, UPGRADABLE, WRITER};
 
+/// Gives precedence to writers: readers that arrive while a writer is pending are held
+/// back until the writer has been admitted.
+///
+/// By default, a continuous stream of writers may starve readers indefinitely. A
+/// [`WriteBiasedConfig`] (applied with [`ZLock::with_config`](crate::zlock::ZLock::with_config))
+/// bounds the run of writers that may be admitted while readers are waiting, after which
+/// the waiting readers are admitted as a batch.
 #[derive(Debug)]
 pub struct WriteBiased;
 
+/// Tunes a [`WriteBiased`] moderator.
+///
+/// The bounds limit the run of writers that may be admitted ahead of waiting readers.
+/// When either bound is reached, the readers that are waiting at the time are admitted as
+/// a batch, before any further writers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteBiasedConfig {
+    /// The number of consecutive writers admitted while readers are waiting, or `None` if
+    /// unbounded.
+    pub max_consecutive_writes: Option<u32>,
+
+    /// The time elapsed since the first of the consecutive writers was admitted while
+    /// readers were waiting, or `None` if unbounded.
+    pub max_write_slice: Option<Duration>,
+
+    /// The backoff policy of the monitor's tracker.
+    pub backoff: &'static ExpBackoff,
+}
+
+impl WriteBiasedConfig {
+    /// Writers are admitted ahead of readers without bound. This is the default.
+    pub const UNBOUNDED: Self = Self {
+        max_consecutive_writes: None,
+        max_write_slice: None,
+        backoff: &ExpBackoff::SLEEPY,
+    };
+}
+
+impl Default for WriteBiasedConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::UNBOUNDED
+    }
+}
+
 pub struct WriteBiasedSync {
-    monitor: SpeculativeMonitor<WriteBiasedState>,
+    word: LockWord,
+    monitor: ModeratorMonitor<WriteBiasedState>,
+}
+
+impl WriteBiasedSync {
+    const fn new(config: WriteBiasedConfig) -> Self {
+        Self {
+            word: LockWord::new(),
+            monitor: SpeculativeMonitor::with_queues(WriteBiasedState::new(config), config.backoff),
+        }
+    }
 }
 
 #[derive(Debug)]
 struct WriteBiasedState {
-    readers: u32,
-    writer: bool,
     writer_pending: bool,
+    config: WriteBiasedConfig,
+
+    /// The number of readers waiting for the lock.
+    waiting_readers: u32,
+
+    /// The number of writers admitted while readers were waiting, since a waiting reader
+    /// was last admitted.
+    consecutive_writes: u32,
+
+    /// When the first of the consecutive writers was admitted.
+    slice_start: Option<Instant>,
+
+    /// Set when the writers have exhausted their slice, admitting the waiting readers
+    /// regardless of pending writers, and holding back writers until no readers are
+    /// waiting.
+    readers_turn: bool,
+}
+
+impl WriteBiasedState {
+    const fn new(config: WriteBiasedConfig) -> Self {
+        Self {
+            writer_pending: false,
+            config,
+            waiting_readers: 0,
+            consecutive_writes: 0,
+            slice_start: None,
+            readers_turn: false,
+        }
+    }
+
+    #[inline]
+    fn write_acquired(&mut self) {
+        if self.waiting_readers > 0 {
+            self.consecutive_writes += 1;
+            self.slice_start.get_or_insert_with(Instant::now);
+        }
+    }
+
+    /// Gives the waiting readers a turn if the writers have exhausted their slice. Called
+    /// when a write lock is released or downgraded.
+    #[inline]
+    fn write_released(&mut self) {
+        if self.waiting_readers > 0 && self.slice_exhausted() {
+            self.readers_turn = true;
+        }
+    }
+
+    #[inline]
+    fn slice_exhausted(&self) -> bool {
+        self.config.max_consecutive_writes.is_some_and(|max| self.consecutive_writes >= max)
+            || self.config.max_write_slice.is_some_and(|max| self.slice_start.is_some_and(|start| start.elapsed() >= max))
+    }
+
+    #[inline]
+    fn reader_waiting(&mut self) {
+        self.waiting_readers += 1;
+    }
+
+    /// Accounts for a waiting reader that has either acquired the lock or given up on it.
+    /// The readers' turn ends once there are no more waiting readers.
+    #[inline]
+    fn reader_done_waiting(&mut self, acquired: bool) {
+        self.waiting_readers -= 1;
+        if acquired {
+            self.consecutive_writes = 0;
+            self.slice_start = None;
+        }
+        if self.waiting_readers == 0 {
+            self.readers_turn = false;
+        }
+    }
 }
 
 impl Moderator for WriteBiased {
     type Sync = WriteBiasedSync;
+    type ModeratorConfig = WriteBiasedConfig;
+
+    const INIT: Self::Sync = WriteBiasedSync::new(WriteBiasedConfig::UNBOUNDED);
 
     #[inline]
-    fn new() -> Self::Sync {
-        Self::Sync {
-            monitor: SpeculativeMonitor::new(WriteBiasedState { readers: 0, writer: false, writer_pending: false }),
-        }
+    fn with_config(config: Self::ModeratorConfig) -> Self::Sync {
+        WriteBiasedSync::new(config)
     }
 
     #[inline]
     fn try_read(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        let mut saw_no_pending_writer = false;
-        sync.monitor.enter(|state| {
-            if !state.writer_pending {
-                saw_no_pending_writer = true;
-            }
+        Self::try_read_until(sync, Deadline::lazy_after(duration))
+    }
 
-            if !acquired && !state.writer && saw_no_pending_writer {
-                acquired = true;
-                state.readers += 1;
-            }
+    #[inline]
+    fn try_read_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_read() {
+            return true;
+        }
 
-            if acquired {
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
-            }
-        });
-        acquired
+        let mut waiter = ReadWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
     }
 
     #[inline]
     fn read_unlock(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers > 0, "readers: {}", state.readers);
-                debug_assert!(!state.writer);
+        if sync.word.try_fast_read_unlock() {
+            return;
+        }
 
-                released = true;
-                state.readers -= 1;
-            }
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().readers() > 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(!sync.word.load().writer());
 
-            match state.readers {
-                0 | 1 => Directive::NotifyAll,
+            // readers are never held back by other readers
+            match sync.word.shift(READER, 0).readers() {
+                0 | 1 => Directive::NotifyAndReturn(Notification::all(WRITE_QUEUE)),
                 _ => Directive::Return
             }
         });
@@ -72,120 +189,397 @@ impl Moderator for WriteBiased {
 
     #[inline]
     fn try_write(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        let mut self_writer_pending = false;
-        sync.monitor.enter(|state| {
-            if !acquired {
-                if state.readers == 0 && !state.writer {
-                    state.writer = true;
-                    acquired = true;
-                } else if !state.writer_pending {
-                    self_writer_pending = true;
-                    state.writer_pending = true;
-                }
-            }
-
-            if acquired {
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
-            }
-        });
-
-        if self_writer_pending {
-            let mut cleared_writer_pending = false;
-            sync.monitor.enter(|state| {
-                if !cleared_writer_pending {
-                    cleared_writer_pending = true;
-                    state.writer_pending = false;
-                }
+        Self::try_write_until(sync, Deadline::lazy_after(duration))
+    }
 
-                if acquired {
-                    Directive::Return
-                } else {
-                    Directive::NotifyAll
-                }
-            });
+    #[inline]
+    fn try_write_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        if sync.word.try_fast_write() {
+            return true;
         }
 
-        acquired
+        let mut waiter = WriteWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
     }
 
     #[inline]
     fn write_unlock(sync: &Self::Sync) {
-        let mut released = false;
-        sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers == 0, "readers: {}", state.readers);
-                debug_assert!(state.writer);
+        if sync.word.try_fast_write_unlock() {
+            return;
+        }
 
-                released = true;
-                state.writer = false;
-            }
+        sync.monitor.enter(|state| {
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
 
-            Directive::NotifyAll
+            sync.word.shift(WRITER, 0);
+            state.write_released();
+            Directive::NotifyAllAndReturn
         });
     }
 
     fn downgrade(sync: &Self::Sync) {
-        let mut released = false;
         sync.monitor.enter(|state| {
-            if !released {
-                debug_assert!(state.readers == 0, "readers: {}", state.readers);
-                debug_assert!(state.writer);
-
-                released = true;
-                state.writer = false;
-                state.readers = 1;
-            }
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
 
-            Directive::NotifyAll
+            sync.word.shift(WRITER, READER);
+            state.write_released();
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
         });
     }
 
     fn try_upgrade(sync: &Self::Sync, duration: Duration) -> bool {
-        let mut deadline = Deadline::lazy_after(duration);
-        let mut acquired = false;
-        let mut self_writer_pending = false;
+        Self::try_upgrade_until(sync, Deadline::lazy_after(duration))
+    }
+
+    fn try_upgrade_until(sync: &Self::Sync, deadline: Deadline) -> bool {
+        let mut waiter = UpgradeWaiter::new(deadline);
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn inspect(sync: &Self::Sync) -> LockState {
+        sync.monitor.compute(|state| LockState {
+            writer_pending: state.writer_pending,
+            ..sync.word.load().inspect()
+        })
+    }
+}
+
+unsafe impl ThreadAgnostic for WriteBiased {}
+
+impl UpgradableModerator for WriteBiased {
+    #[inline]
+    fn try_read_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = ReadWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    #[inline]
+    fn upgradable_unlock(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, 0);
+            Directive::NotifyAllAndReturn
+        });
+    }
+
+    fn try_upgrade_upgradable(sync: &Self::Sync, duration: Duration) -> bool {
+        let mut waiter = UpgradeWaiter::upgradable(Deadline::lazy_after(duration));
+        sync.monitor.enter(|state| waiter.step(&sync.word, state));
+        waiter.conclude(sync);
+        waiter.acquired
+    }
+
+    fn downgrade_upgradable(sync: &Self::Sync) {
+        sync.monitor.enter(|_| {
+            debug_assert!(sync.word.load().upgradable());
+            debug_assert!(!sync.word.load().writer());
+
+            sync.word.shift(UPGRADABLE, READER);
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+
+    fn downgrade_to_upgradable(sync: &Self::Sync) {
         sync.monitor.enter(|state| {
-            if !acquired {
-                debug_assert!(!state.writer);
-
-                if state.readers == 1 {
-                    acquired = true;
-                    state.readers = 0;
-                    state.writer = true;
-                } else if !state.writer_pending {
-                    self_writer_pending = true;
-                    state.writer_pending = true;
+            debug_assert!(sync.word.load().readers() == 0, "readers: {}", sync.word.load().readers());
+            debug_assert!(sync.word.load().writer());
+
+            sync.word.shift(WRITER, UPGRADABLE);
+            state.write_released();
+            Directive::NotifyAndReturn(Notification::all(READ_QUEUE))
+        });
+    }
+}
+
+impl AsyncModerator for WriteBiased {
+    type ReadWaiter = ReadWaiter;
+    type WriteWaiter = WriteWaiter;
+    type UpgradeWaiter = UpgradeWaiter;
+
+    #[inline]
+    fn forget(sync: &Self::Sync, waker: &Waker) {
+        sync.monitor.forget(waker);
+    }
+}
+
+/// Withdraws a reader that gave up waiting, notifying the writers that may have been held
+/// back by the readers' turn.
+#[inline]
+fn withdraw_reader(sync: &WriteBiasedSync) {
+    sync.monitor.enter(|state| {
+        state.reader_done_waiting(false);
+        Directive::NotifyAndReturn(Notification::all(WRITE_QUEUE))
+    });
+}
+
+/// Clears the `writer_pending` flag raised by a writer (or upgrader) that has given up on
+/// the lock, notifying the readers that were held back by the flag. A writer that acquires
+/// the lock clears the flag as it does so.
+#[inline]
+fn clear_writer_pending(sync: &WriteBiasedSync) {
+    sync.monitor.enter(|state| {
+        state.writer_pending = false;
+        Directive::NotifyAllAndReturn
+    });
+}
+
+/// Clears the `writer_pending` flag as a writer (or upgrader) that raised it acquires the
+/// lock, within the same critical section. No notification is needed, as the readers held
+/// back by the flag remain excluded by the lock itself.
+#[inline]
+fn withdraw_writer_pending(self_writer_pending: &mut bool, state: &mut WriteBiasedState) {
+    if *self_writer_pending {
+        *self_writer_pending = false;
+        state.writer_pending = false;
+    }
+}
+
+pub struct ReadWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    upgradable: bool,
+    saw_no_pending_writer: bool,
+    waiting: bool,
+    enlistment: Enlistment,
+}
+
+impl ReadWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, upgradable: false, saw_no_pending_writer: false, waiting: false, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, upgradable: true, saw_no_pending_writer: false, waiting: false, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut WriteBiasedState) -> Directive {
+        if !state.writer_pending {
+            self.saw_no_pending_writer = true;
+        }
+
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let word_state = word.load();
+            let admissible = self.saw_no_pending_writer || state.readers_turn;
+            if !word_state.writer() && admissible && (!self.upgradable || !word_state.upgradable()) {
+                self.acquired = true;
+                if self.waiting {
+                    self.waiting = false;
+                    state.reader_done_waiting(true);
                 }
+                self.enlistment.acquire(word, 0, if self.upgradable { UPGRADABLE } else { READER });
+            } else if !self.waiting {
+                self.waiting = true;
+                state.reader_waiting();
             }
+        }
 
-            if acquired {
-                Directive::Return
-            } else {
-                Directive::Wait(deadline.remaining())
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(READ_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &WriteBiasedSync) {
+        if self.waiting {
+            self.waiting = false;
+            withdraw_reader(sync);
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for ReadWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<WriteBiasedSync> for ReadWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &WriteBiasedSync, cx: &mut Context<'_>) -> Poll<()> {
+        sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx)
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &WriteBiasedSync) {
+        self.conclude(sync);
+    }
+}
+
+pub struct WriteWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    self_writer_pending: bool,
+    enlistment: Enlistment,
+}
+
+impl WriteWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_writer_pending: false, enlistment: Enlistment::default() }
+    }
+
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut WriteBiasedState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let word_state = word.load();
+            if word_state.readers() == 0 && !word_state.writer() && !word_state.upgradable() && !state.readers_turn {
+                self.acquired = true;
+                state.write_acquired();
+                self.enlistment.acquire(word, 0, WRITER);
+                withdraw_writer_pending(&mut self.self_writer_pending, state);
+            } else if !state.writer_pending {
+                self.self_writer_pending = true;
+                state.writer_pending = true;
             }
-        });
+        }
 
-        if self_writer_pending {
-            let mut cleared_writer_pending = false;
-            sync.monitor.enter(|state| {
-                if !cleared_writer_pending {
-                    cleared_writer_pending = true;
-                    state.writer_pending = false;
-                }
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
 
-                if acquired {
-                    Directive::Return
-                } else {
-                    Directive::NotifyAll
-                }
-            });
+    #[inline]
+    fn conclude(&mut self, sync: &WriteBiasedSync) {
+        if self.self_writer_pending {
+            self.self_writer_pending = false;
+            clear_writer_pending(sync);
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for WriteWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<WriteBiasedSync> for WriteWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &WriteBiasedSync, cx: &mut Context<'_>) -> Poll<()> {
+        let poll = sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx);
+        if poll.is_ready() {
+            self.conclude(sync);
+        }
+        poll
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &WriteBiasedSync) {
+        self.conclude(sync);
+    }
+}
+
+pub struct UpgradeWaiter {
+    deadline: Deadline,
+    acquired: bool,
+    self_writer_pending: bool,
+    enlistment: Enlistment,
+
+    /// Whether the lock being upgraded is an upgradable read lock, as opposed to an
+    /// ordinary read lock.
+    upgradable: bool,
+}
+
+impl UpgradeWaiter {
+    #[inline]
+    fn new(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_writer_pending: false, enlistment: Enlistment::default(), upgradable: false }
+    }
+
+    #[inline]
+    fn upgradable(deadline: Deadline) -> Self {
+        Self { deadline, acquired: false, self_writer_pending: false, enlistment: Enlistment::default(), upgradable: true }
+    }
+
+    /// An upgradable reader need only wait for the ordinary readers to drain, whereas an
+    /// ordinary reader must be the sole holder of the lock.
+    #[inline]
+    fn can_acquire(&self, readers: u32, upgradable: bool) -> bool {
+        if self.upgradable {
+            debug_assert!(upgradable);
+            readers == 0
+        } else {
+            readers == 1 && !upgradable
         }
+    }
 
-        acquired
+    #[inline]
+    fn step(&mut self, word: &LockWord, state: &mut WriteBiasedState) -> Directive {
+        if !self.acquired {
+            self.enlistment.enlist(word);
+            let word_state = word.load();
+            debug_assert!(!word_state.writer());
+
+            if self.can_acquire(word_state.readers(), word_state.upgradable()) {
+                self.acquired = true;
+                self.enlistment.acquire(word, if self.upgradable { UPGRADABLE } else { READER }, WRITER);
+                withdraw_writer_pending(&mut self.self_writer_pending, state);
+            } else if !state.writer_pending {
+                self.self_writer_pending = true;
+                state.writer_pending = true;
+            }
+        }
+
+        if self.acquired {
+            Directive::Return
+        } else {
+            Directive::WaitOn(WRITE_QUEUE, self.deadline.remaining())
+        }
+    }
+
+    #[inline]
+    fn conclude(&mut self, sync: &WriteBiasedSync) {
+        if self.self_writer_pending {
+            self.self_writer_pending = false;
+            clear_writer_pending(sync);
+        }
+        self.enlistment.withdraw(&sync.word);
+    }
+}
+
+impl Default for UpgradeWaiter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Deadline::Forever)
+    }
+}
+
+impl Waiter<WriteBiasedSync> for UpgradeWaiter {
+    #[inline]
+    fn poll_acquire(&mut self, sync: &WriteBiasedSync, cx: &mut Context<'_>) -> Poll<()> {
+        let poll = sync.monitor.poll_enter(|state| self.step(&sync.word, state), cx);
+        if poll.is_ready() {
+            self.conclude(sync);
+        }
+        poll
+    }
+
+    #[inline]
+    fn cancel(&mut self, sync: &WriteBiasedSync) {
+        self.conclude(sync);
     }
 }
 
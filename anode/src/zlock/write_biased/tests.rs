@@ -1,13 +1,14 @@
 use std::cmp::Ordering;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::Duration;
 use test_utils::SHORT_WAIT;
 use crate::executor::{Executor, Queue, Submitter, ThreadPool};
 use crate::{test_utils, wait};
 use crate::monitor::{Monitor};
-use crate::test_utils::LONG_WAIT;
+use crate::test_utils::{CHECK_WAIT, LONG_WAIT};
 use crate::wait::{Wait, WaitResult};
-use crate::zlock::{WriteBiased, ZLock};
+use crate::zlock::{WriteBiased, WriteBiasedConfig, ZLock};
 
 #[test]
 fn timeout_in_write_unblocks_readers() {
@@ -117,11 +118,110 @@ fn await_pending_writer_timeout() {
     drop(guard_1);
 }
 
+#[test]
+fn consecutive_writes_admit_waiting_readers() {
+    let config = WriteBiasedConfig { max_consecutive_writes: Some(1), ..WriteBiasedConfig::default() };
+    let order = __admit_waiting_readers(config, Duration::ZERO);
+    assert_eq!(vec!["write", "read", "write"], order);
+}
+
+#[test]
+fn write_slice_admits_waiting_readers() {
+    let config = WriteBiasedConfig { max_write_slice: Some(SHORT_WAIT), ..WriteBiasedConfig::default() };
+    let order = __admit_waiting_readers(config, CHECK_WAIT);
+    assert_eq!(vec!["write", "read", "write"], order);
+}
+
+/// Queues a reader between two writers, while the lock is read-held by the main thread.
+/// Once the read lock is released, the writers are admitted ahead of the reader, unless
+/// the first of them exhausts the writers' slice. Returns the order of admission.
+fn __admit_waiting_readers(config: WriteBiasedConfig, write_hold: Duration) -> Vec<&'static str> {
+    let lock = Arc::new(ZLock::<_, WriteBiased>::with_config(0, config));
+    let order = Arc::new(Mutex::new(vec![]));
+    let guard_1 = lock.read();
+
+    let write = |pool: &ThreadPool| {
+        let (lock, order) = (lock.clone(), order.clone());
+        pool.submitter().submit(move || {
+            let guard = lock.write();
+            order.lock().unwrap().push("write");
+            thread::sleep(write_hold);
+            drop(guard);
+        })
+    };
+
+    let t_2 = ThreadPool::new(1, Queue::Unbounded);
+    let t_2_write = write(&t_2);
+    lock.wait_for_writer_pending_flag(true, LONG_WAIT).unwrap();
+
+    let t_3 = ThreadPool::new(1, Queue::Unbounded);
+    let t_3_read = {
+        let (lock, order) = (lock.clone(), order.clone());
+        t_3.submitter().submit(move || {
+            let guard = lock.read();
+            order.lock().unwrap().push("read");
+            drop(guard);
+        })
+    };
+    lock.wait_for_waiting_readers(1, LONG_WAIT).unwrap();
+
+    let t_4 = ThreadPool::new(1, Queue::Unbounded);
+    let t_4_write = write(&t_4);
+
+    drop(guard_1);
+    assert!(t_2_write.get().is_success());
+    assert!(t_3_read.get().is_success());
+    assert!(t_4_write.get().is_success());
+    assert_eq!(0, lock.waiting_readers());
+    assert!(!lock.is_readers_turn());
+
+    let order = order.lock().unwrap().clone();
+    order
+}
+
+#[test]
+fn timeout_in_read_withdraws_reader() {
+    let config = WriteBiasedConfig { max_consecutive_writes: Some(0), ..WriteBiasedConfig::default() };
+    let lock = Arc::new(ZLock::<_, WriteBiased>::with_config(0, config));
+    let guard_1 = lock.read();
+
+    let t_2 = ThreadPool::new(1, Queue::Unbounded);
+    let t_2_write = {
+        let lock = lock.clone();
+        t_2.submitter().submit(move || {
+            drop(lock.write());
+        })
+    };
+    lock.wait_for_writer_pending_flag(true, LONG_WAIT).unwrap();
+
+    // the reader is held back by the pending writer
+    assert!(lock.try_read(SHORT_WAIT).is_none());
+    assert_eq!(0, lock.waiting_readers());
+
+    // with no readers waiting, the writer does not cede its turn
+    drop(guard_1);
+    assert!(t_2_write.get().is_success());
+    assert!(!lock.is_readers_turn());
+    assert!(lock.try_write(Duration::ZERO).is_some());
+}
+
 impl<T> ZLock<T, WriteBiased> {
     fn is_writer_pending(&self) -> bool {
         self.sync.monitor.compute(|state| state.writer_pending)
     }
 
+    fn waiting_readers(&self) -> u32 {
+        self.sync.monitor.compute(|state| state.waiting_readers)
+    }
+
+    fn is_readers_turn(&self) -> bool {
+        self.sync.monitor.compute(|state| state.readers_turn)
+    }
+
+    fn wait_for_waiting_readers(&self, target: u32, duration: Duration) -> WaitResult {
+        wait::Spin::wait_for_inequality(|| self.waiting_readers(), Ordering::is_eq, &target, duration)
+    }
+
     fn wait_for_writer_pending_flag(&self, target: bool, duration: Duration) -> WaitResult {
         wait::Spin::wait_for_inequality(|| self.is_writer_pending(), Ordering::is_eq, &target, duration)
     }